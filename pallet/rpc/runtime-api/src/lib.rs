@@ -0,0 +1,58 @@
+//! Runtime API definition for the AMM pallet.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use codec::Codec;
+use sp_runtime::{DispatchError, FixedU128, Permill};
+use sp_std::vec::Vec;
+
+sp_api::decl_runtime_apis! {
+	pub trait AmmApi<AccountId, CurrencyId, Balance, Moment>
+	where
+		AccountId: Codec,
+		CurrencyId: Codec,
+		Balance: Codec,
+		Moment: Codec,
+	{
+		/// The reserves of the pool and the time they were last updated.
+		/// Returns `None` if the pool does not exist.
+		fn get_reserves(pool: (CurrencyId, CurrencyId)) -> Option<(Balance, Balance, Moment)>;
+
+		/// The amount received for swapping exactly `amount_in` of `asset_in`.
+		fn quote_exact_in(
+			pool: (CurrencyId, CurrencyId),
+			asset_in: CurrencyId,
+			amount_in: Balance,
+		) -> Result<Balance, DispatchError>;
+
+		/// The amount of `asset_in` needed to receive exactly `amount_out` of the other asset.
+		fn quote_exact_out(
+			pool: (CurrencyId, CurrencyId),
+			asset_in: CurrencyId,
+			amount_out: Balance,
+		) -> Result<Balance, DispatchError>;
+
+		/// The amount of `asset_in` paid per unit of the other asset on the curve of the pool,
+		/// without the swap fee.
+		fn spot_price(
+			pool: (CurrencyId, CurrencyId),
+			asset_in: CurrencyId,
+		) -> Result<FixedU128, DispatchError>;
+
+		fn lp_balance(pool: (CurrencyId, CurrencyId), who: AccountId) -> Balance;
+
+		fn total_supply(pool: (CurrencyId, CurrencyId)) -> Balance;
+
+		/// The cumulative prices of the first and the second asset of the pool.
+		fn price_cumulative(pool: (CurrencyId, CurrencyId)) -> (FixedU128, FixedU128);
+
+		/// The average prices of the first and the second asset over at least the last `window`.
+		fn consult(
+			pool: (CurrencyId, CurrencyId),
+			window: Moment,
+		) -> Result<(FixedU128, FixedU128), DispatchError>;
+
+		/// All pools with their reserves and swap fee.
+		fn pools() -> Vec<((CurrencyId, CurrencyId), Balance, Balance, Permill)>;
+	}
+}
@@ -0,0 +1,304 @@
+//! RPC interface for the AMM pallet.
+
+use std::{fmt::Debug, sync::Arc};
+
+use codec::Codec;
+use jsonrpc_core::{Error as RpcError, ErrorCode, Result};
+use jsonrpc_derive::rpc;
+use sp_api::ProvideRuntimeApi;
+use sp_blockchain::HeaderBackend;
+use sp_runtime::{
+	generic::BlockId,
+	traits::{AtLeast32BitUnsigned, Block as BlockT},
+	FixedPointNumber, FixedU128,
+};
+
+pub use pallet_pendulum_amm_rpc_runtime_api::AmmApi as AmmRuntimeApi;
+
+mod route;
+pub use route::{Route, MAX_HOPS};
+
+#[rpc]
+pub trait AmmApi<BlockHash, AccountId, CurrencyId, Balance, Moment> {
+	#[rpc(name = "amm_getReserves")]
+	fn get_reserves(
+		&self,
+		pool: (CurrencyId, CurrencyId),
+		at: Option<BlockHash>,
+	) -> Result<Option<(Balance, Balance, Moment)>>;
+
+	#[rpc(name = "amm_quoteExactIn")]
+	fn quote_exact_in(
+		&self,
+		pool: (CurrencyId, CurrencyId),
+		asset_in: CurrencyId,
+		amount_in: Balance,
+		at: Option<BlockHash>,
+	) -> Result<Balance>;
+
+	#[rpc(name = "amm_quoteExactOut")]
+	fn quote_exact_out(
+		&self,
+		pool: (CurrencyId, CurrencyId),
+		asset_in: CurrencyId,
+		amount_out: Balance,
+		at: Option<BlockHash>,
+	) -> Result<Balance>;
+
+	#[rpc(name = "amm_lpBalance")]
+	fn lp_balance(
+		&self,
+		pool: (CurrencyId, CurrencyId),
+		who: AccountId,
+		at: Option<BlockHash>,
+	) -> Result<Balance>;
+
+	#[rpc(name = "amm_totalSupply")]
+	fn total_supply(
+		&self,
+		pool: (CurrencyId, CurrencyId),
+		at: Option<BlockHash>,
+	) -> Result<Balance>;
+
+	/// The cumulative prices as fixed-point numbers with 18 decimals, given by their inner value.
+	#[rpc(name = "amm_priceCumulative")]
+	fn price_cumulative(
+		&self,
+		pool: (CurrencyId, CurrencyId),
+		at: Option<BlockHash>,
+	) -> Result<(u128, u128)>;
+
+	/// The average prices over at least the last `window`, given like `amm_priceCumulative`.
+	#[rpc(name = "amm_consult")]
+	fn consult(
+		&self,
+		pool: (CurrencyId, CurrencyId),
+		window: Moment,
+		at: Option<BlockHash>,
+	) -> Result<(u128, u128)>;
+
+	/// The paths from `from` to `to` through at most `max_hops` pools, best output first.
+	/// `max_hops` is capped at `MAX_HOPS`.
+	#[rpc(name = "amm_findRoute")]
+	fn find_route(
+		&self,
+		from: CurrencyId,
+		to: CurrencyId,
+		amount_in: Balance,
+		max_hops: u32,
+		at: Option<BlockHash>,
+	) -> Result<Vec<Route<CurrencyId, Balance>>>;
+}
+
+/// Provides RPC methods to query the pools of the AMM pallet.
+pub struct Amm<C, Block> {
+	client: Arc<C>,
+	_marker: std::marker::PhantomData<Block>,
+}
+
+impl<C, Block> Amm<C, Block> {
+	/// Creates a new instance of the AMM RPC helper.
+	pub fn new(client: Arc<C>) -> Self {
+		Self { client, _marker: Default::default() }
+	}
+}
+
+/// Error type of this RPC api.
+pub enum Error {
+	/// The call to the runtime failed.
+	RuntimeError,
+	/// The quote could not be computed, e.g. because the pool has no liquidity.
+	QuoteError,
+	/// The average price could not be computed, e.g. because the window is too long.
+	OracleError,
+}
+
+impl From<Error> for i64 {
+	fn from(e: Error) -> i64 {
+		match e {
+			Error::RuntimeError => 1,
+			Error::QuoteError => 2,
+			Error::OracleError => 3,
+		}
+	}
+}
+
+fn runtime_error(e: impl Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(Error::RuntimeError.into()),
+		message: "Unable to query the AMM runtime api.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+fn quote_error(e: impl Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(Error::QuoteError.into()),
+		message: "Unable to compute the quote.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+fn oracle_error(e: impl Debug) -> RpcError {
+	RpcError {
+		code: ErrorCode::ServerError(Error::OracleError.into()),
+		message: "Unable to compute the average price.".into(),
+		data: Some(format!("{:?}", e).into()),
+	}
+}
+
+impl<C, Block, AccountId, CurrencyId, Balance, Moment>
+	AmmApi<<Block as BlockT>::Hash, AccountId, CurrencyId, Balance, Moment> for Amm<C, Block>
+where
+	Block: BlockT,
+	C: Send + Sync + 'static + ProvideRuntimeApi<Block> + HeaderBackend<Block>,
+	C::Api: AmmRuntimeApi<Block, AccountId, CurrencyId, Balance, Moment>,
+	AccountId: Codec,
+	CurrencyId: Codec + Clone + PartialEq,
+	Balance: Codec + AtLeast32BitUnsigned + Copy,
+	Moment: Codec,
+{
+	fn get_reserves(
+		&self,
+		pool: (CurrencyId, CurrencyId),
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Option<(Balance, Balance, Moment)>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.get_reserves(&at, pool).map_err(runtime_error)
+	}
+
+	fn quote_exact_in(
+		&self,
+		pool: (CurrencyId, CurrencyId),
+		asset_in: CurrencyId,
+		amount_in: Balance,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Balance> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.quote_exact_in(&at, pool, asset_in, amount_in)
+			.map_err(runtime_error)?
+			.map_err(quote_error)
+	}
+
+	fn quote_exact_out(
+		&self,
+		pool: (CurrencyId, CurrencyId),
+		asset_in: CurrencyId,
+		amount_out: Balance,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Balance> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.quote_exact_out(&at, pool, asset_in, amount_out)
+			.map_err(runtime_error)?
+			.map_err(quote_error)
+	}
+
+	fn lp_balance(
+		&self,
+		pool: (CurrencyId, CurrencyId),
+		who: AccountId,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Balance> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.lp_balance(&at, pool, who).map_err(runtime_error)
+	}
+
+	fn total_supply(
+		&self,
+		pool: (CurrencyId, CurrencyId),
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Balance> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.total_supply(&at, pool).map_err(runtime_error)
+	}
+
+	fn price_cumulative(
+		&self,
+		pool: (CurrencyId, CurrencyId),
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<(u128, u128)> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.price_cumulative(&at, pool)
+			.map(|(price_0, price_1)| (price_0.into_inner(), price_1.into_inner()))
+			.map_err(runtime_error)
+	}
+
+	fn consult(
+		&self,
+		pool: (CurrencyId, CurrencyId),
+		window: Moment,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<(u128, u128)> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		api.consult(&at, pool, window)
+			.map_err(runtime_error)?
+			.map(|(price_0, price_1)| (price_0.into_inner(), price_1.into_inner()))
+			.map_err(oracle_error)
+	}
+
+	fn find_route(
+		&self,
+		from: CurrencyId,
+		to: CurrencyId,
+		amount_in: Balance,
+		max_hops: u32,
+		at: Option<<Block as BlockT>::Hash>,
+	) -> Result<Vec<Route<CurrencyId, Balance>>> {
+		let api = self.client.runtime_api();
+		let at = BlockId::hash(at.unwrap_or_else(|| self.client.info().best_hash));
+
+		let pools = api.pools(&at).map_err(runtime_error)?;
+		let quoter = RuntimeQuoter { api: &*api, at: &at, _marker: Default::default() };
+
+		Ok(route::find_routes(&pools, &quoter, from, to, amount_in, max_hops))
+	}
+}
+
+/// Quotes the hops of a route through the runtime, so that every pool is quoted on its curve.
+struct RuntimeQuoter<'a, Api, Block: BlockT, AccountId, Moment> {
+	api: &'a Api,
+	at: &'a BlockId<Block>,
+	_marker: std::marker::PhantomData<(AccountId, Moment)>,
+}
+
+impl<'a, Api, Block, AccountId, CurrencyId, Balance, Moment> route::Quoter<CurrencyId, Balance>
+	for RuntimeQuoter<'a, Api, Block, AccountId, Moment>
+where
+	Block: BlockT,
+	Api: AmmRuntimeApi<Block, AccountId, CurrencyId, Balance, Moment>,
+	AccountId: Codec,
+	CurrencyId: Codec,
+	Balance: Codec,
+	Moment: Codec,
+{
+	fn quote_exact_in(
+		&self,
+		pool: (CurrencyId, CurrencyId),
+		asset_in: CurrencyId,
+		amount_in: Balance,
+	) -> Option<Balance> {
+		self.api.quote_exact_in(self.at, pool, asset_in, amount_in).ok()?.ok()
+	}
+
+	fn spot_price(
+		&self,
+		pool: (CurrencyId, CurrencyId),
+		asset_in: CurrencyId,
+	) -> Option<FixedU128> {
+		self.api.spot_price(self.at, pool, asset_in).ok()?.ok()
+	}
+}
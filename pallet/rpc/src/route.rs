@@ -0,0 +1,260 @@
+//! Search for the best path of a multi-hop swap.
+
+use serde::{Deserialize, Serialize};
+use sp_runtime::{
+	traits::{AtLeast32BitUnsigned, SaturatedConversion},
+	FixedPointNumber, FixedU128, Permill,
+};
+
+/// Limits the search, which grows exponentially with the number of hops.
+pub const MAX_HOPS: u32 = 4;
+
+/// A pool as returned by the `pools` runtime API: its assets, its reserves and its swap fee.
+pub type PoolInfo<CurrencyId, Balance> = ((CurrencyId, CurrencyId), Balance, Balance, Permill);
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Route<CurrencyId, Balance> {
+	/// The assets to swap through, starting with the input asset.
+	pub path: Vec<CurrencyId>,
+	/// The expected output of swapping along `path`.
+	pub amount_out: Balance,
+	/// How much less `amount_out` is than the output at the current prices, after fees.
+	pub price_impact: Permill,
+}
+
+/// Quotes the hops of a route with the curve and swap fee of each pool, as the pallet would
+/// execute them. A pool which cannot be quoted is left out of the search.
+pub trait Quoter<CurrencyId, Balance> {
+	/// The amount of the other asset of `pool` received for swapping exactly `amount_in`
+	/// of `asset_in`.
+	fn quote_exact_in(
+		&self,
+		pool: (CurrencyId, CurrencyId),
+		asset_in: CurrencyId,
+		amount_in: Balance,
+	) -> Option<Balance>;
+
+	/// The amount of `asset_in` paid per unit of the other asset of `pool`, without the swap fee.
+	fn spot_price(&self, pool: (CurrencyId, CurrencyId), asset_in: CurrencyId)
+		-> Option<FixedU128>;
+}
+
+/// All paths from `from` to `to` through at most `max_hops` of `pools`, without visiting
+/// an asset twice. The path with the highest output comes first.
+pub fn find_routes<CurrencyId, Balance, Q>(
+	pools: &[PoolInfo<CurrencyId, Balance>],
+	quoter: &Q,
+	from: CurrencyId,
+	to: CurrencyId,
+	amount_in: Balance,
+	max_hops: u32,
+) -> Vec<Route<CurrencyId, Balance>>
+where
+	CurrencyId: Clone + PartialEq,
+	Balance: AtLeast32BitUnsigned + Copy,
+	Q: Quoter<CurrencyId, Balance>,
+{
+	let mut search = Search { pools, quoter, to, amount_in, routes: Vec::new() };
+	search.visit(&mut vec![from], amount_in, FixedU128::one(), max_hops.min(MAX_HOPS));
+
+	let mut routes = search.routes;
+	routes.sort_by(|a, b| b.amount_out.cmp(&a.amount_out));
+	routes
+}
+
+struct Search<'a, CurrencyId, Balance, Q> {
+	pools: &'a [PoolInfo<CurrencyId, Balance>],
+	quoter: &'a Q,
+	to: CurrencyId,
+	amount_in: Balance,
+	routes: Vec<Route<CurrencyId, Balance>>,
+}
+
+impl<'a, CurrencyId, Balance, Q> Search<'a, CurrencyId, Balance, Q>
+where
+	CurrencyId: Clone + PartialEq,
+	Balance: AtLeast32BitUnsigned + Copy,
+	Q: Quoter<CurrencyId, Balance>,
+{
+	/// Extends `path`, whose last asset is received as `amount`. `price` is the price of the
+	/// first asset in the last one at the current reserves, after fees.
+	fn visit(
+		&mut self,
+		path: &mut Vec<CurrencyId>,
+		amount: Balance,
+		price: FixedU128,
+		hops_left: u32,
+	) {
+		if hops_left == 0 {
+			return
+		}
+
+		let asset_in = path[path.len() - 1].clone();
+
+		for (pool, reserve_0, reserve_1, fee) in self.pools {
+			let (asset_0, asset_1) = pool;
+			let asset_out = if *asset_0 == asset_in {
+				asset_1
+			} else if *asset_1 == asset_in {
+				asset_0
+			} else {
+				continue
+			};
+
+			if path.contains(asset_out) || reserve_0.is_zero() || reserve_1.is_zero() {
+				continue
+			}
+
+			let amount_out =
+				match self.quoter.quote_exact_in(pool.clone(), asset_in.clone(), amount) {
+					Some(amount_out) if !amount_out.is_zero() => amount_out,
+					_ => continue,
+				};
+
+			// the amount of `asset_out` paid per unit of `asset_in` is the price of `asset_in`
+			let hop_price = match self.quoter.spot_price(pool.clone(), asset_out.clone()) {
+				Some(hop_price) => hop_price,
+				None => continue,
+			};
+
+			let price = price
+				.saturating_mul(hop_price)
+				.saturating_mul(FixedU128::from(fee.left_from_one()));
+
+			path.push(asset_out.clone());
+
+			if *asset_out == self.to {
+				self.routes.push(Route {
+					path: path.clone(),
+					amount_out,
+					price_impact: price_impact(self.amount_in, amount_out, price),
+				});
+			} else {
+				self.visit(path, amount_out, price, hops_left - 1);
+			}
+
+			path.pop();
+		}
+	}
+}
+
+/// The share of the output at `price` that is lost by receiving only `amount_out`.
+fn price_impact<Balance: AtLeast32BitUnsigned + Copy>(
+	amount_in: Balance,
+	amount_out: Balance,
+	price: FixedU128,
+) -> Permill {
+	let expected = price.saturating_mul_int(amount_in.saturated_into::<u128>());
+	let amount_out = amount_out.saturated_into::<u128>();
+
+	if expected == 0 {
+		return Permill::zero()
+	}
+
+	Permill::from_rational(expected.saturating_sub(amount_out), expected)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn pools() -> Vec<PoolInfo<u32, u128>> {
+		let fee = Permill::from_perthousand(3);
+		vec![
+			((0, 1), 100_000, 100_000, fee),
+			((1, 2), 100_000, 100_000, fee),
+			((0, 2), 10_000, 10_000, fee),
+		]
+	}
+
+	/// Quotes `pools` like constant product pools, except for the pools in `one_to_one`,
+	/// which pay out one to one after the fee like a stable pool with a high amplification.
+	struct TestQuoter {
+		pools: Vec<PoolInfo<u32, u128>>,
+		one_to_one: Vec<(u32, u32)>,
+	}
+
+	impl TestQuoter {
+		fn new(one_to_one: Vec<(u32, u32)>) -> Self {
+			Self { pools: pools(), one_to_one }
+		}
+
+		fn directed_reserves(&self, pool: (u32, u32), asset_in: u32) -> Option<(u128, u128)> {
+			let (_, reserve_0, reserve_1, _) = self.pools.iter().find(|info| info.0 == pool)?;
+
+			Some(if asset_in == pool.0 {
+				(*reserve_0, *reserve_1)
+			} else {
+				(*reserve_1, *reserve_0)
+			})
+		}
+	}
+
+	impl Quoter<u32, u128> for TestQuoter {
+		fn quote_exact_in(&self, pool: (u32, u32), asset_in: u32, amount_in: u128) -> Option<u128> {
+			let (reserve_in, reserve_out) = self.directed_reserves(pool, asset_in)?;
+			let amount_in_with_fee = amount_in - Permill::from_perthousand(3).mul_floor(amount_in);
+
+			if self.one_to_one.contains(&pool) {
+				return Some(amount_in_with_fee).filter(|amount_out| *amount_out < reserve_out)
+			}
+
+			Some(amount_in_with_fee * reserve_out / (reserve_in + amount_in_with_fee))
+		}
+
+		fn spot_price(&self, pool: (u32, u32), asset_in: u32) -> Option<FixedU128> {
+			let (reserve_in, reserve_out) = self.directed_reserves(pool, asset_in)?;
+
+			if self.one_to_one.contains(&pool) {
+				return Some(FixedU128::one())
+			}
+
+			FixedU128::checked_from_rational(reserve_in, reserve_out)
+		}
+	}
+
+	#[test]
+	fn find_routes_ranks_paths_by_output() {
+		let routes = find_routes(&pools(), &TestQuoter::new(vec![]), 0, 2, 1_000u128, 2);
+
+		let paths: Vec<(Vec<u32>, u128)> =
+			routes.iter().map(|route| (route.path.clone(), route.amount_out)).collect();
+		// 1000 * 997 * 10_000 / (10_000 * 1000 + 1000 * 997) for the direct path
+		assert_eq!(paths, vec![(vec![0, 1, 2], 975), (vec![0, 2], 906)]);
+
+		assert!(routes[0].price_impact < routes[1].price_impact);
+	}
+
+	#[test]
+	fn find_routes_quotes_each_pool_on_its_curve() {
+		let quoter = TestQuoter::new(vec![(0, 2)]);
+		let routes = find_routes(&pools(), &quoter, 0, 2, 1_000u128, 2);
+
+		// the small pool keeps the price at 1, so it beats the larger ones
+		let paths: Vec<(Vec<u32>, u128)> =
+			routes.iter().map(|route| (route.path.clone(), route.amount_out)).collect();
+		assert_eq!(paths, vec![(vec![0, 2], 997), (vec![0, 1, 2], 975)]);
+		assert_eq!(routes[0].price_impact, Permill::zero());
+	}
+
+	#[test]
+	fn find_routes_skips_hops_that_cannot_be_filled() {
+		let quoter = TestQuoter::new(vec![(0, 2)]);
+		let routes = find_routes(&pools(), &quoter, 0, 2, 20_000u128, 2);
+
+		// the small pool would pay out one to one, but only holds 10_000
+		assert_eq!(routes.len(), 1);
+		assert_eq!(routes[0].path, vec![0, 1, 2]);
+	}
+
+	#[test]
+	fn find_routes_respects_max_hops() {
+		let quoter = TestQuoter::new(vec![]);
+		let routes = find_routes(&pools(), &quoter, 0, 2, 1_000u128, 1);
+
+		assert_eq!(routes.len(), 1);
+		assert_eq!(routes[0].path, vec![0, 2]);
+		assert!(find_routes(&pools(), &quoter, 0, 2, 1_000u128, 0).is_empty());
+	}
+}
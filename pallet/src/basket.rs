@@ -0,0 +1,411 @@
+//! Basket pools of up to `MaxAssetsPerPool` assets with equal weights, like the pools of
+//! Balancer: the invariant is the geometric mean of the reserves, so a swap between any two
+//! assets of a basket is a swap on the constant product of their reserves.
+//!
+//! Unlike pools of two assets, the reserves are only changed by the functions of this module,
+//! so assets sent to the account of a basket are not part of it. The reserves and LP tokens
+//! are updated before any transfer, so a call back into the basket from `T::AmmExtension`
+//! sees the basket as it is after the call.
+
+use crate::{
+	curve::{ConstantProduct, Curve},
+	helper::{
+		_burn, _mint, address_zero, basket_account, bounded, from_u256, mul_div, mul_div_ceil,
+		to_u256, transfer_tokens,
+	},
+	pallet::{
+		AmountsOf, AssetsOf, BasketReserves, BasketSwapFees, Config, Error, Event, LpToken, Pallet,
+	},
+	weighted, LpTokens,
+};
+use frame_support::{ensure, traits::Get};
+use sp_core::U256;
+use sp_runtime::{
+	traits::{Bounded, CheckedAdd, Saturating, Zero},
+	DispatchError, DispatchResult, PerThing,
+};
+use sp_std::{cmp, vec, vec::Vec};
+
+pub(crate) fn _create_basket<T: Config>(assets: AssetsOf<T>) -> Result<AssetsOf<T>, Error<T>> {
+	let mut assets = assets.into_inner();
+	assets.sort();
+
+	ensure!(assets.len() >= 2, Error::<T>::InvalidAssetCount);
+	ensure!(assets.windows(2).all(|pair| pair[0] != pair[1]), Error::<T>::IdenticalAddress);
+
+	let pool = bounded::<T, _>(assets)?;
+	ensure!(!<BasketReserves<T>>::contains_key(&pool), Error::<T>::PairExists);
+
+	let account = basket_account::<T>(&pool)?;
+
+	<BasketReserves<T>>::insert(&pool, bounded::<T, _>(vec![T::Balance::zero(); pool.len()])?);
+
+	<Pallet<T>>::deposit_event(Event::<T>::BasketCreated { pool, account });
+
+	Ok(pool)
+}
+
+/// Deposits at most `max_amounts_in` in the ratio of the reserves and returns the minted
+/// amount of LP tokens. The first deposit takes all of `max_amounts_in`, and its LP tokens are
+/// the geometric mean of the amounts, of which `MinimumLiquidity` are locked.
+pub(crate) fn join<T: Config>(
+	pool: &AssetsOf<T>,
+	who: &T::AccountId,
+	max_amounts_in: &[T::Balance],
+) -> Result<T::Balance, DispatchError> {
+	let zero = T::Balance::zero();
+	let reserves = basket_reserves::<T>(pool)?;
+	ensure!(max_amounts_in.len() == reserves.len(), Error::<T>::InvalidAssetCount);
+
+	let total_supply = <Pallet<T>>::basket_total_supply(pool.clone());
+
+	let (liquidity, amounts) = if total_supply.is_zero() {
+		ensure!(
+			max_amounts_in.iter().all(|amount| *amount > zero),
+			Error::<T>::InsufficientInputAmount
+		);
+
+		let values: Vec<U256> = max_amounts_in.iter().map(|amount| to_u256::<T>(*amount)).collect();
+		let mean = weighted::geometric_mean(&values).ok_or(Error::<T>::Overflow)?;
+		let liquidity = from_u256::<T>(mean)?;
+
+		// permanently lock first liquidity tokens
+		mint_lp::<T>(pool, &address_zero::<T>()?, T::MinimumLiquidity::get())?;
+
+		(liquidity.saturating_sub(T::MinimumLiquidity::get()), max_amounts_in.to_vec())
+	} else {
+		let mut liquidity = T::Balance::max_value();
+		for (amount, reserve) in max_amounts_in.iter().zip(reserves.iter()) {
+			liquidity = cmp::min(liquidity, mul_div::<T>(*amount, total_supply, *reserve)?);
+		}
+
+		let amounts = reserves
+			.iter()
+			.map(|reserve| mul_div_ceil::<T>(*reserve, liquidity, total_supply))
+			.collect::<Result<Vec<_>, _>>()?;
+
+		(liquidity, amounts)
+	};
+
+	ensure!(liquidity > zero, Error::<T>::InsufficientLiquidityMinted);
+
+	let balances = reserves
+		.iter()
+		.zip(amounts.iter())
+		.map(|(reserve, amount)| reserve.checked_add(amount).ok_or(Error::<T>::Overflow))
+		.collect::<Result<Vec<_>, _>>()?;
+
+	<BasketReserves<T>>::insert(pool, bounded::<T, _>(balances)?);
+	mint_lp::<T>(pool, who, liquidity)?;
+
+	let contract = basket_account::<T>(pool)?;
+	for (asset, amount) in pool.iter().zip(amounts.iter()) {
+		transfer_tokens::<T>(who, &contract, *asset, *amount)?;
+	}
+
+	<Pallet<T>>::deposit_event(Event::<T>::Mint {
+		pool: pool.clone(),
+		sender: who.clone(),
+		amounts: bounded::<T, _>(amounts)?,
+	});
+
+	Ok(liquidity)
+}
+
+/// Burns `liquidity` for the same share of every reserve and returns the withdrawn amounts.
+/// Fails if one of them is less than its entry in `min_amounts_out`.
+pub(crate) fn exit<T: Config>(
+	pool: &AssetsOf<T>,
+	who: &T::AccountId,
+	liquidity: T::Balance,
+	min_amounts_out: &[T::Balance],
+) -> Result<Vec<T::Balance>, DispatchError> {
+	let reserves = basket_reserves::<T>(pool)?;
+	ensure!(min_amounts_out.len() == reserves.len(), Error::<T>::InvalidAssetCount);
+
+	let total_supply = <Pallet<T>>::basket_total_supply(pool.clone());
+	ensure!(!total_supply.is_zero(), Error::<T>::WithdrawWithoutSupply);
+
+	let amounts = reserves
+		.iter()
+		.map(|reserve| mul_div::<T>(liquidity, *reserve, total_supply))
+		.collect::<Result<Vec<_>, _>>()?;
+
+	ensure!(
+		amounts.iter().any(|amount| !amount.is_zero()),
+		Error::<T>::InsufficientLiquidityBurned
+	);
+	ensure!(
+		amounts.iter().zip(min_amounts_out).all(|(amount, min)| amount >= min),
+		Error::<T>::SlippageExceeded
+	);
+
+	let balances: Vec<_> = reserves
+		.iter()
+		.zip(amounts.iter())
+		.map(|(reserve, amount)| reserve.saturating_sub(*amount))
+		.collect();
+
+	burn_lp::<T>(pool, who, liquidity)?;
+	<BasketReserves<T>>::insert(pool, bounded::<T, _>(balances)?);
+
+	let contract = basket_account::<T>(pool)?;
+	for (asset, amount) in pool.iter().zip(amounts.iter()) {
+		transfer_tokens::<T>(&contract, who, *asset, *amount)?;
+	}
+
+	<Pallet<T>>::deposit_event(Event::<T>::Burn {
+		pool: pool.clone(),
+		sender: who.clone(),
+		to: who.clone(),
+		amounts: bounded::<T, _>(amounts.clone())?,
+	});
+
+	Ok(amounts)
+}
+
+/// Deposits `amount_in` of a single asset and returns the minted amount of LP tokens.
+/// Depositing a single asset is like swapping the part `1 - 1 / n` of it for the other assets
+/// first, so the swap fee is only taken from that part.
+pub(crate) fn join_single<T: Config>(
+	pool: &AssetsOf<T>,
+	who: &T::AccountId,
+	asset_in: T::CurrencyId,
+	amount_in: T::Balance,
+) -> Result<T::Balance, DispatchError> {
+	let reserves = basket_reserves::<T>(pool)?;
+	let index = asset_index::<T>(pool, asset_in).ok_or(Error::<T>::InvalidDepositToken)?;
+	let amount_out = join_single_amount::<T>(pool, &reserves, index, amount_in)?;
+
+	ensure!(!amount_out.is_zero(), Error::<T>::InsufficientLiquidityMinted);
+
+	let mut balances = reserves.into_inner();
+	balances[index] = balances[index].checked_add(&amount_in).ok_or(Error::<T>::Overflow)?;
+
+	<BasketReserves<T>>::insert(pool, bounded::<T, _>(balances)?);
+	mint_lp::<T>(pool, who, amount_out)?;
+
+	transfer_tokens::<T>(who, &basket_account::<T>(pool)?, asset_in, amount_in)?;
+
+	<Pallet<T>>::deposit_event(Event::<T>::Mint {
+		pool: pool.clone(),
+		sender: who.clone(),
+		amounts: single_amount::<T>(pool.len(), index, amount_in)?,
+	});
+
+	Ok(amount_out)
+}
+
+/// Burns `liquidity` for a single asset and returns the withdrawn amount.
+/// Like `join_single`, the swap fee is only taken from the part `1 - 1 / n` of it.
+pub(crate) fn exit_single<T: Config>(
+	pool: &AssetsOf<T>,
+	who: &T::AccountId,
+	asset_out: T::CurrencyId,
+	liquidity: T::Balance,
+) -> Result<T::Balance, DispatchError> {
+	let reserves = basket_reserves::<T>(pool)?;
+	let index = asset_index::<T>(pool, asset_out).ok_or(Error::<T>::InvalidSwapToken)?;
+	let amount_out = exit_single_amount::<T>(pool, &reserves, index, liquidity)?;
+
+	ensure!(!amount_out.is_zero(), Error::<T>::InsufficientLiquidityBurned);
+
+	let mut balances = reserves.into_inner();
+	balances[index] = balances[index].saturating_sub(amount_out);
+
+	burn_lp::<T>(pool, who, liquidity)?;
+	<BasketReserves<T>>::insert(pool, bounded::<T, _>(balances)?);
+
+	transfer_tokens::<T>(&basket_account::<T>(pool)?, who, asset_out, amount_out)?;
+
+	<Pallet<T>>::deposit_event(Event::<T>::Burn {
+		pool: pool.clone(),
+		sender: who.clone(),
+		to: who.clone(),
+		amounts: single_amount::<T>(pool.len(), index, amount_out)?,
+	});
+
+	Ok(amount_out)
+}
+
+/// Swaps exactly `amount_in` of `asset_in` for `asset_out` and returns the received amount.
+pub(crate) fn swap<T: Config>(
+	pool: &AssetsOf<T>,
+	who: &T::AccountId,
+	asset_in: T::CurrencyId,
+	asset_out: T::CurrencyId,
+	amount_in: T::Balance,
+) -> Result<T::Balance, DispatchError> {
+	let reserves = basket_reserves::<T>(pool)?;
+	let (index_in, index_out) = swap_indices::<T>(pool, asset_in, asset_out)?;
+
+	let amount_out = swap_amount::<T>(pool, &reserves, index_in, index_out, amount_in)?;
+	ensure!(!amount_out.is_zero(), Error::<T>::InsufficientOutputAmount);
+
+	let mut balances = reserves.into_inner();
+	balances[index_in] = balances[index_in].checked_add(&amount_in).ok_or(Error::<T>::Overflow)?;
+	balances[index_out] = balances[index_out].saturating_sub(amount_out);
+
+	<BasketReserves<T>>::insert(pool, bounded::<T, _>(balances)?);
+
+	let contract = basket_account::<T>(pool)?;
+	transfer_tokens::<T>(who, &contract, asset_in, amount_in)?;
+	transfer_tokens::<T>(&contract, who, asset_out, amount_out)?;
+
+	<Pallet<T>>::deposit_event(Event::<T>::Swap {
+		pool: pool.clone(),
+		sender: who.clone(),
+		to: who.clone(),
+		amounts_in: single_amount::<T>(pool.len(), index_in, amount_in)?,
+		amounts_out: single_amount::<T>(pool.len(), index_out, amount_out)?,
+	});
+
+	Ok(amount_out)
+}
+
+/// The amount of `asset_out` received for swapping exactly `amount_in` of `asset_in`.
+pub(crate) fn quote<T: Config>(
+	pool: &AssetsOf<T>,
+	asset_in: T::CurrencyId,
+	asset_out: T::CurrencyId,
+	amount_in: T::Balance,
+) -> Result<T::Balance, Error<T>> {
+	let reserves = basket_reserves::<T>(pool)?;
+	let (index_in, index_out) = swap_indices::<T>(pool, asset_in, asset_out)?;
+
+	swap_amount::<T>(pool, &reserves, index_in, index_out, amount_in)
+}
+
+fn swap_indices<T: Config>(
+	pool: &AssetsOf<T>,
+	asset_in: T::CurrencyId,
+	asset_out: T::CurrencyId,
+) -> Result<(usize, usize), Error<T>> {
+	let index_in = asset_index::<T>(pool, asset_in).ok_or(Error::<T>::InvalidSwapToken)?;
+	let index_out = asset_index::<T>(pool, asset_out).ok_or(Error::<T>::InvalidSwapToken)?;
+	ensure!(index_in != index_out, Error::<T>::IdenticalAddress);
+
+	Ok((index_in, index_out))
+}
+
+/// The amount received for `amount_in` between the reserves at `index_in` and `index_out`,
+/// after the swap fee of the basket.
+fn swap_amount<T: Config>(
+	pool: &AssetsOf<T>,
+	reserves: &[T::Balance],
+	index_in: usize,
+	index_out: usize,
+	amount_in: T::Balance,
+) -> Result<T::Balance, Error<T>> {
+	// with equal weights, the powers of the weighted swap formula are the reserves themselves
+	Curve::<T>::amount_out(
+		&ConstantProduct,
+		amount_in,
+		reserves[index_in],
+		reserves[index_out],
+		<BasketSwapFees<T>>::get(pool),
+	)
+}
+
+/// The LP tokens for depositing `amount_in` into the reserve at `index`:
+/// `supply * ((1 + amount_in / reserve)^(1 / n) - 1)`, with the fee taken from `amount_in`.
+fn join_single_amount<T: Config>(
+	pool: &AssetsOf<T>,
+	reserves: &[T::Balance],
+	index: usize,
+	amount_in: T::Balance,
+) -> Result<T::Balance, Error<T>> {
+	let reserve = reserves[index];
+	let total_supply = <Pallet<T>>::basket_total_supply(pool.clone());
+
+	ensure!(!amount_in.is_zero(), Error::<T>::InsufficientInputAmount);
+	ensure!(!total_supply.is_zero() && !reserve.is_zero(), Error::<T>::InsufficientLiquidity);
+	ensure!(amount_in <= weighted::MAX_SWAP_RATIO.mul_floor(reserve), Error::<T>::SwapTooLarge);
+
+	let amount_in = amount_in.saturating_sub(swapped_fee::<T>(pool, amount_in)?);
+
+	let reserve = to_u256::<T>(reserve);
+	let ratio = weighted::pow_down(
+		reserve.saturating_add(to_u256::<T>(amount_in)),
+		reserve,
+		weighted::ONE / pool.len() as u128,
+	)
+	.ok_or(Error::<T>::Overflow)?;
+
+	let one = U256::from(weighted::ONE);
+	from_u256::<T>(to_u256::<T>(total_supply).saturating_mul(ratio.saturating_sub(one)) / one)
+}
+
+/// The amount of the reserve at `index` for burning `liquidity`:
+/// `reserve * (1 - (1 - liquidity / supply)^n)`, less the fee.
+fn exit_single_amount<T: Config>(
+	pool: &AssetsOf<T>,
+	reserves: &[T::Balance],
+	index: usize,
+	liquidity: T::Balance,
+) -> Result<T::Balance, Error<T>> {
+	let reserve = reserves[index];
+	let total_supply = <Pallet<T>>::basket_total_supply(pool.clone());
+
+	ensure!(!liquidity.is_zero(), Error::<T>::InsufficientLiquidityBurned);
+	ensure!(liquidity < total_supply, Error::<T>::InsufficientLiquidity);
+
+	let total_supply = to_u256::<T>(total_supply);
+	let one = U256::from(weighted::ONE);
+	let ratio = weighted::pow_up(
+		total_supply.saturating_sub(to_u256::<T>(liquidity)),
+		total_supply,
+		weighted::ONE.saturating_mul(pool.len() as u128),
+	)
+	.ok_or(Error::<T>::Overflow)?;
+
+	let amount_out =
+		from_u256::<T>(to_u256::<T>(reserve).saturating_mul(one.saturating_sub(ratio)) / one)?;
+	ensure!(amount_out <= weighted::MAX_SWAP_RATIO.mul_floor(reserve), Error::<T>::SwapTooLarge);
+
+	Ok(amount_out.saturating_sub(swapped_fee::<T>(pool, amount_out)?))
+}
+
+/// The swap fee on the part `1 - 1 / n` of `amount` which is effectively swapped by a deposit
+/// or withdrawal of a single asset, rounded up.
+fn swapped_fee<T: Config>(pool: &AssetsOf<T>, amount: T::Balance) -> Result<T::Balance, Error<T>> {
+	let assets = T::Balance::from(pool.len() as u32);
+	let fee = <BasketSwapFees<T>>::get(pool).mul_ceil(amount);
+
+	mul_div_ceil::<T>(fee, assets.saturating_sub(1u32.into()), assets)
+}
+
+fn basket_reserves<T: Config>(pool: &AssetsOf<T>) -> Result<AmountsOf<T>, Error<T>> {
+	<BasketReserves<T>>::get(pool).ok_or(Error::<T>::BasketNotFound)
+}
+
+fn asset_index<T: Config>(pool: &AssetsOf<T>, asset: T::CurrencyId) -> Option<usize> {
+	pool.binary_search(&asset).ok()
+}
+
+/// `amount` at `index` and zero for the other assets of the pool.
+fn single_amount<T: Config>(
+	assets: usize,
+	index: usize,
+	amount: T::Balance,
+) -> Result<AmountsOf<T>, Error<T>> {
+	let mut amounts = vec![T::Balance::zero(); assets];
+	amounts[index] = amount;
+
+	bounded::<T, _>(amounts)
+}
+
+fn mint_lp<T: Config>(pool: &AssetsOf<T>, to: &T::AccountId, value: T::Balance) -> DispatchResult {
+	_mint::<T>(LpToken::Basket(pool.clone()), to, value)
+}
+
+fn burn_lp<T: Config>(
+	pool: &AssetsOf<T>,
+	from: &T::AccountId,
+	value: T::Balance,
+) -> DispatchResult {
+	let token = LpToken::Basket(pool.clone());
+	ensure!(T::LpTokens::balance(&token, from) >= value, Error::<T>::InsufficientLiquidityBalance);
+
+	_burn::<T>(token, from, value)
+}
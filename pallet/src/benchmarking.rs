@@ -4,12 +4,76 @@ use crate::helper::*;
 
 use crate::Pallet as Amm;
 use frame_benchmarking::{account, benchmarks, whitelisted_caller};
-use frame_support::traits::Get;
+use frame_support::{traits::Get, BoundedVec};
 use frame_system::RawOrigin;
 use pallet_timestamp::Pallet as Timestamp;
-use sp_runtime::traits::One;
+use sp_runtime::{traits::One, Permill};
+use sp_std::vec;
+
+// The benchmarks run against the pool that is created in the genesis config.
+fn genesis_pool<T: Config>() -> PoolIdOf<T> {
+	<Reserves<T>>::iter_keys().next().expect("a pool should be created at genesis")
+}
+
+// A basket of the assets of the genesis pool, with liquidity of `caller`.
+fn joined_basket<T: Config>(caller: &T::AccountId) -> AssetsOf<T> {
+	let (asset_0, asset_1) = genesis_pool::<T>();
+	let pool: AssetsOf<T> = vec![asset_0, asset_1].try_into().unwrap();
+	let amounts: AmountsOf<T> = vec![T::Balance::from(10000u32); 2].try_into().unwrap();
+
+	Amm::<T>::create_basket(RawOrigin::Signed(caller.clone()).into(), pool.clone())
+		.expect("basket should be created");
+	Amm::<T>::join_basket(
+		RawOrigin::Signed(caller.clone()).into(),
+		pool.clone(),
+		amounts,
+		T::Balance::one(),
+		None,
+	)
+	.expect("join should work");
+
+	pool
+}
 
 benchmarks! {
+	create_pool {
+		let caller: T::AccountId = whitelisted_caller();
+		let pool = genesis_pool::<T>();
+		<Reserves<T>>::remove(pool);
+	}: _(RawOrigin::Signed(caller), pool.1, pool.0)
+	verify {
+		assert!(<Reserves<T>>::contains_key(pool));
+	}
+
+	create_stable_pool {
+		let caller: T::AccountId = whitelisted_caller();
+		let pool = genesis_pool::<T>();
+		<Reserves<T>>::remove(pool);
+	}: _(RawOrigin::Signed(caller), pool.1, pool.0, 100)
+	verify {
+		assert!(<Amplifications<T>>::contains_key(pool));
+	}
+
+	ramp_amplification {
+		let caller: T::AccountId = whitelisted_caller();
+		let pool = genesis_pool::<T>();
+		<Reserves<T>>::remove(pool);
+		Amm::<T>::create_stable_pool(RawOrigin::Signed(caller).into(), pool.0, pool.1, 100)?;
+		let future_time = Timestamp::<T>::now() + T::MinRampTime::get();
+	}: _(RawOrigin::Root, pool, 1000, future_time)
+	verify {
+		assert_eq!(<Amplifications<T>>::get(pool).map(|a| a.future), Some(1000));
+	}
+
+	create_weighted_pool {
+		let caller: T::AccountId = whitelisted_caller();
+		let pool = genesis_pool::<T>();
+		<Reserves<T>>::remove(pool);
+	}: _(RawOrigin::Signed(caller), pool.0, pool.1, Permill::from_percent(80))
+	verify {
+		assert_eq!(<Weights<T>>::get(pool), Some(Permill::from_percent(80)));
+	}
+
 	set_fee_to {
 		let alice: T::AccountId = account("Alice",0,0);
 		let caller: T::AccountId = whitelisted_caller();
@@ -21,38 +85,100 @@ benchmarks! {
 		assert_eq!(fee_to, alice);
 	}
 
+	set_fee_to_setter {
+		let alice: T::AccountId = account("Alice",0,0);
+		let caller: T::AccountId = whitelisted_caller();
+
+		<FeeToSetter<T>>::put(caller.clone());
+	}: _(RawOrigin::Signed(caller),alice.clone())
+	verify {
+		assert_eq!(<FeeToSetter<T>>::get(), Some(alice));
+	}
+
+	force_set_fee_to {
+		let alice: T::AccountId = account("Alice",0,0);
+	}: _(RawOrigin::Root, Some(alice.clone()))
+	verify {
+		assert_eq!(<FeeTo<T>>::get(), Some(alice));
+	}
+
+	set_swap_fee {
+		let pool = genesis_pool::<T>();
+		let fee = Permill::from_percent(1);
+	}: _(RawOrigin::Root, pool, fee)
+	verify {
+		assert_eq!(<SwapFees<T>>::get(pool), fee);
+	}
+
+	transfer_lp {
+		let alice: T::AccountId = account("Alice",0,0);
+		let caller: T::AccountId = whitelisted_caller();
+		let token: LpTokenOf<T> = genesis_pool::<T>().into();
+
+		let amount = T::Balance::from(1000u32);
+		T::LpTokens::mint(&token, &caller, amount).expect("mint should work");
+	}: _(RawOrigin::Signed(caller.clone()), token.clone(), alice.clone(), amount)
+	verify {
+		assert_eq!(T::LpTokens::balance(&token, &alice), amount);
+	}
+
+	approve_lp {
+		let alice: T::AccountId = account("Alice",0,0);
+		let caller: T::AccountId = whitelisted_caller();
+		let token: LpTokenOf<T> = genesis_pool::<T>().into();
+
+		let amount = T::Balance::from(1000u32);
+	}: _(RawOrigin::Signed(caller.clone()), token.clone(), alice.clone(), amount)
+	verify {
+		assert_eq!(<Allowances<T>>::get((&token, &caller, &alice)), amount);
+	}
+
+	transfer_lp_from {
+		let alice: T::AccountId = account("Alice",0,0);
+		let caller: T::AccountId = whitelisted_caller();
+		let token: LpTokenOf<T> = genesis_pool::<T>().into();
+
+		let amount = T::Balance::from(1000u32);
+		T::LpTokens::mint(&token, &alice, amount).expect("mint should work");
+		<Allowances<T>>::insert((&token, &alice, &caller), amount);
+	}: _(RawOrigin::Signed(caller.clone()), token.clone(), alice.clone(), caller.clone(), amount)
+	verify {
+		assert_eq!(T::LpTokens::balance(&token, &caller), amount);
+		assert_eq!(<Allowances<T>>::get((&token, &alice, &caller)), T::Balance::zero());
+	}
+
 	skim {
 		let caller: T::AccountId = whitelisted_caller();
-	}: _(RawOrigin::Signed(caller.clone()))
+		let pool = genesis_pool::<T>();
+	}: _(RawOrigin::Signed(caller.clone()), pool)
 	verify {
-		let asset_0 = T::Asset0::get();
+		let (asset_0, asset_1) = pool;
 		let verify_asset_0 = balance_of::<T>(&caller, asset_0);
 		assert_eq!(verify_asset_0, T::Balance::zero());
 
-		let asset_1 = T::Asset1::get();
 		let verify_asset_1 = balance_of::<T>(&caller, asset_1);
 		assert_eq!(verify_asset_1, T::Balance::zero());
 	}
 
 	sync {
 		let caller: T::AccountId = whitelisted_caller();
+		let pool = genesis_pool::<T>();
 		let orig_bal = BalanceReserves::new(10u8.into(),5u8.into(),2u8.into());
-		<Reserves<T>>::put(orig_bal);
+		<Reserves<T>>::insert(pool, orig_bal);
 
 		<Timestamp<T>>::set_timestamp(3u8.into());
 
-		let (r_orig0, r_orig1, time_orig) = reserves::<T>();
+		let (r_orig0, r_orig1, time_orig) = reserves::<T>(pool);
 
-	}: _(RawOrigin::Signed(caller.clone()))
+	}: _(RawOrigin::Signed(caller.clone()), pool)
 	verify {
-		let asset_0 = T::Asset0::get();
-		let asset_1 = T::Asset1::get();
-		let contract = <PalletAccountId<T>>::get().unwrap();
+		let (asset_0, asset_1) = pool;
+		let contract = pool_account::<T>(pool).unwrap();
 
 		let balance_0 = balance_of::<T>(&contract, asset_0);
 		let balance_1 = balance_of::<T>(&contract, asset_1);
 
-		let (r_new0, r_new1, time_new) = reserves::<T>();
+		let (r_new0, r_new1, time_new) = reserves::<T>(pool);
 		assert_ne!(r_new0, r_orig0);
 		assert_eq!(r_new0, balance_0);
 
@@ -63,8 +189,8 @@ benchmarks! {
 	}
 
 	deposit_asset_1{
-		let asset_0 = T::Asset0::get();
-		let asset_1 = T::Asset1::get();
+		let pool = genesis_pool::<T>();
+		let (asset_0, asset_1) = pool;
 
 		let caller: T::AccountId = <FeeToSetter<T>>::get().unwrap();
 
@@ -72,9 +198,9 @@ benchmarks! {
 		let caller_orig1_bal = balance_of::<T>(&caller,asset_1);
 
 		let deposit_bal = T::Balance::from(10000u32);
-	}: _(RawOrigin::Signed(caller.clone()), deposit_bal)
+	}: _(RawOrigin::Signed(caller.clone()), pool, deposit_bal, None)
 	verify {
-		let contract = <PalletAccountId<T>>::get().unwrap();
+		let contract = pool_account::<T>(pool).unwrap();
 		let contract_result = balance_of::<T>(&contract, asset_0);
 		assert_eq!(contract_result, deposit_bal);
 
@@ -95,8 +221,8 @@ benchmarks! {
 	}
 
 	deposit_asset_2{
-		let asset_0 = T::Asset0::get();
-		let asset_1 = T::Asset1::get();
+		let pool = genesis_pool::<T>();
+		let (asset_0, asset_1) = pool;
 
 		let caller: T::AccountId = <FeeToSetter<T>>::get().unwrap();
 
@@ -104,9 +230,41 @@ benchmarks! {
 		let caller_orig1_bal = balance_of::<T>(&caller,asset_1);
 
 		let deposit_bal = T::Balance::from(10000u32);
-	}: _(RawOrigin::Signed(caller.clone()), deposit_bal)
+	}: _(RawOrigin::Signed(caller.clone()), pool, deposit_bal, None)
 	verify {
-		let contract = <PalletAccountId<T>>::get().unwrap();
+		let contract = pool_account::<T>(pool).unwrap();
+		let contract_result = balance_of::<T>(&contract, asset_0);
+		assert_eq!(contract_result, deposit_bal);
+
+		let contract_result = balance_of::<T>(&contract, asset_1);
+		assert_eq!(contract_result, deposit_bal);
+
+		let caller_new0_bal = balance_of::<T>(&caller, asset_0);
+		assert_eq!(
+			caller_new0_bal + deposit_bal,
+			caller_orig0_bal
+		);
+
+		let caller_new1_bal = balance_of::<T>(&caller, asset_1);
+		assert_eq!(
+			caller_new1_bal + deposit_bal,
+			caller_orig1_bal
+		);
+	}
+
+	add_liquidity{
+		let pool = genesis_pool::<T>();
+		let (asset_0, asset_1) = pool;
+
+		let caller: T::AccountId = <FeeToSetter<T>>::get().unwrap();
+
+		let caller_orig0_bal = balance_of::<T>(&caller,asset_0);
+		let caller_orig1_bal = balance_of::<T>(&caller,asset_1);
+
+		let deposit_bal = T::Balance::from(10000u32);
+	}: _(RawOrigin::Signed(caller.clone()), pool, deposit_bal, deposit_bal, deposit_bal, deposit_bal, None)
+	verify {
+		let contract = pool_account::<T>(pool).unwrap();
 		let contract_result = balance_of::<T>(&contract, asset_0);
 		assert_eq!(contract_result, deposit_bal);
 
@@ -127,8 +285,8 @@ benchmarks! {
 	}
 
 	withdraw{
-		let asset_0 = T::Asset0::get();
-		let asset_1 = T::Asset1::get();
+		let pool = genesis_pool::<T>();
+		let (asset_0, asset_1) = pool;
 
 		let caller: T::AccountId = <FeeToSetter<T>>::get().unwrap();
 		let origin = RawOrigin::Signed(caller.clone());
@@ -138,20 +296,20 @@ benchmarks! {
 
 		let deposit_bal = T::Balance::from(10000u32);
 
-		<Amm<T>>::deposit_asset_1(T::Origin::from(origin), deposit_bal).expect("deposit should work");
+		<Amm<T>>::deposit_asset_1(T::Origin::from(origin), pool, deposit_bal, None).expect("deposit should work");
 
 		let caller_orig0_bal = balance_of::<T>(&caller, asset_0);
 		let caller_orig1_bal = balance_of::<T>(&caller, asset_1);
 
-		let contract = <PalletAccountId<T>>::get().unwrap();
+		let contract = pool_account::<T>(pool).unwrap();
 		let contract_orig0_bal = balance_of::<T>(&contract, asset_0);
 		let contract_orig1_bal = balance_of::<T>(&contract, asset_1);
 
-		let (reserve_orig0, reserve_orig1, _) = reserves::<T>();
+		let (reserve_orig0, reserve_orig1, _) = reserves::<T>(pool);
 
 		let withdrawal_bal = contract_orig0_bal - T::MinimumLiquidity::get() - T::Balance::one();
 
-	}: _(RawOrigin::Signed(caller.clone()), withdrawal_bal)
+	}: _(RawOrigin::Signed(caller.clone()), pool, withdrawal_bal, None)
 	verify {
 		let caller_new0_bal = balance_of::<T>(&caller, asset_0);
 		assert_eq!(
@@ -177,35 +335,110 @@ benchmarks! {
 			contract_orig1_bal - withdrawal_bal
 		);
 
-		let k_last = <KLast<T>>::get();
-		assert_eq!(k_last, reserve_orig0 * reserve_orig1);
+		let k_last = <KLast<T>>::get(pool);
+		assert_eq!(k_last, product::<T>(reserve_orig0, reserve_orig1));
 
-		let (reserve_new0, reserve_new1, _) = reserves::<T>();
+		let (reserve_new0, reserve_new1, _) = reserves::<T>(pool);
 		assert_eq!(reserve_new0, reserve_orig0 - withdrawal_bal);
 		assert_eq!(reserve_new1, reserve_orig1 - withdrawal_bal);
 	}
 
-	swap_asset_1_for_asset_2{
+	remove_liquidity{
+		let pool = genesis_pool::<T>();
+		let (asset_0, asset_1) = pool;
+
 		let caller: T::AccountId = <FeeToSetter<T>>::get().unwrap();
 		let origin = RawOrigin::Signed(caller.clone());
+		let recipient: T::AccountId = account("recipient", 0, 0);
 
 		let deposit_bal = T::Balance::from(10000u32);
-		<Amm<T>>::deposit_asset_1(T::Origin::from(origin), deposit_bal).expect("deposit should work");
 
-		let asset_0 = T::Asset0::get();
-		let asset_1 = T::Asset1::get();
+		<Amm<T>>::deposit_asset_1(T::Origin::from(origin), pool, deposit_bal, None).expect("deposit should work");
+
+		let recipient_orig0_bal = balance_of::<T>(&recipient, asset_0);
+		let recipient_orig1_bal = balance_of::<T>(&recipient, asset_1);
+
+		let contract = pool_account::<T>(pool).unwrap();
+		let contract_orig0_bal = balance_of::<T>(&contract, asset_0);
+
+		let withdrawal_bal = contract_orig0_bal - T::MinimumLiquidity::get() - T::Balance::one();
+
+	}: _(RawOrigin::Signed(caller.clone()), pool, withdrawal_bal, withdrawal_bal, withdrawal_bal, recipient.clone(), None)
+	verify {
+		let recipient_new0_bal = balance_of::<T>(&recipient, asset_0);
+		assert_eq!(recipient_new0_bal, recipient_orig0_bal + withdrawal_bal);
+
+		let recipient_new1_bal = balance_of::<T>(&recipient, asset_1);
+		assert_eq!(recipient_new1_bal, recipient_orig1_bal + withdrawal_bal);
+	}
+
+	zap_in{
+		let caller: T::AccountId = <FeeToSetter<T>>::get().unwrap();
+		let origin = RawOrigin::Signed(caller.clone());
+
+		let pool = genesis_pool::<T>();
+		let (asset_0, _) = pool;
+
+		let deposit_bal = T::Balance::from(100000u32);
+		<Amm<T>>::deposit_asset_1(T::Origin::from(origin), pool, deposit_bal, None).expect("deposit should work");
+
+		let caller_orig0_bal = balance_of::<T>(&caller, asset_0);
+		let caller_orig_lp = Amm::<T>::lp_balance(pool, caller.clone());
+
+		let zap_bal = T::Balance::from(10000u32);
+	}: _(RawOrigin::Signed(caller.clone()), pool, asset_0, zap_bal, T::Balance::one(), None)
+	verify {
+		let caller_new0_bal = balance_of::<T>(&caller, asset_0);
+		assert!(caller_new0_bal < caller_orig0_bal);
+
+		let caller_new_lp = Amm::<T>::lp_balance(pool, caller.clone());
+		assert!(caller_new_lp > caller_orig_lp);
+	}
+
+	zap_out{
+		let caller: T::AccountId = <FeeToSetter<T>>::get().unwrap();
+		let origin = RawOrigin::Signed(caller.clone());
+
+		let pool = genesis_pool::<T>();
+		let (asset_0, asset_1) = pool;
+
+		let deposit_bal = T::Balance::from(100000u32);
+		<Amm<T>>::deposit_asset_1(T::Origin::from(origin), pool, deposit_bal, None).expect("deposit should work");
+
+		let caller_orig0_bal = balance_of::<T>(&caller, asset_0);
+		let caller_orig1_bal = balance_of::<T>(&caller, asset_1);
+
+		let zap_bal = T::Balance::from(10000u32);
+	}: _(RawOrigin::Signed(caller.clone()), pool, zap_bal, asset_0, T::Balance::one(), None)
+	verify {
+		let caller_new0_bal = balance_of::<T>(&caller, asset_0);
+		assert!(caller_new0_bal > caller_orig0_bal + zap_bal);
+
+		let caller_new1_bal = balance_of::<T>(&caller, asset_1);
+		assert_eq!(caller_new1_bal, caller_orig1_bal);
+	}
+
+	swap_asset_1_for_asset_2{
+		let caller: T::AccountId = <FeeToSetter<T>>::get().unwrap();
+		let origin = RawOrigin::Signed(caller.clone());
+
+		let pool = genesis_pool::<T>();
+		let (asset_0, asset_1) = pool;
+
+		let deposit_bal = T::Balance::from(10000u32);
+		<Amm<T>>::deposit_asset_1(T::Origin::from(origin), pool, deposit_bal, None).expect("deposit should work");
 
 		let caller_orig0_bal = balance_of::<T>(&caller, asset_0);
 		let caller_orig1_bal = balance_of::<T>(&caller, asset_1);
 
-		let contract = <PalletAccountId<T>>::get().unwrap();
+		let contract = pool_account::<T>(pool).unwrap();
 		let contract_orig0_bal = balance_of::<T>(&contract, asset_0);
 		let contract_orig1_bal = balance_of::<T>(&contract, asset_1);
 
-		let (reserve_orig0, reserve_orig1, _) = reserves::<T>();
+		let (reserve_orig0, reserve_orig1, _) = reserves::<T>(pool);
 
 		let swap_bal = T::Balance::from(5000u32);
-	}: _(RawOrigin::Signed(caller.clone()), swap_bal)
+	}: _(RawOrigin::Signed(caller.clone()), pool, swap_bal, caller_orig0_bal, None)
 	verify {
 		let caller_new0_bal = balance_of::<T>(&caller, asset_0);
 		assert!(caller_new0_bal < caller_orig0_bal);
@@ -219,7 +452,7 @@ benchmarks! {
 		let contract_new1_bal = balance_of::<T>(&contract, asset_1);
 		assert!(contract_new1_bal < contract_orig1_bal);
 
-		let (reserve_0, reserve_1, _) = reserves::<T>();
+		let (reserve_0, reserve_1, _) = reserves::<T>(pool);
 		assert!(reserve_0 > reserve_1);
 	}
 
@@ -227,23 +460,23 @@ benchmarks! {
 		let caller: T::AccountId = <FeeToSetter<T>>::get().unwrap();
 		let origin = RawOrigin::Signed(caller.clone());
 
-		let deposit_bal = T::Balance::from(10000u32);
-		<Amm<T>>::deposit_asset_1(T::Origin::from(origin), deposit_bal).expect("deposit should work");
+		let pool = genesis_pool::<T>();
+		let (asset_0, asset_1) = pool;
 
-		let asset_0 = T::Asset0::get();
-		let asset_1 = T::Asset1::get();
+		let deposit_bal = T::Balance::from(10000u32);
+		<Amm<T>>::deposit_asset_1(T::Origin::from(origin), pool, deposit_bal, None).expect("deposit should work");
 
 		let caller_orig0_bal = balance_of::<T>(&caller, asset_0);
 		let caller_orig1_bal = balance_of::<T>(&caller, asset_1);
 
-		let contract = <PalletAccountId<T>>::get().unwrap();
+		let contract = pool_account::<T>(pool).unwrap();
 		let contract_orig0_bal = balance_of::<T>(&contract, asset_0);
 		let contract_orig1_bal = balance_of::<T>(&contract, asset_1);
 
-		let (reserve_orig0, reserve_orig1, _) = reserves::<T>();
+		let (reserve_orig0, reserve_orig1, _) = reserves::<T>(pool);
 
 		let swap_bal = T::Balance::from(5000u32);
-	}: _(RawOrigin::Signed(caller.clone()), swap_bal)
+	}: _(RawOrigin::Signed(caller.clone()), pool, swap_bal, caller_orig1_bal, None)
 	verify {
 		let caller_new0_bal = balance_of::<T>(&caller, asset_0);
 		assert_eq!(caller_new0_bal, caller_orig0_bal + swap_bal);
@@ -257,7 +490,199 @@ benchmarks! {
 		let contract_new1_bal = balance_of::<T>(&contract, asset_1);
 		assert!(contract_new1_bal > contract_orig1_bal);
 
-		let (reserve_0, reserve_1, _) = reserves::<T>();
+		let (reserve_0, reserve_1, _) = reserves::<T>(pool);
+		assert!(reserve_0 < reserve_1);
+	}
+
+	swap_exact_in_asset_1_for_asset_2{
+		let caller: T::AccountId = <FeeToSetter<T>>::get().unwrap();
+		let origin = RawOrigin::Signed(caller.clone());
+
+		let pool = genesis_pool::<T>();
+		let (asset_0, asset_1) = pool;
+
+		let deposit_bal = T::Balance::from(10000u32);
+		<Amm<T>>::deposit_asset_1(T::Origin::from(origin), pool, deposit_bal, None).expect("deposit should work");
+
+		let caller_orig0_bal = balance_of::<T>(&caller, asset_0);
+		let caller_orig1_bal = balance_of::<T>(&caller, asset_1);
+
+		let swap_bal = T::Balance::from(5000u32);
+	}: _(RawOrigin::Signed(caller.clone()), pool, swap_bal, T::Balance::one(), None)
+	verify {
+		let caller_new0_bal = balance_of::<T>(&caller, asset_0);
+		assert_eq!(caller_new0_bal, caller_orig0_bal - swap_bal);
+
+		let caller_new1_bal = balance_of::<T>(&caller, asset_1);
+		assert!(caller_new1_bal > caller_orig1_bal);
+
+		let (reserve_0, reserve_1, _) = reserves::<T>(pool);
+		assert!(reserve_0 > reserve_1);
+	}
+
+	swap_exact_in_asset_2_for_asset_1{
+		let caller: T::AccountId = <FeeToSetter<T>>::get().unwrap();
+		let origin = RawOrigin::Signed(caller.clone());
+
+		let pool = genesis_pool::<T>();
+		let (asset_0, asset_1) = pool;
+
+		let deposit_bal = T::Balance::from(10000u32);
+		<Amm<T>>::deposit_asset_1(T::Origin::from(origin), pool, deposit_bal, None).expect("deposit should work");
+
+		let caller_orig0_bal = balance_of::<T>(&caller, asset_0);
+		let caller_orig1_bal = balance_of::<T>(&caller, asset_1);
+
+		let swap_bal = T::Balance::from(5000u32);
+	}: _(RawOrigin::Signed(caller.clone()), pool, swap_bal, T::Balance::one(), None)
+	verify {
+		let caller_new0_bal = balance_of::<T>(&caller, asset_0);
+		assert!(caller_new0_bal > caller_orig0_bal);
+
+		let caller_new1_bal = balance_of::<T>(&caller, asset_1);
+		assert_eq!(caller_new1_bal, caller_orig1_bal - swap_bal);
+
+		let (reserve_0, reserve_1, _) = reserves::<T>(pool);
 		assert!(reserve_0 < reserve_1);
 	}
+
+	// The genesis config creates a single pool, so the path has one hop.
+	swap_exact_in_along_path{
+		let caller: T::AccountId = <FeeToSetter<T>>::get().unwrap();
+		let origin = RawOrigin::Signed(caller.clone());
+
+		let pool = genesis_pool::<T>();
+		let (asset_0, asset_1) = pool;
+		let path: BoundedVec<_, T::MaxPathLength> = vec![asset_0, asset_1].try_into().unwrap();
+
+		let deposit_bal = T::Balance::from(10000u32);
+		<Amm<T>>::deposit_asset_1(T::Origin::from(origin), pool, deposit_bal, None).expect("deposit should work");
+
+		let caller_orig0_bal = balance_of::<T>(&caller, asset_0);
+		let caller_orig1_bal = balance_of::<T>(&caller, asset_1);
+
+		let swap_bal = T::Balance::from(5000u32);
+	}: _(RawOrigin::Signed(caller.clone()), path, swap_bal, T::Balance::one(), None)
+	verify {
+		let caller_new0_bal = balance_of::<T>(&caller, asset_0);
+		assert_eq!(caller_new0_bal, caller_orig0_bal - swap_bal);
+
+		let caller_new1_bal = balance_of::<T>(&caller, asset_1);
+		assert!(caller_new1_bal > caller_orig1_bal);
+	}
+
+	// The genesis config creates a single pool, so the path has one hop.
+	swap_exact_out_along_path{
+		let caller: T::AccountId = <FeeToSetter<T>>::get().unwrap();
+		let origin = RawOrigin::Signed(caller.clone());
+
+		let pool = genesis_pool::<T>();
+		let (asset_0, asset_1) = pool;
+		let path: BoundedVec<_, T::MaxPathLength> = vec![asset_0, asset_1].try_into().unwrap();
+
+		let deposit_bal = T::Balance::from(10000u32);
+		<Amm<T>>::deposit_asset_1(T::Origin::from(origin), pool, deposit_bal, None).expect("deposit should work");
+
+		let caller_orig0_bal = balance_of::<T>(&caller, asset_0);
+		let caller_orig1_bal = balance_of::<T>(&caller, asset_1);
+
+		let swap_bal = T::Balance::from(10u32);
+	}: _(RawOrigin::Signed(caller.clone()), path, swap_bal, caller_orig0_bal, None)
+	verify {
+		let caller_new0_bal = balance_of::<T>(&caller, asset_0);
+		assert!(caller_new0_bal < caller_orig0_bal);
+
+		let caller_new1_bal = balance_of::<T>(&caller, asset_1);
+		assert_eq!(caller_new1_bal, caller_orig1_bal + swap_bal);
+	}
+
+	// The input is paid beforehand, as the runtime has no flash loan receiver.
+	flash_swap{
+		let caller: T::AccountId = <FeeToSetter<T>>::get().unwrap();
+		let origin = RawOrigin::Signed(caller.clone());
+
+		let pool = genesis_pool::<T>();
+		let (asset_0, asset_1) = pool;
+
+		let deposit_bal = T::Balance::from(10000u32);
+		<Amm<T>>::deposit_asset_1(T::Origin::from(origin), pool, deposit_bal, None).expect("deposit should work");
+
+		let contract = pool_account::<T>(pool).unwrap();
+		transfer_tokens::<T>(&caller, &contract, asset_0, T::Balance::from(20u32)).expect("transfer should work");
+
+		let caller_orig1_bal = balance_of::<T>(&caller, asset_1);
+
+		let swap_bal = T::Balance::from(10u32);
+	}: _(RawOrigin::Signed(caller.clone()), pool, T::Balance::zero(), swap_bal, caller.clone(), BoundedVec::default())
+	verify {
+		let caller_new1_bal = balance_of::<T>(&caller, asset_1);
+		assert_eq!(caller_new1_bal, caller_orig1_bal + swap_bal);
+	}
+
+	// The genesis config has two assets, so the basket benchmarks use baskets of two assets.
+	create_basket {
+		let caller: T::AccountId = whitelisted_caller();
+		let (asset_0, asset_1) = genesis_pool::<T>();
+		let assets: AssetsOf<T> = vec![asset_1, asset_0].try_into().unwrap();
+		let pool: AssetsOf<T> = vec![asset_0, asset_1].try_into().unwrap();
+	}: _(RawOrigin::Signed(caller), assets)
+	verify {
+		assert!(<BasketReserves<T>>::contains_key(&pool));
+	}
+
+	join_basket {
+		let caller: T::AccountId = <FeeToSetter<T>>::get().unwrap();
+		let pool = joined_basket::<T>(&caller);
+		let amounts: AmountsOf<T> = vec![T::Balance::from(5000u32); 2].try_into().unwrap();
+		let liquidity = Amm::<T>::basket_lp_balance(pool.clone(), caller.clone());
+	}: _(RawOrigin::Signed(caller.clone()), pool.clone(), amounts, T::Balance::one(), None)
+	verify {
+		assert!(Amm::<T>::basket_lp_balance(pool.clone(), caller.clone()) > liquidity);
+	}
+
+	exit_basket {
+		let caller: T::AccountId = <FeeToSetter<T>>::get().unwrap();
+		let pool = joined_basket::<T>(&caller);
+		let amounts: AmountsOf<T> = vec![T::Balance::zero(); 2].try_into().unwrap();
+		let liquidity = Amm::<T>::basket_lp_balance(pool.clone(), caller.clone());
+	}: _(RawOrigin::Signed(caller.clone()), pool.clone(), liquidity, amounts, None)
+	verify {
+		assert!(Amm::<T>::basket_lp_balance(pool.clone(), caller.clone()).is_zero());
+	}
+
+	join_basket_single {
+		let caller: T::AccountId = <FeeToSetter<T>>::get().unwrap();
+		let pool = joined_basket::<T>(&caller);
+		let liquidity = Amm::<T>::basket_lp_balance(pool.clone(), caller.clone());
+	}: _(RawOrigin::Signed(caller.clone()), pool.clone(), pool[0], T::Balance::from(1000u32), T::Balance::one(), None)
+	verify {
+		assert!(Amm::<T>::basket_lp_balance(pool.clone(), caller.clone()) > liquidity);
+	}
+
+	exit_basket_single {
+		let caller: T::AccountId = <FeeToSetter<T>>::get().unwrap();
+		let pool = joined_basket::<T>(&caller);
+		let balance = balance_of::<T>(&caller, pool[0]);
+	}: _(RawOrigin::Signed(caller.clone()), pool.clone(), pool[0], T::Balance::from(1000u32), T::Balance::one(), None)
+	verify {
+		assert!(balance_of::<T>(&caller, pool[0]) > balance);
+	}
+
+	swap_basket {
+		let caller: T::AccountId = <FeeToSetter<T>>::get().unwrap();
+		let pool = joined_basket::<T>(&caller);
+		let balance = balance_of::<T>(&caller, pool[1]);
+	}: _(RawOrigin::Signed(caller.clone()), pool.clone(), pool[0], pool[1], T::Balance::from(1000u32), T::Balance::one(), None)
+	verify {
+		assert!(balance_of::<T>(&caller, pool[1]) > balance);
+	}
+
+	set_basket_swap_fee {
+		let caller: T::AccountId = <FeeToSetter<T>>::get().unwrap();
+		let pool = joined_basket::<T>(&caller);
+		let fee = Permill::from_percent(1);
+	}: _(RawOrigin::Root, pool.clone(), fee)
+	verify {
+		assert_eq!(<BasketSwapFees<T>>::get(&pool), fee);
+	}
 }
@@ -0,0 +1,518 @@
+//! The math of the different kinds of pools.
+//!
+//! The accounting of deposits, withdrawals and swaps in the helpers only goes through the
+//! `Curve` of the pool, so a new kind of pool needs an implementation of `Curve`, a variant of
+//! `PoolCurve` and a way to tell its pools apart in `helper::pool_curve`.
+//!
+//! Swaps pass the reserve of the input asset first. Curves which are not symmetric, like
+//! `Weighted`, have to be `PoolCurve::reversed` for swaps of the second asset of a pool.
+
+use crate::{
+	helper::{from_u256, mul_div, mul_div_ceil, product, to_u256},
+	pallet::{Config, Error},
+	stable, weighted,
+};
+use frame_support::ensure;
+use sp_core::U256;
+use sp_runtime::{
+	traits::{CheckedAdd, One, Saturating, Zero},
+	FixedPointNumber, FixedU128, PerThing, Permill,
+};
+use sp_std::cmp;
+
+/// The invariant of a pool of two assets, and the amounts of swaps and LP tokens it implies.
+pub trait Curve<T: Config> {
+	/// The amount received for `amount_in`, after taking the `fee` from `amount_in`.
+	fn amount_out(
+		&self,
+		amount_in: T::Balance,
+		reserve_in: T::Balance,
+		reserve_out: T::Balance,
+		fee: Permill,
+	) -> Result<T::Balance, Error<T>>;
+
+	/// The amount needed to receive `amount_out`, including the `fee`.
+	fn amount_in(
+		&self,
+		amount_out: T::Balance,
+		reserve_in: T::Balance,
+		reserve_out: T::Balance,
+		fee: Permill,
+	) -> Result<T::Balance, Error<T>>;
+
+	/// The invariant for the given balances, which swaps must not decrease.
+	/// It grows with the square of the liquidity, like `x * y`, so that its square root can be
+	/// compared to the supply of LP tokens.
+	fn invariant(&self, balance_0: T::Balance, balance_1: T::Balance) -> Result<U256, Error<T>>;
+
+	/// The amount of the input asset paid per unit of the output asset for an infinitesimal
+	/// swap, without the fee.
+	fn spot_price(
+		&self,
+		reserve_in: T::Balance,
+		reserve_out: T::Balance,
+	) -> Result<FixedU128, Error<T>>;
+
+	/// Fails with `InvalidK` if the invariant of the balances after a swap, without the fee on
+	/// the input, is less than that of the reserves before.
+	fn ensure_invariant(
+		&self,
+		balance_0_adjusted: T::Balance,
+		balance_1_adjusted: T::Balance,
+		reserve_0: T::Balance,
+		reserve_1: T::Balance,
+	) -> Result<(), Error<T>> {
+		let balance = self.invariant(balance_0_adjusted, balance_1_adjusted)?;
+		let reserve = self.invariant(reserve_0, reserve_1)?;
+
+		ensure!(balance >= reserve, Error::<T>::InvalidK);
+		Ok(())
+	}
+
+	/// The LP tokens for depositing `amount_0` and `amount_1`, including the `MinimumLiquidity`
+	/// which is locked by the first deposit.
+	fn mint_amount(
+		&self,
+		amount_0: T::Balance,
+		amount_1: T::Balance,
+		reserve_0: T::Balance,
+		reserve_1: T::Balance,
+		total_supply: T::Balance,
+	) -> Result<T::Balance, Error<T>> {
+		if total_supply.is_zero() {
+			return from_u256::<T>(self.invariant(amount_0, amount_1)?.integer_sqrt())
+		}
+
+		Ok(cmp::min(
+			mul_div::<T>(amount_0, total_supply, reserve_0)?,
+			mul_div::<T>(amount_1, total_supply, reserve_1)?,
+		))
+	}
+
+	/// The amounts of both assets for burning `liquidity` of the `total_supply`.
+	fn burn_amounts(
+		&self,
+		liquidity: T::Balance,
+		balance_0: T::Balance,
+		balance_1: T::Balance,
+		total_supply: T::Balance,
+	) -> Result<(T::Balance, T::Balance), Error<T>> {
+		Ok((
+			mul_div::<T>(liquidity, balance_0, total_supply)?,
+			mul_div::<T>(liquidity, balance_1, total_supply)?,
+		))
+	}
+}
+
+/// The `x * y = k` invariant of Uniswap V2.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ConstantProduct;
+
+impl<T: Config> Curve<T> for ConstantProduct {
+	fn amount_out(
+		&self,
+		amount_in: T::Balance,
+		reserve_in: T::Balance,
+		reserve_out: T::Balance,
+		fee: Permill,
+	) -> Result<T::Balance, Error<T>> {
+		let zero = T::Balance::zero();
+
+		ensure!(amount_in > zero, Error::<T>::InsufficientInputAmount);
+
+		ensure!(reserve_in > zero && reserve_out > zero, Error::<T>::InsufficientLiquidity);
+
+		let amount_in_with_fee = amount_in.saturating_sub(fee.mul_floor(amount_in));
+
+		let denominator =
+			reserve_in.checked_add(&amount_in_with_fee).ok_or(Error::<T>::Overflow)?;
+
+		mul_div::<T>(amount_in_with_fee, reserve_out, denominator)
+	}
+
+	fn amount_in(
+		&self,
+		amount_out: T::Balance,
+		reserve_in: T::Balance,
+		reserve_out: T::Balance,
+		fee: Permill,
+	) -> Result<T::Balance, Error<T>> {
+		let zero = T::Balance::zero();
+
+		if amount_out <= zero {
+			return Err(Error::<T>::InsufficientOutputAmount)
+		}
+
+		// the whole reserve cannot be paid out at any price
+		if reserve_in <= zero || reserve_out <= amount_out {
+			return Err(Error::<T>::InsufficientLiquidity)
+		}
+
+		let denominator = reserve_out - amount_out;
+
+		let res = mul_div::<T>(reserve_in, amount_out, denominator)?
+			.checked_add(&T::Balance::one())
+			.ok_or(Error::<T>::Overflow)?;
+
+		amount_before_fee::<T>(res, fee)
+	}
+
+	fn invariant(&self, balance_0: T::Balance, balance_1: T::Balance) -> Result<U256, Error<T>> {
+		Ok(product::<T>(balance_0, balance_1))
+	}
+
+	fn spot_price(
+		&self,
+		reserve_in: T::Balance,
+		reserve_out: T::Balance,
+	) -> Result<FixedU128, Error<T>> {
+		fixed_ratio::<T>(to_u256::<T>(reserve_in), to_u256::<T>(reserve_out))
+	}
+}
+
+/// The StableSwap invariant of Curve with the given amplification, see `stable`.
+/// Its `invariant` is `D^2`, as `D` is the sum of balanced reserves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StableSwap {
+	pub amplification: u32,
+}
+
+impl<T: Config> Curve<T> for StableSwap {
+	fn amount_out(
+		&self,
+		amount_in: T::Balance,
+		reserve_in: T::Balance,
+		reserve_out: T::Balance,
+		fee: Permill,
+	) -> Result<T::Balance, Error<T>> {
+		let zero = T::Balance::zero();
+
+		ensure!(amount_in > zero, Error::<T>::InsufficientInputAmount);
+
+		ensure!(reserve_in > zero && reserve_out > zero, Error::<T>::InsufficientLiquidity);
+
+		let amount_in_with_fee = amount_in.saturating_sub(fee.mul_floor(amount_in));
+
+		let reserve_in = to_u256::<T>(reserve_in);
+		let reserve_out = to_u256::<T>(reserve_out);
+		let d = stable::get_d(reserve_in, reserve_out, self.amplification)
+			.ok_or(Error::<T>::InvariantNotSolved)?;
+		let y = stable::get_y(reserve_in + to_u256::<T>(amount_in_with_fee), d, self.amplification)
+			.ok_or(Error::<T>::InvariantNotSolved)?;
+
+		// rounded down, so that the invariant does not decrease despite the rounding of `D`
+		from_u256::<T>(reserve_out.saturating_sub(y).saturating_sub(U256::from(2u32)))
+	}
+
+	fn amount_in(
+		&self,
+		amount_out: T::Balance,
+		reserve_in: T::Balance,
+		reserve_out: T::Balance,
+		fee: Permill,
+	) -> Result<T::Balance, Error<T>> {
+		let zero = T::Balance::zero();
+
+		ensure!(amount_out > zero, Error::<T>::InsufficientOutputAmount);
+
+		ensure!(reserve_in > zero && reserve_out > amount_out, Error::<T>::InsufficientLiquidity);
+
+		let reserve_in = to_u256::<T>(reserve_in);
+		let reserve_out = to_u256::<T>(reserve_out);
+		let d = stable::get_d(reserve_in, reserve_out, self.amplification)
+			.ok_or(Error::<T>::InvariantNotSolved)?;
+		let x = stable::get_y(reserve_out - to_u256::<T>(amount_out), d, self.amplification)
+			.ok_or(Error::<T>::InvariantNotSolved)?;
+
+		// rounded up, so that the invariant does not decrease despite the rounding of `D`
+		let res = from_u256::<T>(x.saturating_sub(reserve_in).saturating_add(U256::from(2u32)))?;
+
+		amount_before_fee::<T>(res, fee)
+	}
+
+	fn invariant(&self, balance_0: T::Balance, balance_1: T::Balance) -> Result<U256, Error<T>> {
+		let d = stable::get_d(to_u256::<T>(balance_0), to_u256::<T>(balance_1), self.amplification)
+			.ok_or(Error::<T>::InvariantNotSolved)?;
+
+		d.checked_mul(d).ok_or(Error::<T>::Overflow)
+	}
+
+	fn spot_price(
+		&self,
+		reserve_in: T::Balance,
+		reserve_out: T::Balance,
+	) -> Result<FixedU128, Error<T>> {
+		let zero = T::Balance::zero();
+
+		ensure!(reserve_in > zero && reserve_out > zero, Error::<T>::InsufficientLiquidity);
+
+		let price = stable::spot_price(
+			to_u256::<T>(reserve_in),
+			to_u256::<T>(reserve_out),
+			self.amplification,
+		)
+		.ok_or(Error::<T>::InvariantNotSolved)?;
+
+		Ok(FixedU128::from_inner(price.try_into().map_err(|_| Error::<T>::Overflow)?))
+	}
+}
+
+/// The weighted invariant of Balancer, `x^w_0 * y^w_1` with `w_0 + w_1 = 1`, where `weight_0`
+/// is the weight of the first reserve passed to any method. Its `invariant` is the square of
+/// `x^w_0 * y^w_1`, see `weighted`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Weighted {
+	pub weight_0: Permill,
+	pub weight_1: Permill,
+}
+
+impl Weighted {
+	/// The curve of a pool where the first asset has `weight_0`.
+	pub fn new(weight_0: Permill) -> Self {
+		Weighted { weight_0, weight_1: weight_0.left_from_one() }
+	}
+
+	/// The same curve with the weights swapped.
+	pub fn reversed(self) -> Self {
+		Weighted { weight_0: self.weight_1, weight_1: self.weight_0 }
+	}
+
+	/// `w_0 * ln_0 + w_1 * ln_1`, with the weights in parts per million.
+	fn weighted_sum(&self, ln_0: i128, ln_1: i128) -> i128 {
+		ln_0 * i128::from(self.weight_0.deconstruct()) +
+			ln_1 * i128::from(self.weight_1.deconstruct())
+	}
+}
+
+impl<T: Config> Curve<T> for Weighted {
+	fn amount_out(
+		&self,
+		amount_in: T::Balance,
+		reserve_in: T::Balance,
+		reserve_out: T::Balance,
+		fee: Permill,
+	) -> Result<T::Balance, Error<T>> {
+		let zero = T::Balance::zero();
+
+		ensure!(amount_in > zero, Error::<T>::InsufficientInputAmount);
+
+		ensure!(reserve_in > zero && reserve_out > zero, Error::<T>::InsufficientLiquidity);
+
+		ensure!(
+			amount_in <= weighted::MAX_SWAP_RATIO.mul_floor(reserve_in),
+			Error::<T>::SwapTooLarge
+		);
+
+		let amount_in_with_fee = amount_in.saturating_sub(fee.mul_floor(amount_in));
+
+		// reserve_out * (1 - (reserve_in / (reserve_in + amount_in))^(w_in / w_out))
+		let reserve_in = to_u256::<T>(reserve_in);
+		let power = weighted::pow_up(
+			reserve_in,
+			reserve_in + to_u256::<T>(amount_in_with_fee),
+			weight_ratio(self.weight_0, self.weight_1),
+		)
+		.ok_or(Error::<T>::InvariantNotSolved)?;
+
+		let one = U256::from(weighted::ONE);
+		from_u256::<T>(to_u256::<T>(reserve_out) * one.saturating_sub(power) / one)
+	}
+
+	fn amount_in(
+		&self,
+		amount_out: T::Balance,
+		reserve_in: T::Balance,
+		reserve_out: T::Balance,
+		fee: Permill,
+	) -> Result<T::Balance, Error<T>> {
+		let zero = T::Balance::zero();
+
+		ensure!(amount_out > zero, Error::<T>::InsufficientOutputAmount);
+
+		ensure!(reserve_in > zero && reserve_out > amount_out, Error::<T>::InsufficientLiquidity);
+
+		ensure!(
+			amount_out <= weighted::MAX_SWAP_RATIO.mul_floor(reserve_out),
+			Error::<T>::SwapTooLarge
+		);
+
+		// reserve_in * ((reserve_out / (reserve_out - amount_out))^(w_out / w_in) - 1)
+		let reserve_out = to_u256::<T>(reserve_out);
+		let power = weighted::pow_up(
+			reserve_out,
+			reserve_out - to_u256::<T>(amount_out),
+			weight_ratio(self.weight_1, self.weight_0),
+		)
+		.ok_or(Error::<T>::InvariantNotSolved)?;
+
+		let one = U256::from(weighted::ONE);
+		let res = to_u256::<T>(reserve_in)
+			.checked_mul(power.saturating_sub(one))
+			.ok_or(Error::<T>::Overflow)?;
+		let res = from_u256::<T>((res + one - U256::one()) / one)?;
+
+		amount_before_fee::<T>(res, fee)
+	}
+
+	fn invariant(&self, balance_0: T::Balance, balance_1: T::Balance) -> Result<U256, Error<T>> {
+		let zero = T::Balance::zero();
+		if balance_0 == zero || balance_1 == zero {
+			return Ok(U256::zero())
+		}
+
+		let ln_0 =
+			weighted::ln(to_u256::<T>(balance_0), U256::one()).ok_or(Error::<T>::Overflow)?;
+		let ln_1 =
+			weighted::ln(to_u256::<T>(balance_1), U256::one()).ok_or(Error::<T>::Overflow)?;
+		let ln = self.weighted_sum(ln_0, ln_1) / i128::from(Permill::ACCURACY);
+
+		let value = weighted::exp(ln).ok_or(Error::<T>::Overflow)? / U256::from(weighted::ONE);
+		value.checked_mul(value).ok_or(Error::<T>::Overflow)
+	}
+
+	fn ensure_invariant(
+		&self,
+		balance_0_adjusted: T::Balance,
+		balance_1_adjusted: T::Balance,
+		reserve_0: T::Balance,
+		reserve_1: T::Balance,
+	) -> Result<(), Error<T>> {
+		// the logarithm of the ratio of the invariants is more accurate than both invariants
+		let ln_0 = weighted::ln(to_u256::<T>(balance_0_adjusted), to_u256::<T>(reserve_0))
+			.ok_or(Error::<T>::InvalidK)?;
+		let ln_1 = weighted::ln(to_u256::<T>(balance_1_adjusted), to_u256::<T>(reserve_1))
+			.ok_or(Error::<T>::InvalidK)?;
+
+		ensure!(self.weighted_sum(ln_0, ln_1) >= 0, Error::<T>::InvalidK);
+		Ok(())
+	}
+
+	fn spot_price(
+		&self,
+		reserve_in: T::Balance,
+		reserve_out: T::Balance,
+	) -> Result<FixedU128, Error<T>> {
+		// (reserve_in / w_in) / (reserve_out / w_out)
+		fixed_ratio::<T>(
+			to_u256::<T>(reserve_in) * U256::from(self.weight_1.deconstruct()),
+			to_u256::<T>(reserve_out) * U256::from(self.weight_0.deconstruct()),
+		)
+	}
+}
+
+/// `a / b` with 18 decimals, for the exponents of the swap formulas.
+fn weight_ratio(a: Permill, b: Permill) -> u128 {
+	u128::from(a.deconstruct()) * weighted::ONE / u128::from(b.deconstruct()).max(1)
+}
+
+/// The curve of a pool, as selected by `helper::pool_curve`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PoolCurve {
+	ConstantProduct(ConstantProduct),
+	StableSwap(StableSwap),
+	Weighted(Weighted),
+}
+
+impl PoolCurve {
+	/// The curve with the reserves passed the other way round, for swaps of the second asset.
+	pub fn reversed(self) -> Self {
+		match self {
+			PoolCurve::Weighted(curve) => PoolCurve::Weighted(curve.reversed()),
+			curve => curve,
+		}
+	}
+
+	fn inner<T: Config>(&self) -> &dyn Curve<T> {
+		match self {
+			PoolCurve::ConstantProduct(curve) => curve,
+			PoolCurve::StableSwap(curve) => curve,
+			PoolCurve::Weighted(curve) => curve,
+		}
+	}
+}
+
+impl<T: Config> Curve<T> for PoolCurve {
+	fn amount_out(
+		&self,
+		amount_in: T::Balance,
+		reserve_in: T::Balance,
+		reserve_out: T::Balance,
+		fee: Permill,
+	) -> Result<T::Balance, Error<T>> {
+		self.inner::<T>().amount_out(amount_in, reserve_in, reserve_out, fee)
+	}
+
+	fn amount_in(
+		&self,
+		amount_out: T::Balance,
+		reserve_in: T::Balance,
+		reserve_out: T::Balance,
+		fee: Permill,
+	) -> Result<T::Balance, Error<T>> {
+		self.inner::<T>().amount_in(amount_out, reserve_in, reserve_out, fee)
+	}
+
+	fn invariant(&self, balance_0: T::Balance, balance_1: T::Balance) -> Result<U256, Error<T>> {
+		self.inner::<T>().invariant(balance_0, balance_1)
+	}
+
+	fn spot_price(
+		&self,
+		reserve_in: T::Balance,
+		reserve_out: T::Balance,
+	) -> Result<FixedU128, Error<T>> {
+		self.inner::<T>().spot_price(reserve_in, reserve_out)
+	}
+
+	fn ensure_invariant(
+		&self,
+		balance_0_adjusted: T::Balance,
+		balance_1_adjusted: T::Balance,
+		reserve_0: T::Balance,
+		reserve_1: T::Balance,
+	) -> Result<(), Error<T>> {
+		self.inner::<T>().ensure_invariant(
+			balance_0_adjusted,
+			balance_1_adjusted,
+			reserve_0,
+			reserve_1,
+		)
+	}
+
+	fn mint_amount(
+		&self,
+		amount_0: T::Balance,
+		amount_1: T::Balance,
+		reserve_0: T::Balance,
+		reserve_1: T::Balance,
+		total_supply: T::Balance,
+	) -> Result<T::Balance, Error<T>> {
+		self.inner::<T>()
+			.mint_amount(amount_0, amount_1, reserve_0, reserve_1, total_supply)
+	}
+
+	fn burn_amounts(
+		&self,
+		liquidity: T::Balance,
+		balance_0: T::Balance,
+		balance_1: T::Balance,
+		total_supply: T::Balance,
+	) -> Result<(T::Balance, T::Balance), Error<T>> {
+		self.inner::<T>().burn_amounts(liquidity, balance_0, balance_1, total_supply)
+	}
+}
+
+/// The smallest amount which is at least `amount` after taking the `fee`.
+fn amount_before_fee<T: Config>(amount: T::Balance, fee: Permill) -> Result<T::Balance, Error<T>> {
+	let accuracy = T::Balance::from(Permill::ACCURACY);
+	let fee_factor = T::Balance::from(fee.left_from_one().deconstruct());
+	mul_div_ceil::<T>(amount, accuracy, fee_factor)
+}
+
+/// `n / d` as a fixed-point number. Fails with `InsufficientLiquidity` if `d` is zero.
+fn fixed_ratio<T: Config>(n: U256, d: U256) -> Result<FixedU128, Error<T>> {
+	ensure!(!d.is_zero(), Error::<T>::InsufficientLiquidity);
+
+	let ratio = n.checked_mul(U256::from(FixedU128::DIV)).ok_or(Error::<T>::Overflow)? / d;
+	Ok(FixedU128::from_inner(ratio.try_into().map_err(|_| Error::<T>::Overflow)?))
+}
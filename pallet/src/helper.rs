@@ -1,34 +1,128 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 
 use crate::{
+	curve::{ConstantProduct, Curve, PoolCurve, StableSwap, Weighted},
 	pallet::{
-		reserves, AddressZero, BalanceReserves, Config, Error, Event, FeeTo, KLast, LpBalances,
-		Pallet, PalletAccountId, Price0CumulativeLast, Price1CumulativeLast, Reserves, TotalSupply,
+		reserves, Allowances, Amplifications, AssetsOf, BalanceReserves, BasketReserves, Config,
+		Error, Event, FeeTo, KLast, Locks, LpToken, LpTokenOf, Observation, ObservationIndex,
+		Observations, Pallet, PoolIdOf, Price0CumulativeLast, Price1CumulativeLast, Reserves,
+		SwapFees, TotalSupply, Weights,
 	},
-	AmmExtension,
+	AmmExtension, FlashLoanReceiver, LpTokens,
+};
+use codec::{Decode, Encode, TrailingZeroInput};
+use frame_support::{ensure, storage::with_transaction, traits::Get, BoundedVec};
+use sp_core::U256;
+use sp_runtime::{
+	DispatchError, DispatchResult, FixedPointNumber, FixedU128, PerThing, Permill,
+	TransactionOutcome,
 };
-use frame_support::{ensure, traits::Get};
-use sp_runtime::DispatchResult;
 
 use sp_runtime::traits::{
-	Bounded, CheckedAdd, CheckedDiv, CheckedSub, IntegerSquareRoot, One, Saturating, Zero,
+	Bounded, CheckedAdd, CheckedMul, CheckedSub, Hash, One, SaturatedConversion, Saturating, Zero,
 };
 
 use sp_std::{
-	cmp,
 	ops::{Add, Sub},
+	vec,
+	vec::Vec,
 };
 
 type FuncResult<T> = Result<(), Error<T>>;
 
-pub(crate) fn mint<T: Config>(to: &T::AccountId, caller: T::AccountId) -> DispatchResult {
+pub(crate) fn _create_pool<T: Config>(
+	asset_a: T::CurrencyId,
+	asset_b: T::CurrencyId,
+) -> Result<PoolIdOf<T>, Error<T>> {
+	let pool = sorted_pool::<T>(asset_a, asset_b)?;
+
+	ensure!(!<Reserves<T>>::contains_key(pool), Error::<T>::PairExists);
+
+	let account = pool_account::<T>(pool)?;
+
+	<Reserves<T>>::insert(pool, BalanceReserves::default());
+
+	<Pallet<T>>::deposit_event(Event::<T>::PoolCreated { pool, account });
+
+	Ok(pool)
+}
+
+/// The account holding the reserves of the pool.
+/// It is derived from the `PalletId` and the assets of the pool, so no configuration is needed.
+pub(crate) fn pool_account<T: Config>(pool: PoolIdOf<T>) -> Result<T::AccountId, Error<T>> {
+	derive_account::<T, _>(&pool)
+}
+
+/// The account holding the reserves of a basket, derived like that of a pool of two assets.
+pub(crate) fn basket_account<T: Config>(pool: &AssetsOf<T>) -> Result<T::AccountId, Error<T>> {
+	derive_account::<T, _>(pool)
+}
+
+fn derive_account<T: Config, P: Encode>(pool: &P) -> Result<T::AccountId, Error<T>> {
+	let entropy = (b"modl", T::PalletId::get(), pool).using_encoded(T::Hashing::hash);
+
+	T::AccountId::decode(&mut TrailingZeroInput::new(entropy.as_ref()))
+		.map_err(|_| Error::<T>::AddressGenerationFailed)
+}
+
+/// The account the first `MinimumLiquidity` tokens of every pool are locked to.
+pub(crate) fn address_zero<T: Config>() -> Result<T::AccountId, Error<T>> {
+	T::AccountId::decode(&mut TrailingZeroInput::zeroes())
+		.map_err(|_| Error::<T>::AddressGenerationFailed)
+}
+
+/// Runs `f` with the pool locked, so that calls back into the pool from `T::AmmExtension`
+/// or `T::FlashLoanReceiver` fail with `Locked`. If `f` fails, everything it wrote to storage
+/// is reverted. The lock is released either way.
+pub(crate) fn with_lock<T: Config, R>(
+	pool: PoolIdOf<T>,
+	f: impl FnOnce() -> Result<R, DispatchError>,
+) -> Result<R, DispatchError> {
+	ensure!(!<Locks<T>>::get(pool), Error::<T>::Locked);
+	<Locks<T>>::insert(pool, true);
+
+	let result = with_transaction(|| {
+		let result = f();
+		if result.is_ok() {
+			TransactionOutcome::Commit(result)
+		} else {
+			TransactionOutcome::Rollback(result)
+		}
+	});
+
+	<Locks<T>>::remove(pool);
+	result
+}
+
+/// Fails if the `deadline` is set and the current block is past it.
+pub(crate) fn ensure_not_expired<T: Config>(deadline: Option<T::Moment>) -> FuncResult<T> {
+	if let Some(deadline) = deadline {
+		ensure!(pallet_timestamp::Pallet::<T>::now() <= deadline, Error::<T>::Expired);
+	}
+
+	Ok(())
+}
+
+/// Returns the minted amount of LP tokens.
+pub(crate) fn mint<T: Config>(
+	pool: PoolIdOf<T>,
+	to: &T::AccountId,
+	caller: T::AccountId,
+) -> Result<T::Balance, DispatchError> {
+	with_lock::<T, _>(pool, || mint_locked::<T>(pool, to, caller))
+}
+
+fn mint_locked<T: Config>(
+	pool: PoolIdOf<T>,
+	to: &T::AccountId,
+	caller: T::AccountId,
+) -> Result<T::Balance, DispatchError> {
 	let zero = T::Balance::zero();
 
-	let contract = <PalletAccountId<T>>::get().unwrap();
-	let (reserve_0, reserve_1, _) = reserves::<T>();
+	let contract = pool_account::<T>(pool)?;
+	let (reserve_0, reserve_1, _) = reserves::<T>(pool);
 
-	let asset_0 = T::Asset0::get();
-	let asset_1 = T::Asset1::get();
+	let (asset_0, asset_1) = pool;
 
 	let balance_0 = balance_of::<T>(&contract, asset_0);
 	let balance_1 = balance_of::<T>(&contract, asset_1);
@@ -36,72 +130,82 @@ pub(crate) fn mint<T: Config>(to: &T::AccountId, caller: T::AccountId) -> Dispat
 	let amount_0 = balance_0.checked_sub(&reserve_0).unwrap_or(zero);
 	let amount_1 = balance_1.checked_sub(&reserve_1).unwrap_or(zero);
 
-	let fee_on = _mint_fee::<T>(reserve_0, reserve_1)?;
-	let total_supply = <TotalSupply<T>>::get();
+	let curve = pool_curve::<T>(pool);
+	let fee_on = _mint_fee::<T>(pool, &curve, reserve_0, reserve_1)?;
+	let total_supply = <Pallet<T>>::total_supply(pool);
 
-	let liquidity = if total_supply == zero {
-		let to_sqrt = amount_0.saturating_mul(amount_1);
-		let liquidity = to_sqrt.integer_sqrt().saturating_sub(T::MinimumLiquidity::get());
+	let liquidity = curve.mint_amount(amount_0, amount_1, reserve_0, reserve_1, total_supply)?;
 
-		let address_zero = <AddressZero<T>>::get().unwrap();
+	let liquidity = if total_supply == zero {
+		let address_zero = address_zero::<T>()?;
 
 		// permanently lock first liquidity tokens
-		_mint::<T>(&address_zero, T::MinimumLiquidity::get());
+		_mint::<T>(pool.into(), &address_zero, T::MinimumLiquidity::get())?;
 
-		liquidity
+		liquidity.saturating_sub(T::MinimumLiquidity::get())
 	} else {
-		let res = cmp::min(
-			amount_0.saturating_mul(total_supply).checked_div(&reserve_0).unwrap_or(zero),
-			amount_1.saturating_mul(total_supply).checked_div(&reserve_1).unwrap_or(zero),
-		);
-
-		res
+		liquidity
 	};
 
 	ensure!(liquidity > zero, Error::<T>::InsufficientLiquidityMinted);
 
-	_mint::<T>(to, liquidity);
-	_update::<T>(balance_0, balance_1, reserve_0, reserve_1);
+	_mint::<T>(pool.into(), to, liquidity)?;
+	_update::<T>(pool, balance_0, balance_1, reserve_0, reserve_1)?;
 
 	if fee_on {
-		<KLast<T>>::put(reserve_0.saturating_mul(reserve_1));
+		<KLast<T>>::insert(pool, curve.invariant(reserve_0, reserve_1)?);
 	}
 
-	<Pallet<T>>::deposit_event(Event::<T>::Mint { sender: caller, amount_0, amount_1 });
+	<Pallet<T>>::deposit_event(Event::<T>::Mint {
+		pool: pool_assets::<T>(pool)?,
+		sender: caller,
+		amounts: bounded::<T, _>(vec![amount_0, amount_1])?,
+	});
 
-	Ok(())
+	Ok(liquidity)
+}
+
+/// Returns the withdrawn amounts of both assets.
+/// Fails if they are less than `amount_0_min` or `amount_1_min`.
+pub(crate) fn burn<T: Config>(
+	pool: PoolIdOf<T>,
+	to: &T::AccountId,
+	caller: T::AccountId,
+	amount_0_min: T::Balance,
+	amount_1_min: T::Balance,
+) -> Result<(T::Balance, T::Balance), DispatchError> {
+	with_lock::<T, _>(pool, || burn_locked::<T>(pool, to, caller, amount_0_min, amount_1_min))
 }
 
-pub(crate) fn burn<T: Config>(to: &T::AccountId, caller: T::AccountId) -> DispatchResult {
+fn burn_locked<T: Config>(
+	pool: PoolIdOf<T>,
+	to: &T::AccountId,
+	caller: T::AccountId,
+	amount_0_min: T::Balance,
+	amount_1_min: T::Balance,
+) -> Result<(T::Balance, T::Balance), DispatchError> {
 	let zero = T::Balance::zero();
 
-	let contract = <PalletAccountId<T>>::get().unwrap();
-	let (reserve_0, reserve_1, _) = reserves::<T>();
+	let contract = pool_account::<T>(pool)?;
+	let (reserve_0, reserve_1, _) = reserves::<T>(pool);
 
-	let asset_0 = T::Asset0::get();
-	let asset_1 = T::Asset1::get();
+	let (asset_0, asset_1) = pool;
 
 	let balance_0 = balance_of::<T>(&contract, asset_0.clone());
 	let balance_1 = balance_of::<T>(&contract, asset_1.clone());
 
-	let liquidity = <LpBalances<T>>::get(&contract).unwrap();
-
-	let fee_on = _mint_fee::<T>(reserve_0, reserve_1)?;
-	let total_supply = <TotalSupply<T>>::get();
+	let liquidity = T::LpTokens::balance(&pool.into(), &contract);
 
-	let amount_0 = {
-		let to_div = liquidity.saturating_mul(balance_0);
-		to_div.checked_div(&total_supply).unwrap_or(zero)
-	};
+	let curve = pool_curve::<T>(pool);
+	let fee_on = _mint_fee::<T>(pool, &curve, reserve_0, reserve_1)?;
+	let total_supply = <Pallet<T>>::total_supply(pool);
 
-	let amount_1 = {
-		let to_div = liquidity.saturating_mul(balance_1);
-		to_div.checked_div(&total_supply).unwrap_or(zero)
-	};
+	let (amount_0, amount_1) = curve.burn_amounts(liquidity, balance_0, balance_1, total_supply)?;
 
 	ensure!(amount_0 > zero && amount_1 > zero, Error::<T>::InsufficientLiquidityBurned);
+	ensure!(amount_0 >= amount_0_min && amount_1 >= amount_1_min, Error::<T>::SlippageExceeded);
 
-	_burn::<T>(&contract, liquidity)?;
+	_burn::<T>(pool.into(), &contract, liquidity)?;
 
 	transfer_tokens::<T>(&contract, to, asset_0.clone(), amount_0)?;
 	transfer_tokens::<T>(&contract, to, asset_1.clone(), amount_1)?;
@@ -109,35 +213,47 @@ pub(crate) fn burn<T: Config>(to: &T::AccountId, caller: T::AccountId) -> Dispat
 	let balance_0 = balance_of::<T>(&contract, asset_0);
 	let balance_1 = balance_of::<T>(&contract, asset_1);
 
-	_update::<T>(balance_0, balance_1, reserve_0, reserve_1);
+	_update::<T>(pool, balance_0, balance_1, reserve_0, reserve_1)?;
 
 	if fee_on {
-		let k_last = reserve_0.saturating_mul(reserve_1);
-		<KLast<T>>::put(k_last);
+		let k_last = curve.invariant(reserve_0, reserve_1)?;
+		<KLast<T>>::insert(pool, k_last);
 	}
 
 	<Pallet<T>>::deposit_event(Event::<T>::Burn {
+		pool: pool_assets::<T>(pool)?,
 		sender: caller,
 		to: to.clone(),
-		amount_0,
-		amount_1,
+		amounts: bounded::<T, _>(vec![amount_0, amount_1])?,
 	});
 
-	Ok(())
+	Ok((amount_0, amount_1))
 }
 
 pub(crate) fn _swap<T: Config>(
+	pool: PoolIdOf<T>,
+	amount_0_out: T::Balance,
+	amount_1_out: T::Balance,
+	to: &T::AccountId,
+	sender: T::AccountId,
+	data: &[u8],
+) -> DispatchResult {
+	with_lock::<T, _>(pool, || swap_locked::<T>(pool, amount_0_out, amount_1_out, to, sender, data))
+}
+
+fn swap_locked<T: Config>(
+	pool: PoolIdOf<T>,
 	amount_0_out: T::Balance,
 	amount_1_out: T::Balance,
 	to: &T::AccountId,
 	sender: T::AccountId,
+	data: &[u8],
 ) -> DispatchResult {
 	let zero = T::Balance::zero();
-	let asset_0 = T::Asset0::get();
-	let asset_1 = T::Asset1::get();
+	let (asset_0, asset_1) = pool;
 
 	ensure!(amount_0_out > zero || amount_1_out > zero, Error::<T>::InsufficientOutputAmount);
-	let (reserve_0, reserve_1, _) = reserves::<T>();
+	let (reserve_0, reserve_1, _) = reserves::<T>(pool);
 
 	ensure!(
 		amount_0_out < reserve_0 && amount_1_out < reserve_1,
@@ -145,7 +261,7 @@ pub(crate) fn _swap<T: Config>(
 	);
 
 	// optimistically transfe tokens
-	let contract = <PalletAccountId<T>>::get().unwrap();
+	let contract = pool_account::<T>(pool)?;
 
 	if amount_0_out > zero {
 		transfer_tokens::<T>(&contract, to, asset_0.clone(), amount_0_out)?;
@@ -155,6 +271,18 @@ pub(crate) fn _swap<T: Config>(
 		transfer_tokens::<T>(&contract, to, asset_1.clone(), amount_1_out)?;
 	}
 
+	if !data.is_empty() {
+		T::FlashLoanReceiver::on_flash_swap(
+			&sender,
+			to,
+			pool,
+			&contract,
+			amount_0_out,
+			amount_1_out,
+			data,
+		)?;
+	}
+
 	let balance_0 = balance_of::<T>(&contract, asset_0.clone());
 	let balance_1 = balance_of::<T>(&contract, asset_1.clone());
 
@@ -175,36 +303,30 @@ pub(crate) fn _swap<T: Config>(
 		Error::<T>::InsufficientInputAmount
 	}
 
-	let multiplier_1000 = T::Balance::from(1000u32);
-	let multiplier_3 = T::BaseFee::get();
+	// the same fee as in `Curve::amount_out` and `Curve::amount_in`
+	let fee = <SwapFees<T>>::get(pool);
 
-	let balance_0_adjusted = balance_0
-		.saturating_mul(multiplier_1000)
-		.saturating_sub(amount_0_in.saturating_mul(multiplier_3));
+	let balance_0_adjusted = balance_0.saturating_sub(fee.mul_floor(amount_0_in));
+	let balance_1_adjusted = balance_1.saturating_sub(fee.mul_floor(amount_1_in));
 
-	let balance_1_adjusted = balance_1
-		.saturating_mul(multiplier_1000)
-		.saturating_sub(amount_1_in.saturating_mul(multiplier_3));
-
-	let balance = balance_0_adjusted.saturating_mul(balance_1_adjusted);
-	let reserve = reserve_0
-		.saturating_mul(reserve_1)
-		.saturating_mul(multiplier_1000 * multiplier_1000);
-
-	ensure!(balance >= reserve, Error::<T>::InvalidK);
+	pool_curve::<T>(pool).ensure_invariant(
+		balance_0_adjusted,
+		balance_1_adjusted,
+		reserve_0,
+		reserve_1,
+	)?;
 
 	let balance_0 = balance_of::<T>(&contract, asset_0);
 	let balance_1 = balance_of::<T>(&contract, asset_1);
 
-	_update::<T>(balance_0, balance_1, reserve_0, reserve_1);
+	_update::<T>(pool, balance_0, balance_1, reserve_0, reserve_1)?;
 
 	<Pallet<T>>::deposit_event(Event::<T>::Swap {
+		pool: pool_assets::<T>(pool)?,
 		sender,
 		to: to.clone(),
-		amount_0_in,
-		amount_1_in,
-		amount_0_out,
-		amount_1_out,
+		amounts_in: bounded::<T, _>(vec![amount_0_in, amount_1_in])?,
+		amounts_out: bounded::<T, _>(vec![amount_0_out, amount_1_out])?,
 	});
 
 	Ok(())
@@ -230,13 +352,14 @@ pub(crate) fn balance_of<T: Config>(owner: &T::AccountId, asset: T::CurrencyId)
 }
 
 pub(crate) fn _update<T: Config>(
+	pool: PoolIdOf<T>,
 	balance_0: T::Balance,
 	balance_1: T::Balance,
 	reserve_0: T::Balance,
 	reserve_1: T::Balance,
-) {
+) -> Result<(), Error<T>> {
 	let zero = T::Balance::zero();
-	let (_, _, block_timestamp_last) = reserves::<T>();
+	let (_, _, block_timestamp_last) = reserves::<T>(pool);
 
 	let block_timestamp: T::Moment = pallet_timestamp::Pallet::<T>::now();
 
@@ -244,119 +367,221 @@ pub(crate) fn _update<T: Config>(
 		overflowing_sub::<T::Moment>(block_timestamp, block_timestamp_last).0,
 	);
 
-	block_timestamp
-		.checked_sub(&block_timestamp_last)
-		.map(|timestamp| T::AmmExtension::moment_to_balance_type(timestamp))
-		.unwrap_or(T::Balance::max_value()); // overflow is desired
-
-	let mutate_cumulative_price = |price: &mut T::Balance,
-	                               reserve_x: T::Balance,
-	                               reserve_y: T::Balance,
-	                               time_elapsed: T::Balance| {
-		// * never overflows, and + overflow is desired
-		let to_add = reserve_x.checked_div(&reserve_y).unwrap_or(zero).saturating_mul(time_elapsed);
-
-		*price = overflowing_add::<T::Balance>(*price, to_add).0;
-	};
-
 	if time_elapsed > zero && reserve_0 != zero && reserve_1 != zero {
-		<Price0CumulativeLast<T>>::mutate(|price| {
-			mutate_cumulative_price(price, reserve_1, reserve_0, time_elapsed);
+		let (price_0, price_1) = spot_prices::<T>(pool, reserve_0, reserve_1)?;
+
+		<Price0CumulativeLast<T>>::mutate(pool, |price| {
+			*price = accumulate_price::<T>(*price, price_0, time_elapsed);
 		});
 
-		<Price1CumulativeLast<T>>::mutate(|price| {
-			mutate_cumulative_price(price, reserve_0, reserve_1, time_elapsed);
+		<Price1CumulativeLast<T>>::mutate(pool, |price| {
+			*price = accumulate_price::<T>(*price, price_1, time_elapsed);
 		});
 	}
 
+	if time_elapsed > zero {
+		write_observation::<T>(
+			pool,
+			Observation {
+				timestamp: block_timestamp,
+				price_0_cumulative: <Price0CumulativeLast<T>>::get(pool),
+				price_1_cumulative: <Price1CumulativeLast<T>>::get(pool),
+			},
+		);
+	}
+
 	let reserve = BalanceReserves::new(balance_0, balance_1, block_timestamp);
-	<Reserves<T>>::put(reserve);
+	<Reserves<T>>::insert(pool, reserve);
+
+	<Pallet<T>>::deposit_event(Event::<T>::Sync { pool, reserve_0, reserve_1 });
+
+	Ok(())
+}
+
+/// The prices of asset 0 in asset 1 and of asset 1 in asset 0 on the curve of the pool.
+fn spot_prices<T: Config>(
+	pool: PoolIdOf<T>,
+	reserve_0: T::Balance,
+	reserve_1: T::Balance,
+) -> Result<(FixedU128, FixedU128), Error<T>> {
+	let (asset_0, asset_1) = pool;
+
+	Ok((
+		directed_curve::<T>(pool, asset_1).spot_price(reserve_1, reserve_0)?,
+		directed_curve::<T>(pool, asset_0).spot_price(reserve_0, reserve_1)?,
+	))
+}
+
+/// Adds `spot_price` for `time_elapsed` to the cumulative `price`.
+fn accumulate_price<T: Config>(
+	price: FixedU128,
+	spot_price: FixedU128,
+	time_elapsed: T::Balance,
+) -> FixedU128 {
+	// * and + overflow is desired, only the difference between two cumulative prices matters
+	let to_add = spot_price.into_inner().wrapping_mul(time_elapsed.saturated_into::<u128>());
+
+	FixedU128::from_inner(overflowing_add::<u128>(price.into_inner(), to_add).0)
+}
+
+fn write_observation<T: Config>(pool: PoolIdOf<T>, observation: Observation<T::Moment>) {
+	<Observations<T>>::mutate(pool, |observations| {
+		if observations.try_push(observation.clone()).is_err() {
+			// the buffer is full, so the oldest observation is replaced
+			let index = <ObservationIndex<T>>::get(pool);
+			if let Some(oldest) = observations.get_mut(index as usize) {
+				*oldest = observation;
+			}
+
+			let next_index = index.saturating_add(1) % T::MaxObservations::get().max(1);
+			<ObservationIndex<T>>::insert(pool, next_index);
+		}
+	});
+}
 
-	<Pallet<T>>::deposit_event(Event::<T>::Sync { reserve_0, reserve_1 });
+/// The average prices since the newest observation that is at least `window` old.
+/// The cumulative prices are brought up to date with the current reserves first,
+/// in case they were not updated in this block.
+pub(crate) fn consult<T: Config>(
+	pool: PoolIdOf<T>,
+	window: T::Moment,
+) -> Result<(FixedU128, FixedU128), Error<T>> {
+	ensure!(<Reserves<T>>::contains_key(pool), Error::<T>::PairNotFound);
+	let (reserve_0, reserve_1, block_timestamp_last) = reserves::<T>(pool);
+
+	let now = pallet_timestamp::Pallet::<T>::now();
+	let target = now.checked_sub(&window).ok_or(Error::<T>::InsufficientHistory)?;
+
+	let observation = <Observations<T>>::get(pool)
+		.into_iter()
+		.filter(|observation| observation.timestamp <= target)
+		.max_by_key(|observation| observation.timestamp)
+		.ok_or(Error::<T>::InsufficientHistory)?;
+
+	let time_elapsed = T::AmmExtension::moment_to_balance_type(now - observation.timestamp)
+		.saturated_into::<u128>();
+	ensure!(time_elapsed > 0, Error::<T>::InsufficientHistory);
+
+	let mut price_0_cumulative = <Price0CumulativeLast<T>>::get(pool);
+	let mut price_1_cumulative = <Price1CumulativeLast<T>>::get(pool);
+
+	let zero = T::Balance::zero();
+	if reserve_0 != zero && reserve_1 != zero {
+		let since_update =
+			T::AmmExtension::moment_to_balance_type(now.saturating_sub(block_timestamp_last));
+		let (price_0, price_1) = spot_prices::<T>(pool, reserve_0, reserve_1)?;
+
+		price_0_cumulative = accumulate_price::<T>(price_0_cumulative, price_0, since_update);
+		price_1_cumulative = accumulate_price::<T>(price_1_cumulative, price_1, since_update);
+	}
+
+	let average = |current: FixedU128, past: FixedU128| {
+		FixedU128::from_inner(current.into_inner().wrapping_sub(past.into_inner()) / time_elapsed)
+	};
+
+	Ok((
+		average(price_0_cumulative, observation.price_0_cumulative),
+		average(price_1_cumulative, observation.price_1_cumulative),
+	))
 }
 
-fn _mint_fee<T: Config>(reserve_0: T::Balance, reserve_1: T::Balance) -> Result<bool, Error<T>> {
+fn _mint_fee<T: Config>(
+	pool: PoolIdOf<T>,
+	curve: &PoolCurve,
+	reserve_0: T::Balance,
+	reserve_1: T::Balance,
+) -> Result<bool, DispatchError> {
 	let zero = T::Balance::zero();
-	let k_last = <KLast<T>>::get();
+	let k_last = <KLast<T>>::get(pool);
 
 	match <FeeTo<T>>::get() {
 		Some(fee_to) => {
-			if k_last != zero {
-				let root_k = {
-					let to_sqrt = reserve_0.saturating_mul(reserve_1);
-					to_sqrt.integer_sqrt()
-				};
-
-				let root_k_last = k_last.integer_sqrt();
+			if !k_last.is_zero() {
+				let root_k = from_u256::<T>(curve.invariant(reserve_0, reserve_1)?.integer_sqrt())?;
+				let root_k_last = from_u256::<T>(k_last.integer_sqrt())?;
 
 				if root_k > root_k_last {
-					let total_supply = <TotalSupply<T>>::get();
+					let total_supply = <Pallet<T>>::total_supply(pool);
 
 					let sub_k_last = root_k.saturating_sub(root_k_last);
-					let numerator = total_supply.saturating_mul(sub_k_last);
+					let denominator = root_k
+						.checked_mul(&T::MintFee::get())
+						.and_then(|root_k| root_k.checked_add(&root_k_last))
+						.ok_or(Error::<T>::Overflow)?;
 
-					let denominator =
-						root_k.saturating_mul(T::MintFee::get()).saturating_add(root_k_last);
-
-					let liquidity = numerator.checked_div(&denominator).unwrap_or(zero);
+					let liquidity = mul_div::<T>(total_supply, sub_k_last, denominator)?;
 
 					if liquidity > zero {
-						_mint::<T>(&fee_to, liquidity);
+						_mint::<T>(pool.into(), &fee_to, liquidity)?;
 					}
 				}
 			}
 			Ok(true)
 		},
 		None => {
-			if k_last != zero {
-				<KLast<T>>::put(zero);
+			if !k_last.is_zero() {
+				<KLast<T>>::remove(pool);
 			}
 			Ok(false)
 		},
 	}
 }
 
-fn _mint<T: Config>(to: &T::AccountId, value: T::Balance) {
-	<TotalSupply<T>>::mutate(|v| {
+pub(crate) fn _mint<T: Config>(
+	pool: LpTokenOf<T>,
+	to: &T::AccountId,
+	value: T::Balance,
+) -> DispatchResult {
+	T::LpTokens::mint(&pool, to, value)?;
+
+	<TotalSupply<T>>::mutate(&pool, |v| {
 		*v = v.saturating_add(value);
 	});
 
-	let prev_bal = <LpBalances<T>>::get(to).unwrap_or(T::Balance::zero());
-	<LpBalances<T>>::insert(to.clone(), prev_bal.saturating_add(value));
+	<Pallet<T>>::deposit_event(Event::<T>::Transfer {
+		pool,
+		from: None,
+		to: Some(to.clone()),
+		value,
+	});
 
-	<Pallet<T>>::deposit_event(Event::<T>::Transfer { from: None, to: Some(to.clone()), value })
+	Ok(())
 }
 
-fn _burn<T: Config>(from: &T::AccountId, value: T::Balance) -> FuncResult<T> {
-	let prev_bal = <LpBalances<T>>::get(from).ok_or(Error::<T>::Forbidden)?;
+pub(crate) fn _burn<T: Config>(
+	pool: LpTokenOf<T>,
+	from: &T::AccountId,
+	value: T::Balance,
+) -> DispatchResult {
+	T::LpTokens::burn(&pool, from, value)?;
 
-	<TotalSupply<T>>::mutate(|v| {
+	<TotalSupply<T>>::mutate(&pool, |v| {
 		*v = v.saturating_sub(value);
 	});
 
-	<LpBalances<T>>::insert(from.clone(), prev_bal.saturating_sub(value));
-
-	<Pallet<T>>::deposit_event(Event::<T>::Transfer { from: Some(from.clone()), to: None, value });
+	<Pallet<T>>::deposit_event(Event::<T>::Transfer {
+		pool,
+		from: Some(from.clone()),
+		to: None,
+		value,
+	});
 
 	Ok(())
 }
 
 pub(crate) fn _transfer_liquidity<T: Config>(
+	pool: LpTokenOf<T>,
 	from: T::AccountId,
 	to: T::AccountId,
 	amount: T::Balance,
-) -> FuncResult<T> {
-	let zero = T::Balance::zero();
-
-	let from_balance = <LpBalances<T>>::get(&from).unwrap_or(zero);
+) -> DispatchResult {
+	let from_balance = T::LpTokens::balance(&pool, &from);
 	ensure!(from_balance >= amount, Error::<T>::InsufficientBalance);
 
-	<LpBalances<T>>::insert(from.clone(), from_balance.saturating_sub(amount));
-
-	let to_balance = <LpBalances<T>>::get(&to).unwrap_or(zero);
-	<LpBalances<T>>::insert(to.clone(), to_balance.saturating_add(amount));
+	T::LpTokens::transfer(&pool, &from, &to, amount)?;
 
 	<Pallet<T>>::deposit_event(Event::<T>::Transfer {
+		pool,
 		from: Some(from),
 		to: Some(to),
 		value: amount,
@@ -365,56 +590,321 @@ pub(crate) fn _transfer_liquidity<T: Config>(
 	Ok(())
 }
 
-pub(crate) fn _get_amount_out<T: Config>(
+pub(crate) fn _approve<T: Config>(
+	pool: LpTokenOf<T>,
+	owner: T::AccountId,
+	spender: T::AccountId,
+	value: T::Balance,
+) {
+	<Allowances<T>>::insert((&pool, &owner, &spender), value);
+
+	<Pallet<T>>::deposit_event(Event::<T>::Approval { pool, owner, spender, value });
+}
+
+/// Fails if the pool or basket of the LP token does not exist.
+pub(crate) fn ensure_lp_token_exists<T: Config>(token: &LpTokenOf<T>) -> FuncResult<T> {
+	match token {
+		LpToken::Pool(asset_0, asset_1) => {
+			ensure!(<Reserves<T>>::contains_key((*asset_0, *asset_1)), Error::<T>::PairNotFound)
+		},
+		LpToken::Basket(pool) => {
+			ensure!(<BasketReserves<T>>::contains_key(pool), Error::<T>::BasketNotFound)
+		},
+	}
+
+	Ok(())
+}
+
+/// The current amplification of a StableSwap pool, `None` for a constant product pool.
+pub(crate) fn amplification<T: Config>(pool: PoolIdOf<T>) -> Option<u32> {
+	<Amplifications<T>>::get(pool)
+		.map(|amplification| amplification.at(pallet_timestamp::Pallet::<T>::now()))
+}
+
+/// The curve of the pool: weighted if it has weights, StableSwap if it has an amplification
+/// and constant product otherwise.
+pub(crate) fn pool_curve<T: Config>(pool: PoolIdOf<T>) -> PoolCurve {
+	if let Some(weight_0) = <Weights<T>>::get(pool) {
+		return PoolCurve::Weighted(Weighted::new(weight_0))
+	}
+
+	match amplification::<T>(pool) {
+		Some(amplification) => PoolCurve::StableSwap(StableSwap { amplification }),
+		None => PoolCurve::ConstantProduct(ConstantProduct),
+	}
+}
+
+/// The curve of the pool for swaps of `asset_in`.
+pub(crate) fn directed_curve<T: Config>(pool: PoolIdOf<T>, asset_in: T::CurrencyId) -> PoolCurve {
+	let curve = pool_curve::<T>(pool);
+
+	if asset_in == pool.0 {
+		curve
+	} else {
+		curve.reversed()
+	}
+}
+
+/// The amount received for `amount_in` of `asset_in` from the pool, on its curve and after
+/// its swap fee.
+pub(crate) fn pool_amount_out<T: Config>(
+	pool: PoolIdOf<T>,
+	asset_in: T::CurrencyId,
 	amount_in: T::Balance,
 	reserve_in: T::Balance,
 	reserve_out: T::Balance,
 ) -> Result<T::Balance, Error<T>> {
-	let zero = T::Balance::zero();
+	directed_curve::<T>(pool, asset_in).amount_out(
+		amount_in,
+		reserve_in,
+		reserve_out,
+		<SwapFees<T>>::get(pool),
+	)
+}
 
-	ensure!(amount_in > zero, Error::<T>::InsufficientInputAmount);
+/// The amount of `asset_in` needed to receive `amount_out` from the pool, on its curve and
+/// including its swap fee.
+pub(crate) fn pool_amount_in<T: Config>(
+	pool: PoolIdOf<T>,
+	asset_in: T::CurrencyId,
+	amount_out: T::Balance,
+	reserve_in: T::Balance,
+	reserve_out: T::Balance,
+) -> Result<T::Balance, Error<T>> {
+	directed_curve::<T>(pool, asset_in).amount_in(
+		amount_out,
+		reserve_in,
+		reserve_out,
+		<SwapFees<T>>::get(pool),
+	)
+}
 
-	ensure!(reserve_in > zero && reserve_out > zero, Error::<T>::InsufficientLiquidity);
+/// The pool of two assets, which are sorted like in `create_pool`.
+pub(crate) fn sorted_pool<T: Config>(
+	asset_a: T::CurrencyId,
+	asset_b: T::CurrencyId,
+) -> Result<PoolIdOf<T>, Error<T>> {
+	ensure!(asset_a != asset_b, Error::<T>::IdenticalAddress);
 
-	let amount_in_with_fee = {
-		let sub_fee = T::Balance::from(997u32);
-		amount_in.saturating_mul(sub_fee)
-	};
-	let multiplier_1000 = T::Balance::from(1000u32);
+	Ok(if asset_a < asset_b { (asset_a, asset_b) } else { (asset_b, asset_a) })
+}
+
+/// The assets of a pool of two assets, as they are given in the events shared with baskets.
+pub(crate) fn pool_assets<T: Config>(pool: PoolIdOf<T>) -> Result<AssetsOf<T>, Error<T>> {
+	bounded::<T, _>(vec![pool.0, pool.1])
+}
+
+/// A value per asset of a pool, fails if there are more than `MaxAssetsPerPool`.
+pub(crate) fn bounded<T: Config, V>(
+	values: Vec<V>,
+) -> Result<BoundedVec<V, T::MaxAssetsPerPool>, Error<T>> {
+	values.try_into().map_err(|_| Error::<T>::InvalidAssetCount)
+}
+
+/// The pool of one hop of a path, with its reserves of `asset_in` and `asset_out`.
+fn hop_reserves<T: Config>(
+	asset_in: T::CurrencyId,
+	asset_out: T::CurrencyId,
+) -> Result<(PoolIdOf<T>, T::Balance, T::Balance), Error<T>> {
+	let pool = sorted_pool::<T>(asset_in, asset_out)?;
+	let reserves = <Reserves<T>>::get(pool).ok_or(Error::<T>::PairNotFound)?;
+
+	if asset_in == pool.0 {
+		Ok((pool, reserves.reserve_0, reserves.reserve_1))
+	} else {
+		Ok((pool, reserves.reserve_1, reserves.reserve_0))
+	}
+}
+
+/// The amounts of every asset of `path` when swapping exactly `amount_in` of the first one.
+pub(crate) fn get_amounts_out<T: Config>(
+	amount_in: T::Balance,
+	path: &[T::CurrencyId],
+) -> Result<Vec<T::Balance>, Error<T>> {
+	ensure!(path.len() >= 2, Error::<T>::InvalidPath);
+
+	let mut amounts = Vec::with_capacity(path.len());
+	amounts.push(amount_in);
+
+	for hop in path.windows(2) {
+		let (pool, reserve_in, reserve_out) = hop_reserves::<T>(hop[0], hop[1])?;
+		let amount = amounts[amounts.len() - 1];
 
-	let numerator = amount_in_with_fee.saturating_mul(reserve_out);
-	let denominator = reserve_in.saturating_mul(multiplier_1000).saturating_add(amount_in_with_fee);
+		amounts.push(pool_amount_out::<T>(pool, hop[0], amount, reserve_in, reserve_out)?);
+	}
 
-	Ok(numerator.checked_div(&denominator).unwrap_or(T::Balance::zero()))
+	Ok(amounts)
 }
 
-pub(crate) fn get_amount_in<T: Config>(
+/// The amounts of every asset of `path` when swapping for exactly `amount_out` of the last one.
+pub(crate) fn get_amounts_in<T: Config>(
 	amount_out: T::Balance,
+	path: &[T::CurrencyId],
+) -> Result<Vec<T::Balance>, Error<T>> {
+	ensure!(path.len() >= 2, Error::<T>::InvalidPath);
+
+	let mut amounts = vec![T::Balance::zero(); path.len()];
+	amounts[path.len() - 1] = amount_out;
+
+	for i in (1..path.len()).rev() {
+		let (pool, reserve_in, reserve_out) = hop_reserves::<T>(path[i - 1], path[i])?;
+
+		amounts[i - 1] =
+			pool_amount_in::<T>(pool, path[i - 1], amounts[i], reserve_in, reserve_out)?;
+	}
+
+	Ok(amounts)
+}
+
+/// Swaps the `amounts` along `path`. The output of every hop is sent to the pool of the next one,
+/// and the output of the last hop to `to`.
+pub(crate) fn _swap_along_path<T: Config>(
+	amounts: &[T::Balance],
+	path: &[T::CurrencyId],
+	to: &T::AccountId,
+	sender: T::AccountId,
+) -> DispatchResult {
+	let first_pool = sorted_pool::<T>(path[0], path[1])?;
+	transfer_tokens::<T>(&sender, &pool_account::<T>(first_pool)?, path[0], amounts[0])?;
+
+	for (i, hop) in path.windows(2).enumerate() {
+		let pool = sorted_pool::<T>(hop[0], hop[1])?;
+		let amount_out = amounts[i + 1];
+
+		let (amount_0_out, amount_1_out) = if hop[0] == pool.0 {
+			(T::Balance::zero(), amount_out)
+		} else {
+			(amount_out, T::Balance::zero())
+		};
+
+		let recipient = match path.get(i + 2) {
+			Some(next) => pool_account::<T>(sorted_pool::<T>(hop[1], *next)?)?,
+			None => to.clone(),
+		};
+
+		_swap::<T>(pool, amount_0_out, amount_1_out, &recipient, sender.clone(), &[])?;
+	}
+
+	Ok(())
+}
+
+/// The part of `amount_in` of `asset_in` to swap in the pool before depositing, so that the
+/// swapped amount and the rest of `amount_in` match the ratio of the reserves after the swap.
+/// Solves `(amount_in - s) / (reserve_in + s) = amount_out(s) / (reserve_out - amount_out(s))`
+/// for `s` on the curve of the pool, with its swap fee applied to the swap.
+pub(crate) fn zap_swap_amount<T: Config>(
+	pool: PoolIdOf<T>,
+	asset_in: T::CurrencyId,
+	amount_in: T::Balance,
 	reserve_in: T::Balance,
 	reserve_out: T::Balance,
 ) -> Result<T::Balance, Error<T>> {
-	let zero = T::Balance::zero();
+	let fee = <SwapFees<T>>::get(pool);
+
+	let curve = match directed_curve::<T>(pool, asset_in) {
+		PoolCurve::ConstantProduct(_) =>
+			return constant_product_zap_amount::<T>(amount_in, reserve_in, fee),
+		curve => curve,
+	};
 
-	if amount_out <= zero {
-		return Err(Error::<T>::InsufficientOutputAmount)
+	// the rest of `amount_in` shrinks and `amount_out(s)` grows with `s`, so the largest `s`
+	// which leaves enough of `amount_in` for `amount_out(s)` is found by bisection
+	let mut low = T::Balance::zero();
+	let mut high = amount_in;
+	while low < high {
+		let mid = high - (high - low) / T::Balance::from(2u32);
+
+		if zap_leaves_enough::<T>(&curve, mid, amount_in, reserve_in, reserve_out, fee)? {
+			low = mid;
+		} else {
+			high = mid - T::Balance::one();
+		}
 	}
 
-	if reserve_in <= zero || reserve_out <= zero {
-		return Err(Error::<T>::InsufficientLiquidity)
+	Ok(low)
+}
+
+/// Whether the rest of `amount_in` after swapping `swap_amount` is at least what has to be
+/// deposited with the swapped amount. Swaps larger than the curve allows leave too little.
+fn zap_leaves_enough<T: Config>(
+	curve: &PoolCurve,
+	swap_amount: T::Balance,
+	amount_in: T::Balance,
+	reserve_in: T::Balance,
+	reserve_out: T::Balance,
+	fee: Permill,
+) -> Result<bool, Error<T>> {
+	let amount_out = Curve::<T>::amount_out(curve, swap_amount, reserve_in, reserve_out, fee);
+	let amount_out = match amount_out {
+		Err(Error::<T>::SwapTooLarge) => return Ok(false),
+		amount_out => amount_out?,
+	};
+
+	let reserve_in = reserve_in.checked_add(&swap_amount).ok_or(Error::<T>::Overflow)?;
+	let reserve_out =
+		reserve_out.checked_sub(&amount_out).ok_or(Error::<T>::InsufficientLiquidity)?;
+
+	Ok(product::<T>(amount_in - swap_amount, reserve_out) >= product::<T>(amount_out, reserve_in))
+}
+
+/// `zap_swap_amount` of a constant product pool, which has a closed form.
+fn constant_product_zap_amount<T: Config>(
+	amount_in: T::Balance,
+	reserve_in: T::Balance,
+	fee: Permill,
+) -> Result<T::Balance, Error<T>> {
+	let accuracy = U256::from(Permill::ACCURACY);
+	let fee_factor = U256::from(fee.left_from_one().deconstruct());
+	let fee_factor_sum = accuracy + fee_factor;
+
+	let b = to_u256::<T>(reserve_in) * fee_factor_sum;
+	let to_sqrt = b
+		.checked_mul(b)
+		.and_then(|b_squared| {
+			product::<T>(amount_in, reserve_in)
+				.checked_mul(fee_factor * accuracy * U256::from(4u32))
+				.and_then(|c| b_squared.checked_add(c))
+		})
+		.ok_or(Error::<T>::Overflow)?;
+
+	let numerator = to_sqrt.integer_sqrt().saturating_sub(b);
+	let denominator = fee_factor * U256::from(2u32);
+	if denominator.is_zero() {
+		return Ok(T::Balance::zero())
+	}
+
+	from_u256::<T>(numerator / denominator)
+}
+
+/// The amounts to deposit so that the ratio of the reserves is kept,
+/// without exceeding the desired amounts or falling below the minimum amounts.
+pub(crate) fn optimal_liquidity_amounts<T: Config>(
+	reserve_0: T::Balance,
+	reserve_1: T::Balance,
+	amount_0_desired: T::Balance,
+	amount_1_desired: T::Balance,
+	amount_0_min: T::Balance,
+	amount_1_min: T::Balance,
+) -> Result<(T::Balance, T::Balance), Error<T>> {
+	let zero = T::Balance::zero();
+
+	if reserve_0 == zero && reserve_1 == zero {
+		return Ok((amount_0_desired, amount_1_desired))
 	}
 
-	let sub_fee = T::Balance::from(997u32);
-	let multiplier_1000 = T::Balance::from(1000u32);
+	let amount_1_optimal = quote::<T>(amount_0_desired, reserve_0, reserve_1)?;
+	if amount_1_optimal <= amount_1_desired {
+		ensure!(amount_1_optimal >= amount_1_min, Error::<T>::SlippageExceeded);
+		return Ok((amount_0_desired, amount_1_optimal))
+	}
 
-	let numerator = reserve_in
-		.saturating_mul(reserve_out)
-		.saturating_mul(multiplier_1000);
-	let denominator = reserve_out.saturating_sub(amount_out).saturating_mul(sub_fee);
+	let amount_0_optimal = quote::<T>(amount_1_desired, reserve_1, reserve_0)?;
+	ensure!(
+		amount_0_optimal <= amount_0_desired && amount_0_optimal >= amount_0_min,
+		Error::<T>::SlippageExceeded
+	);
 
-	Ok(numerator
-		.checked_div(&denominator)
-		.map(|res| res.saturating_add(T::Balance::one()))
-		.unwrap_or(T::Balance::zero()))
+	Ok((amount_0_optimal, amount_1_desired))
 }
 
 pub(crate) fn quote<T: Config>(
@@ -428,13 +918,54 @@ pub(crate) fn quote<T: Config>(
 
 	ensure!(reserve_a > zero && reserve_b > zero, Error::<T>::InsufficientLiquidity);
 
-	let amount_b = amount_a
-		.saturating_mul(reserve_b)
-		.checked_div(&reserve_a)
-		.unwrap_or(T::Balance::zero());
+	let amount_b = mul_div::<T>(amount_a, reserve_b, reserve_a)?;
 	Ok(amount_b)
 }
 
+/// Widens a balance, so that the product of two balances cannot overflow.
+pub(crate) fn to_u256<T: Config>(value: T::Balance) -> U256 {
+	U256::from(value.saturated_into::<u128>())
+}
+
+/// Narrows an intermediate result back to a balance.
+pub(crate) fn from_u256<T: Config>(value: U256) -> Result<T::Balance, Error<T>> {
+	let value: u128 = value.try_into().map_err(|_| Error::<T>::Overflow)?;
+	value.try_into().map_err(|_| Error::<T>::Overflow)
+}
+
+/// The product of two balances, like `k = reserve_0 * reserve_1`.
+pub(crate) fn product<T: Config>(a: T::Balance, b: T::Balance) -> U256 {
+	// cannot overflow, as a balance has at most 128 bits
+	to_u256::<T>(a).saturating_mul(to_u256::<T>(b))
+}
+
+/// `a * b / c`, rounded down, without overflowing in the product. Zero if `c` is zero.
+pub(crate) fn mul_div<T: Config>(
+	a: T::Balance,
+	b: T::Balance,
+	c: T::Balance,
+) -> Result<T::Balance, Error<T>> {
+	product::<T>(a, b)
+		.checked_div(to_u256::<T>(c))
+		.map_or(Ok(T::Balance::zero()), from_u256::<T>)
+}
+
+/// `a * b / c`, rounded up, without overflowing in the product. Zero if `c` is zero.
+pub(crate) fn mul_div_ceil<T: Config>(
+	a: T::Balance,
+	b: T::Balance,
+	c: T::Balance,
+) -> Result<T::Balance, Error<T>> {
+	let c = to_u256::<T>(c);
+	if c.is_zero() {
+		return Ok(T::Balance::zero())
+	}
+
+	let (quotient, remainder) = product::<T>(a, b).div_mod(c);
+	let quotient = if remainder.is_zero() { quotient } else { quotient + U256::one() };
+	from_u256::<T>(quotient)
+}
+
 fn overflowing_add<Integer>(augend: Integer, addend: Integer) -> (Integer, bool)
 where
 	Integer: Bounded + One + CheckedAdd + Add + Sub<Output = Integer>,
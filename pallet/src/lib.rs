@@ -2,8 +2,16 @@
 
 pub use pallet::*;
 
+mod basket;
 mod helper;
 
+pub mod oracle;
+pub use oracle::AmmOracle;
+
+pub mod curve;
+pub mod stable;
+pub mod weighted;
+
 #[cfg(test)]
 mod tests;
 
@@ -22,7 +30,10 @@ use codec::{Codec, Decode, Encode, MaxEncodedLen};
 use frame_support::dispatch::DispatchResult;
 use scale_info::TypeInfo;
 
-use sp_runtime::traits::{AtLeast32BitUnsigned, Zero};
+use sp_runtime::{
+	traits::{AtLeast32BitUnsigned, Saturating, Zero},
+	DispatchError,
+};
 use sp_std::marker::PhantomData;
 
 #[frame_support::pallet]
@@ -30,15 +41,19 @@ pub mod pallet {
 	use helper::*;
 
 	use super::*;
+	use crate::curve::Curve;
 
-	use frame_support::{ensure, pallet_prelude::*};
+	use frame_support::{ensure, pallet_prelude::*, transactional, PalletId};
 	use frame_system::{ensure_signed, pallet_prelude::*};
-	use sp_runtime::traits::{CheckedSub, IntegerSquareRoot};
-	use sp_std::fmt::Debug;
+	use sp_core::U256;
+	use sp_runtime::{
+		traits::{Bounded, CheckedSub, IntegerSquareRoot, SaturatedConversion},
+		FixedU128, PerThing, Permill,
+	};
+	use sp_std::{fmt::Debug, vec::Vec};
 	// use substrate_stellar_sdk as stellar;
 
 	/// Configure the pallet by specifying the parameters and types on which it depends.
-	// todo: move to config, the assets.
 	#[pallet::config]
 	pub trait Config: frame_system::Config + pallet_timestamp::Config {
 		/// Because this pallet emits events, it depends on the runtime's definition of an event.
@@ -74,11 +89,21 @@ pub mod pallet {
 			Self::Moment,
 		>;
 
+		/// Keeps the LP token balances, e.g. `LpBalancesStorage` for the `LpBalances` storage.
+		type LpTokens: LpTokens<Self::AccountId, LpTokenOf<Self>, Self::Balance>;
+
+		/// Called by `flash_swap` to pay for the assets sent out.
+		type FlashLoanReceiver: FlashLoanReceiver<Self::AccountId, Self::CurrencyId, Self::Balance>;
+
 		// type AddressConversion: StaticLookup<
 		//     Source = <Self as frame_system::Config>::AccountId,
 		//     Target = stellar::PublicKey,
 		// >;
 
+		/// The id from which the accounts holding the reserves of the pools are derived.
+		#[pallet::constant]
+		type PalletId: Get<PalletId>;
+
 		#[pallet::constant]
 		type MinimumLiquidity: Get<Self::Balance>;
 
@@ -88,57 +113,108 @@ pub mod pallet {
 		#[pallet::constant]
 		type MintFee: Get<Self::Balance>;
 
-		// a value to multiply to, in the `swap` func.
-		// expected value is 3
+		/// The swap fee of pools without a fee set by `set_swap_fee`.
+		/// The expected value is 0.3%.
+		#[pallet::constant]
+		type DefaultSwapFee: Get<Permill>;
+
+		/// The origin allowed to set the swap fee of a pool and to force `fee_to`.
+		type AdminOrigin: EnsureOrigin<Self::Origin>;
+
+		/// The number of cumulative price observations kept per pool for `consult`.
+		#[pallet::constant]
+		type MaxObservations: Get<u32>;
+
+		/// The maximum number of assets in the path of a multi-hop swap.
 		#[pallet::constant]
-		type BaseFee: Get<Self::Balance>;
+		type MaxPathLength: Get<u32>;
 
+		/// The maximum length of the `data` passed to `T::FlashLoanReceiver` by a flash swap.
 		#[pallet::constant]
-		type Asset0: Get<Self::CurrencyId>;
+		type MaxFlashDataLen: Get<u32>;
 
+		/// The minimum duration of a ramp of the amplification of a StableSwap pool.
 		#[pallet::constant]
-		type Asset1: Get<Self::CurrencyId>;
+		type MinRampTime: Get<Self::Moment>;
+
+		/// The maximum number of assets of a basket pool. Has to be at least 2.
+		#[pallet::constant]
+		type MaxAssetsPerPool: Get<u32>;
 	}
 
+	/// A pool is identified by its two assets, sorted in ascending order.
+	pub type PoolIdOf<T> = (<T as Config>::CurrencyId, <T as Config>::CurrencyId);
+
+	/// A basket pool is identified by its assets, sorted in ascending order.
+	/// Events also identify pools of two assets by their assets.
+	pub type AssetsOf<T> = BoundedVec<<T as Config>::CurrencyId, <T as Config>::MaxAssetsPerPool>;
+
+	/// An amount of every asset of a pool, in the order of its assets.
+	pub type AmountsOf<T> = BoundedVec<<T as Config>::Balance, <T as Config>::MaxAssetsPerPool>;
+
+	/// The LP token of a pool or a basket, see `LpToken`.
+	pub type LpTokenOf<T> = LpToken<<T as Config>::CurrencyId, AssetsOf<T>>;
+
 	pub trait WeightInfo {
+		fn create_pool() -> Weight;
+		fn create_stable_pool() -> Weight;
+		fn ramp_amplification() -> Weight;
+		fn create_weighted_pool() -> Weight;
 		fn set_fee_to() -> Weight;
+		fn set_fee_to_setter() -> Weight;
+		fn force_set_fee_to() -> Weight;
+		fn set_swap_fee() -> Weight;
+		fn transfer_lp() -> Weight;
+		fn approve_lp() -> Weight;
+		fn transfer_lp_from() -> Weight;
 		fn skim() -> Weight;
 		fn sync() -> Weight;
 		fn deposit_asset_1() -> Weight;
 		fn deposit_asset_2() -> Weight;
+		fn add_liquidity() -> Weight;
 		fn withdraw() -> Weight;
+		fn remove_liquidity() -> Weight;
+		fn zap_in() -> Weight;
+		fn zap_out() -> Weight;
 		fn swap_asset_1_for_asset_2() -> Weight;
 		fn swap_asset_2_for_asset_1() -> Weight;
+		fn swap_exact_in_asset_1_for_asset_2() -> Weight;
+		fn swap_exact_in_asset_2_for_asset_1() -> Weight;
+		fn swap_exact_in_along_path(n: u32) -> Weight;
+		fn swap_exact_out_along_path(n: u32) -> Weight;
+		fn flash_swap() -> Weight;
+		fn create_basket() -> Weight;
+		fn join_basket(n: u32) -> Weight;
+		fn exit_basket(n: u32) -> Weight;
+		fn join_basket_single() -> Weight;
+		fn exit_basket_single() -> Weight;
+		fn swap_basket() -> Weight;
+		fn set_basket_swap_fee() -> Weight;
 	}
 
 	#[pallet::genesis_config]
 	pub struct GenesisConfig<T: Config> {
-		pub contract_id: Option<T::AccountId>,
-		pub zero_account: Option<T::AccountId>,
 		pub fee_to_setter: Option<T::AccountId>,
+		pub pools: Vec<(T::CurrencyId, T::CurrencyId)>,
 	}
 
 	#[cfg(feature = "std")]
 	impl<T: Config> Default for GenesisConfig<T> {
 		fn default() -> Self {
-			Self { contract_id: None, zero_account: None, fee_to_setter: None }
+			Self { fee_to_setter: None, pools: vec![] }
 		}
 	}
 
 	#[pallet::genesis_build]
 	impl<T: Config> GenesisBuild<T> for GenesisConfig<T> {
 		fn build(&self) {
-			if let Some(contract) = &self.contract_id {
-				<PalletAccountId<T>>::put(contract.clone());
-			}
-
-			if let Some(address_zero) = &self.zero_account {
-				<AddressZero<T>>::put(address_zero.clone());
-			}
-
 			if let Some(fee_to_setter) = &self.fee_to_setter {
 				<FeeToSetter<T>>::put(fee_to_setter.clone());
 			}
+
+			for (asset_a, asset_b) in &self.pools {
+				_create_pool::<T>(*asset_a, *asset_b).expect("invalid pool in genesis config");
+			}
 		}
 	}
 
@@ -146,6 +222,13 @@ pub mod pallet {
 	#[pallet::generate_store(pub(super) trait Store)]
 	pub struct Pallet<T>(_);
 
+	#[pallet::hooks]
+	impl<T: Config> Hooks<BlockNumberFor<T>> for Pallet<T> {
+		fn integrity_test() {
+			assert!(T::MaxAssetsPerPool::get() >= 2, "a pool has at least two assets");
+		}
+	}
+
 	#[derive(Debug, Clone, Encode, Decode, Eq, PartialEq, Default, MaxEncodedLen, TypeInfo)]
 	#[cfg_attr(feature = "std", derive(Serialize, Deserialize))]
 	pub(super) struct BalanceReserves<Balance, Moment> {
@@ -164,43 +247,118 @@ pub mod pallet {
 		}
 	}
 
-	#[pallet::type_value]
-	pub(super) fn ReservesDefault<T: Config>() -> BalanceReserves<T::Balance, T::Moment> {
-		BalanceReserves {
-			reserve_0: T::Balance::zero(),
-			reserve_1: T::Balance::zero(),
-			block_timestamp_last: T::Moment::zero(),
+	/// The LP token of a pool of two assets or of a basket. A basket of two assets has its own
+	/// LP token, apart from that of the pool of the same assets.
+	#[derive(Debug, Clone, Encode, Decode, Eq, PartialEq, MaxEncodedLen, TypeInfo)]
+	pub enum LpToken<CurrencyId, Assets> {
+		Pool(CurrencyId, CurrencyId),
+		Basket(Assets),
+	}
+
+	impl<CurrencyId, Assets> From<(CurrencyId, CurrencyId)> for LpToken<CurrencyId, Assets> {
+		fn from((asset_0, asset_1): (CurrencyId, CurrencyId)) -> Self {
+			LpToken::Pool(asset_0, asset_1)
 		}
 	}
 
-	#[pallet::storage]
-	pub(super) type Reserves<T: Config> =
-		StorageValue<_, BalanceReserves<T::Balance, T::Moment>, ValueQuery, ReservesDefault<T>>;
+	/// The cumulative prices of a pool at a point in time.
+	#[derive(Debug, Clone, Encode, Decode, Eq, PartialEq, Default, MaxEncodedLen, TypeInfo)]
+	pub struct Observation<Moment> {
+		pub timestamp: Moment,
+		pub price_0_cumulative: FixedU128,
+		pub price_1_cumulative: FixedU128,
+	}
 
-	pub fn reserves<T: Config>() -> (T::Balance, T::Balance, T::Moment) {
-		let res = <Reserves<T>>::get();
+	/// The amplification of a StableSwap pool, which moves linearly from `initial` at
+	/// `initial_time` to `future` at `future_time`.
+	#[derive(Debug, Clone, Encode, Decode, Eq, PartialEq, Default, MaxEncodedLen, TypeInfo)]
+	pub struct Amplification<Moment> {
+		pub initial: u32,
+		pub future: u32,
+		pub initial_time: Moment,
+		pub future_time: Moment,
+	}
 
-		(res.reserve_0, res.reserve_1, res.block_timestamp_last)
+	impl<Moment: AtLeast32BitUnsigned + Copy> Amplification<Moment> {
+		/// The amplification at the time `now`.
+		pub fn at(&self, now: Moment) -> u32 {
+			if now >= self.future_time || self.future_time <= self.initial_time {
+				return self.future
+			}
+
+			let elapsed = now.saturating_sub(self.initial_time).saturated_into::<u128>();
+			let duration = (self.future_time - self.initial_time).saturated_into::<u128>();
+			let (initial, future) = (u128::from(self.initial), u128::from(self.future));
+
+			let amplification = if future > initial {
+				initial + (future - initial) * elapsed / duration
+			} else {
+				initial - (initial - future) * elapsed / duration
+			};
+			amplification.saturated_into()
+		}
 	}
 
-	#[pallet::type_value]
-	pub(super) fn ZeroDefault<T: Config>() -> T::Balance {
-		T::Balance::zero()
+	#[pallet::storage]
+	pub(super) type Reserves<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		PoolIdOf<T>,
+		BalanceReserves<T::Balance, T::Moment>,
+		OptionQuery,
+	>;
+
+	pub fn reserves<T: Config>(pool: PoolIdOf<T>) -> (T::Balance, T::Balance, T::Moment) {
+		let res = <Reserves<T>>::get(pool).unwrap_or_default();
+
+		(res.reserve_0, res.reserve_1, res.block_timestamp_last)
 	}
 
+	/// The price of Asset1 in Asset2, summed up for every millisecond since the pool was created.
+	/// Only the difference between two readings is meaningful, since the sum wraps on overflow.
 	#[pallet::storage]
 	#[pallet::getter(fn price_0_cumulative_last)]
 	pub(super) type Price0CumulativeLast<T: Config> =
-		StorageValue<_, T::Balance, ValueQuery, ZeroDefault<T>>;
+		StorageMap<_, Blake2_128Concat, PoolIdOf<T>, FixedU128, ValueQuery>;
 
+	/// The price of Asset2 in Asset1, summed up like `Price0CumulativeLast`.
 	#[pallet::storage]
 	#[pallet::getter(fn price_1_cumulative_last)]
 	pub(super) type Price1CumulativeLast<T: Config> =
-		StorageValue<_, T::Balance, ValueQuery, ZeroDefault<T>>;
+		StorageMap<_, Blake2_128Concat, PoolIdOf<T>, FixedU128, ValueQuery>;
 
 	#[pallet::storage]
 	#[pallet::getter(fn k_last)]
-	pub(super) type KLast<T: Config> = StorageValue<_, T::Balance, ValueQuery, ZeroDefault<T>>;
+	pub(super) type KLast<T: Config> =
+		StorageMap<_, Blake2_128Concat, PoolIdOf<T>, U256, ValueQuery>;
+
+	/// The last `MaxObservations` cumulative prices of the pool, at most one per timestamp.
+	/// Once full, the oldest observation is overwritten.
+	#[pallet::storage]
+	#[pallet::getter(fn observations)]
+	pub type Observations<T: Config> = StorageMap<
+		_,
+		Blake2_128Concat,
+		PoolIdOf<T>,
+		BoundedVec<Observation<T::Moment>, T::MaxObservations>,
+		ValueQuery,
+	>;
+
+	/// The position in `Observations` that is overwritten next.
+	#[pallet::storage]
+	pub(super) type ObservationIndex<T: Config> =
+		StorageMap<_, Blake2_128Concat, PoolIdOf<T>, u32, ValueQuery>;
+
+	#[pallet::type_value]
+	pub(super) fn SwapFeeDefault<T: Config>() -> Permill {
+		T::DefaultSwapFee::get()
+	}
+
+	/// The fee taken from the input amount of every swap in the pool.
+	#[pallet::storage]
+	#[pallet::getter(fn swap_fee)]
+	pub type SwapFees<T: Config> =
+		StorageMap<_, Blake2_128Concat, PoolIdOf<T>, Permill, ValueQuery, SwapFeeDefault<T>>;
 
 	#[pallet::storage]
 	#[pallet::getter(fn fee_to)]
@@ -211,59 +369,173 @@ pub mod pallet {
 	pub type FeeToSetter<T: Config> = StorageValue<_, T::AccountId, OptionQuery>;
 
 	#[pallet::storage]
-	#[pallet::getter(fn total_supply)]
-	pub type TotalSupply<T: Config> = StorageValue<_, T::Balance, ValueQuery>;
+	pub type TotalSupply<T: Config> =
+		StorageMap<_, Blake2_128Concat, LpTokenOf<T>, T::Balance, ValueQuery>;
 
 	#[pallet::storage]
 	#[pallet::getter(fn lp_balances)]
-	pub type LpBalances<T: Config> =
-		StorageMap<_, Blake2_128Concat, T::AccountId, T::Balance, OptionQuery>;
+	pub type LpBalances<T: Config> = StorageDoubleMap<
+		_,
+		Blake2_128Concat,
+		LpTokenOf<T>,
+		Blake2_128Concat,
+		T::AccountId,
+		T::Balance,
+		OptionQuery,
+	>;
+
+	/// The amplification of every StableSwap pool. Pools without one are constant product pools.
+	#[pallet::storage]
+	#[pallet::getter(fn amplification)]
+	pub type Amplifications<T: Config> =
+		StorageMap<_, Blake2_128Concat, PoolIdOf<T>, Amplification<T::Moment>, OptionQuery>;
+
+	/// The weight of the first asset of every weighted pool. The second asset has the rest.
+	#[pallet::storage]
+	#[pallet::getter(fn weight)]
+	pub type Weights<T: Config> =
+		StorageMap<_, Blake2_128Concat, PoolIdOf<T>, Permill, OptionQuery>;
+
+	/// The fee taken from the input amount of every swap in the basket.
+	#[pallet::storage]
+	#[pallet::getter(fn basket_swap_fee)]
+	pub type BasketSwapFees<T: Config> =
+		StorageMap<_, Blake2_128Concat, AssetsOf<T>, Permill, ValueQuery, SwapFeeDefault<T>>;
+
+	/// The reserves of every basket pool, in the order of its assets.
+	#[pallet::storage]
+	#[pallet::getter(fn basket_reserves)]
+	pub type BasketReserves<T: Config> =
+		StorageMap<_, Blake2_128Concat, AssetsOf<T>, AmountsOf<T>, OptionQuery>;
 
+	/// Pools which are in the middle of a deposit, withdrawal, swap, skim or sync.
 	#[pallet::storage]
-	pub(super) type PalletAccountId<T: Config> = StorageValue<_, T::AccountId, OptionQuery>; // what exactly is this? the account id of this pallet kind of but does it make sense?
+	pub type Locks<T: Config> = StorageMap<_, Blake2_128Concat, PoolIdOf<T>, bool, ValueQuery>;
 
+	/// The amount of LP tokens of a pool which the spender may still transfer from the owner.
 	#[pallet::storage]
-	pub(super) type AddressZero<T: Config> = StorageValue<_, T::AccountId, OptionQuery>;
+	#[pallet::getter(fn allowance)]
+	pub type Allowances<T: Config> = StorageNMap<
+		_,
+		(
+			NMapKey<Blake2_128Concat, LpTokenOf<T>>,
+			NMapKey<Blake2_128Concat, T::AccountId>, // owner
+			NMapKey<Blake2_128Concat, T::AccountId>, // spender
+		),
+		T::Balance,
+		ValueQuery,
+	>;
 
 	// Pallets use events to inform users when important changes are made.
 	// https://docs.substrate.io/v3/runtime/events-and-errors
 	#[pallet::event]
 	#[pallet::generate_deposit(pub(super) fn deposit_event)]
 	pub enum Event<T: Config> {
+		/// A new pool was created.
+		/// parameters: [pool,account]
+		PoolCreated {
+			pool: PoolIdOf<T>,
+			account: T::AccountId,
+		},
+
 		/// A token transfer occurred.
-		/// parameters: [from,to,value]
+		/// parameters: [pool,from,to,value]
 		Transfer {
+			pool: LpTokenOf<T>,
 			from: Option<T::AccountId>,
 			to: Option<T::AccountId>,
 			value: T::Balance,
 		},
 
+		/// `spender` may transfer up to `value` LP tokens of the pool from `owner`.
+		/// parameters: [pool,owner,spender,value]
+		Approval {
+			pool: LpTokenOf<T>,
+			owner: T::AccountId,
+			spender: T::AccountId,
+			value: T::Balance,
+		},
+
+		/// Assets were deposited into a pool, with an amount for each of its assets.
+		/// parameters: [pool,sender,amounts]
 		Mint {
+			pool: AssetsOf<T>,
 			sender: T::AccountId,
-			amount_0: T::Balance,
-			amount_1: T::Balance,
+			amounts: AmountsOf<T>,
 		},
 
+		/// Assets were withdrawn from a pool, with an amount for each of its assets.
+		/// parameters: [pool,sender,to,amounts]
 		Burn {
+			pool: AssetsOf<T>,
 			sender: T::AccountId,
 			to: T::AccountId,
-			amount_0: T::Balance,
-			amount_1: T::Balance,
+			amounts: AmountsOf<T>,
 		},
 
+		/// Assets were swapped in a pool, with an amount in and out for each of its assets.
+		/// parameters: [pool,sender,to,amounts_in,amounts_out]
 		Swap {
+			pool: AssetsOf<T>,
 			sender: T::AccountId,
 			to: T::AccountId,
-			amount_0_in: T::Balance,
-			amount_1_in: T::Balance,
-			amount_0_out: T::Balance,
-			amount_1_out: T::Balance,
+			amounts_in: AmountsOf<T>,
+			amounts_out: AmountsOf<T>,
 		},
 
 		Sync {
+			pool: PoolIdOf<T>,
 			reserve_0: T::Balance,
 			reserve_1: T::Balance,
 		},
+
+		/// The recipient of the protocol fee was changed, `None` disables the fee.
+		/// parameters: [fee_to]
+		FeeToChanged {
+			fee_to: Option<T::AccountId>,
+		},
+
+		/// The account allowed to set the recipient of the protocol fee was changed.
+		/// parameters: [fee_to_setter]
+		FeeToSetterChanged {
+			fee_to_setter: T::AccountId,
+		},
+
+		/// The swap fee of a pool was changed.
+		/// parameters: [pool,fee]
+		SwapFeeSet {
+			pool: PoolIdOf<T>,
+			fee: Permill,
+		},
+
+		/// The amplification of a StableSwap pool was set or started to ramp.
+		/// parameters: [pool,amplification]
+		AmplificationSet {
+			pool: PoolIdOf<T>,
+			amplification: Amplification<T::Moment>,
+		},
+
+		/// A weighted pool was created.
+		/// parameters: [pool,weight_0,weight_1]
+		PoolWeightsSet {
+			pool: PoolIdOf<T>,
+			weight_0: Permill,
+			weight_1: Permill,
+		},
+
+		/// A new basket pool was created.
+		/// parameters: [pool,account]
+		BasketCreated {
+			pool: AssetsOf<T>,
+			account: T::AccountId,
+		},
+
+		/// The swap fee of a basket was changed.
+		/// parameters: [pool,fee]
+		BasketSwapFeeSet {
+			pool: AssetsOf<T>,
+			fee: Permill,
+		},
 	}
 
 	// Errors inform users that something went wrong.
@@ -282,14 +554,46 @@ pub mod pallet {
 		InsufficientLiquidityBurned,
 		InsufficientInputAmount,
 		InsufficientOutputAmount,
+		/// Returned if the price moved beyond the bounds given by the caller.
+		SlippageExceeded,
 		InvalidDepositToken,
 		InvalidSwapToken,
 		InvalidTo,
 		InvalidK,
 		IdenticalAddress,
 		PairExists,
+		/// Returned if no pool exists for the given pair of assets.
+		PairNotFound,
 		AddressGenerationFailed,
 		WithdrawWithoutSupply,
+		/// Returned if the deadline given by the caller has passed.
+		Expired,
+		/// Returned if the swap fee is not below 100%.
+		InvalidFee,
+		/// Returned if no price observation is old enough for the requested window.
+		InsufficientHistory,
+		/// Returned if a swap path has less than two assets.
+		InvalidPath,
+		/// Returned if the pool is entered again while it is being updated,
+		/// e.g. from a flash swap callback.
+		Locked,
+		/// Returned if the result of a calculation does not fit into the balance type.
+		Overflow,
+		/// Returned if the amplification or its ramp is out of bounds.
+		InvalidAmplification,
+		/// Returned if the pool is not a StableSwap pool.
+		NotStableSwapPool,
+		/// Returned if the StableSwap invariant could not be solved for the reserves.
+		InvariantNotSolved,
+		/// Returned if the weight of an asset of a weighted pool is out of bounds.
+		InvalidWeight,
+		/// Returned if a swap in a weighted pool takes in or out too much of a reserve.
+		SwapTooLarge,
+		/// Returned if a basket has less than two or more than `MaxAssetsPerPool` assets,
+		/// or if an amount is not given for every asset of a basket.
+		InvalidAssetCount,
+		/// Returned if no basket exists for the given assets.
+		BasketNotFound,
 	}
 
 	// Dispatchable functions allows users to interact with the pallet and invoke state changes.
@@ -297,73 +601,307 @@ pub mod pallet {
 	// Dispatchable functions must be annotated with a weight and must return a DispatchResult.
 	#[pallet::call]
 	impl<T: Config> Pallet<T> {
+		/// Create a new pool for the given pair of assets.
+		/// The order of the assets does not matter, they are sorted before the pool is stored.
+		#[pallet::weight(<T as Config>::WeightInfo::create_pool())]
+		pub fn create_pool(
+			origin: OriginFor<T>,
+			asset_a: T::CurrencyId,
+			asset_b: T::CurrencyId,
+		) -> DispatchResult {
+			let _ = ensure_signed(origin)?;
+
+			_create_pool::<T>(asset_a, asset_b)?;
+
+			Ok(())
+		}
+
+		/// Create a new StableSwap pool for the given pair of assets, for assets of about the
+		/// same value. The higher the `amplification`, the less the price moves around 1:1.
+		#[pallet::weight(<T as Config>::WeightInfo::create_stable_pool())]
+		pub fn create_stable_pool(
+			origin: OriginFor<T>,
+			asset_a: T::CurrencyId,
+			asset_b: T::CurrencyId,
+			amplification: u32,
+		) -> DispatchResult {
+			let _ = ensure_signed(origin)?;
+			ensure!(
+				amplification >= 1 && amplification <= stable::MAX_AMPLIFICATION,
+				Error::<T>::InvalidAmplification
+			);
+
+			let pool = _create_pool::<T>(asset_a, asset_b)?;
+
+			let now = pallet_timestamp::Pallet::<T>::now();
+			let amplification = Amplification {
+				initial: amplification,
+				future: amplification,
+				initial_time: now,
+				future_time: now,
+			};
+			<Amplifications<T>>::insert(pool, amplification.clone());
+
+			Self::deposit_event(Event::<T>::AmplificationSet { pool, amplification });
+
+			Ok(())
+		}
+
+		/// Create a new weighted pool for the given pair of assets, where `asset_a` makes up
+		/// `weight_a` of the value of the pool and `asset_b` the rest.
+		#[pallet::weight(<T as Config>::WeightInfo::create_weighted_pool())]
+		pub fn create_weighted_pool(
+			origin: OriginFor<T>,
+			asset_a: T::CurrencyId,
+			asset_b: T::CurrencyId,
+			weight_a: Permill,
+		) -> DispatchResult {
+			let _ = ensure_signed(origin)?;
+			ensure!(
+				weight_a >= weighted::MIN_WEIGHT &&
+					weight_a.left_from_one() >= weighted::MIN_WEIGHT,
+				Error::<T>::InvalidWeight
+			);
+
+			let pool = _create_pool::<T>(asset_a, asset_b)?;
+
+			let weight_0 = if asset_a == pool.0 { weight_a } else { weight_a.left_from_one() };
+			<Weights<T>>::insert(pool, weight_0);
+
+			Self::deposit_event(Event::<T>::PoolWeightsSet {
+				pool,
+				weight_0,
+				weight_1: weight_0.left_from_one(),
+			});
+
+			Ok(())
+		}
+
+		/// Move the amplification of a StableSwap pool linearly from its current value to
+		/// `future` until `future_time`, which has to be at least `MinRampTime` from now.
+		/// The amplification can change by at most a factor of 10 per ramp.
+		#[pallet::weight(<T as Config>::WeightInfo::ramp_amplification())]
+		pub fn ramp_amplification(
+			origin: OriginFor<T>,
+			pool: PoolIdOf<T>,
+			future: u32,
+			future_time: T::Moment,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			let current = <Amplifications<T>>::get(pool).ok_or(Error::<T>::NotStableSwapPool)?;
+
+			let now = pallet_timestamp::Pallet::<T>::now();
+			ensure!(
+				future_time >= now.saturating_add(T::MinRampTime::get()),
+				Error::<T>::InvalidAmplification
+			);
+
+			let initial = current.at(now);
+			ensure!(
+				future >= 1 && future <= stable::MAX_AMPLIFICATION,
+				Error::<T>::InvalidAmplification
+			);
+			ensure!(
+				future <= initial.saturating_mul(stable::MAX_AMPLIFICATION_CHANGE) &&
+					initial <= future.saturating_mul(stable::MAX_AMPLIFICATION_CHANGE),
+				Error::<T>::InvalidAmplification
+			);
+
+			let amplification = Amplification { initial, future, initial_time: now, future_time };
+			<Amplifications<T>>::insert(pool, amplification.clone());
+
+			Self::deposit_event(Event::<T>::AmplificationSet { pool, amplification });
+
+			Ok(())
+		}
+
 		#[pallet::weight(<T as Config>::WeightInfo::set_fee_to())]
 		pub fn set_fee_to(origin: OriginFor<T>, fee_to: T::AccountId) -> DispatchResult {
 			let caller = ensure_signed(origin)?;
 
 			ensure!(
-				caller == <FeeToSetter<T>>::get().unwrap(), // the read
+				<FeeToSetter<T>>::get() == Some(caller), // the read
 				Error::<T>::Forbidden
 			);
 
-			<FeeTo<T>>::put(fee_to); //the write
+			<FeeTo<T>>::put(fee_to.clone()); //the write
+
+			Self::deposit_event(Event::<T>::FeeToChanged { fee_to: Some(fee_to) });
 
 			Ok(())
 		}
 
-		/// Force balances to match reserves
-		/// At this point, the caller is the recipient.
-		/// todo: weight
-		#[pallet::weight(<T as Config>::WeightInfo::skim())]
-		pub fn skim(origin: OriginFor<T>) -> DispatchResult {
-			let to = ensure_signed(origin)?;
-			let contract = <PalletAccountId<T>>::get().unwrap();
-			let reserves = <Reserves<T>>::get();
+		/// Hand over the right to set `fee_to` to another account.
+		/// Only the current `fee_to_setter` can call this.
+		#[pallet::weight(<T as Config>::WeightInfo::set_fee_to_setter())]
+		pub fn set_fee_to_setter(
+			origin: OriginFor<T>,
+			fee_to_setter: T::AccountId,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
 
-			let asset_0 = T::Asset0::get();
-			let asset_1 = T::Asset1::get();
+			ensure!(<FeeToSetter<T>>::get() == Some(caller), Error::<T>::Forbidden);
 
-			let amount_0_calc =
-				balance_of::<T>(&contract, asset_0).checked_sub(&reserves.reserve_0);
-			if let Some(amount_0) = amount_0_calc {
-				transfer_tokens::<T>(&contract, &to, asset_0, amount_0)?;
-			}
+			<FeeToSetter<T>>::put(fee_to_setter.clone());
+
+			Self::deposit_event(Event::<T>::FeeToSetterChanged { fee_to_setter });
+
+			Ok(())
+		}
+
+		/// Set `fee_to` without being the `fee_to_setter`, e.g. if its key was lost.
+		/// `None` disables the protocol fee.
+		#[pallet::weight(<T as Config>::WeightInfo::force_set_fee_to())]
+		pub fn force_set_fee_to(
+			origin: OriginFor<T>,
+			fee_to: Option<T::AccountId>,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+
+			<FeeTo<T>>::set(fee_to.clone());
+
+			Self::deposit_event(Event::<T>::FeeToChanged { fee_to });
 
-			let amount_1_calc =
-				balance_of::<T>(&contract, asset_1).checked_sub(&reserves.reserve_1);
-			if let Some(amount_1) = amount_1_calc {
-				transfer_tokens::<T>(&contract, &to, asset_1, amount_1)?;
+			Ok(())
+		}
+
+		/// Set the fee taken from the input amount of every swap in the pool.
+		#[pallet::weight(<T as Config>::WeightInfo::set_swap_fee())]
+		pub fn set_swap_fee(
+			origin: OriginFor<T>,
+			pool: PoolIdOf<T>,
+			fee: Permill,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(<Reserves<T>>::contains_key(pool), Error::<T>::PairNotFound);
+			ensure!(fee < Permill::one(), Error::<T>::InvalidFee);
+
+			<SwapFees<T>>::insert(pool, fee);
+
+			Self::deposit_event(Event::<T>::SwapFeeSet { pool, fee });
+
+			Ok(())
+		}
+
+		/// Transfer `amount` LP tokens of the pool to `to`.
+		#[pallet::weight(<T as Config>::WeightInfo::transfer_lp())]
+		pub fn transfer_lp(
+			origin: OriginFor<T>,
+			pool: LpTokenOf<T>,
+			to: T::AccountId,
+			amount: T::Balance,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			ensure_lp_token_exists::<T>(&pool)?;
+
+			_transfer_liquidity::<T>(pool, caller, to, amount)?;
+
+			Ok(())
+		}
+
+		/// Allow `spender` to transfer up to `amount` LP tokens of the pool from the caller.
+		/// An existing allowance is overwritten.
+		#[pallet::weight(<T as Config>::WeightInfo::approve_lp())]
+		pub fn approve_lp(
+			origin: OriginFor<T>,
+			pool: LpTokenOf<T>,
+			spender: T::AccountId,
+			amount: T::Balance,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			ensure_lp_token_exists::<T>(&pool)?;
+
+			_approve::<T>(pool, caller, spender, amount);
+
+			Ok(())
+		}
+
+		/// Transfer `amount` LP tokens of the pool from `from` to `to`, within the allowance
+		/// given to the caller by `from`. An allowance of the maximum balance is never decreased.
+		#[pallet::weight(<T as Config>::WeightInfo::transfer_lp_from())]
+		pub fn transfer_lp_from(
+			origin: OriginFor<T>,
+			pool: LpTokenOf<T>,
+			from: T::AccountId,
+			to: T::AccountId,
+			amount: T::Balance,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			ensure_lp_token_exists::<T>(&pool)?;
+
+			let allowance = <Allowances<T>>::get((&pool, &from, &caller));
+			ensure!(allowance >= amount, Error::<T>::InsufficientAllowance);
+
+			_transfer_liquidity::<T>(pool.clone(), from.clone(), to, amount)?;
+
+			if allowance != T::Balance::max_value() {
+				<Allowances<T>>::insert((&pool, &from, &caller), allowance.saturating_sub(amount));
 			}
 
 			Ok(())
 		}
 
+		/// Force balances to match reserves
+		/// At this point, the caller is the recipient.
+		/// todo: weight
+		#[pallet::weight(<T as Config>::WeightInfo::skim())]
+		pub fn skim(origin: OriginFor<T>, pool: PoolIdOf<T>) -> DispatchResult {
+			let to = ensure_signed(origin)?;
+			let reserves = <Reserves<T>>::get(pool).ok_or(Error::<T>::PairNotFound)?;
+			let contract = pool_account::<T>(pool)?;
+
+			let (asset_0, asset_1) = pool;
+
+			with_lock::<T, _>(pool, || {
+				let amount_0_calc =
+					balance_of::<T>(&contract, asset_0).checked_sub(&reserves.reserve_0);
+				if let Some(amount_0) = amount_0_calc {
+					transfer_tokens::<T>(&contract, &to, asset_0, amount_0)?;
+				}
+
+				let amount_1_calc =
+					balance_of::<T>(&contract, asset_1).checked_sub(&reserves.reserve_1);
+				if let Some(amount_1) = amount_1_calc {
+					transfer_tokens::<T>(&contract, &to, asset_1, amount_1)?;
+				}
+
+				Ok(())
+			})
+		}
+
 		#[pallet::weight(<T as Config>::WeightInfo::sync())]
-		pub fn sync(origin: OriginFor<T>) -> DispatchResult {
+		pub fn sync(origin: OriginFor<T>, pool: PoolIdOf<T>) -> DispatchResult {
 			let _ = ensure_signed(origin)?;
-			let contract = <PalletAccountId<T>>::get().unwrap();
-			let reserves = <Reserves<T>>::get();
+			let reserves = <Reserves<T>>::get(pool).ok_or(Error::<T>::PairNotFound)?;
+			let contract = pool_account::<T>(pool)?;
 
-			let asset_0 = T::Asset0::get();
-			let asset_1 = T::Asset1::get();
+			let (asset_0, asset_1) = pool;
 
-			let balance_0 = balance_of::<T>(&contract, asset_0);
-			let balance_1 = balance_of::<T>(&contract, asset_1);
+			with_lock::<T, _>(pool, || {
+				let balance_0 = balance_of::<T>(&contract, asset_0);
+				let balance_1 = balance_of::<T>(&contract, asset_1);
 
-			_update::<T>(balance_0, balance_1, reserves.reserve_0, reserves.reserve_1);
+				_update::<T>(pool, balance_0, balance_1, reserves.reserve_0, reserves.reserve_1)?;
 
-			Ok(())
+				Ok(())
+			})
 		}
 
 		/// Add liquidity
 		#[pallet::weight(<T as Config>::WeightInfo::deposit_asset_1())]
-		pub fn deposit_asset_1(origin: OriginFor<T>, amount: T::Balance) -> DispatchResult {
+		#[transactional]
+		pub fn deposit_asset_1(
+			origin: OriginFor<T>,
+			pool: PoolIdOf<T>,
+			amount: T::Balance,
+			deadline: Option<T::Moment>,
+		) -> DispatchResult {
 			let caller = ensure_signed(origin)?;
-			let contract = <PalletAccountId<T>>::get().unwrap();
-			let reserves = <Reserves<T>>::get();
+			ensure_not_expired::<T>(deadline)?;
+			let reserves = <Reserves<T>>::get(pool).ok_or(Error::<T>::PairNotFound)?;
+			let contract = pool_account::<T>(pool)?;
 
-			let asset_0 = T::Asset0::get();
-			let asset_1 = T::Asset1::get();
+			let (asset_0, asset_1) = pool;
 
 			let zero = T::Balance::zero();
 
@@ -376,17 +914,25 @@ pub mod pallet {
 			transfer_tokens::<T>(&caller, &contract, asset_0, amount)?;
 			transfer_tokens::<T>(&caller, &contract, asset_1, amount_1)?;
 
-			mint::<T>(&caller, caller.clone())
+			mint::<T>(pool, &caller, caller.clone())?;
+
+			Ok(())
 		}
 
 		#[pallet::weight(<T as Config>::WeightInfo::deposit_asset_2())]
-		pub fn deposit_asset_2(origin: OriginFor<T>, amount: T::Balance) -> DispatchResult {
+		#[transactional]
+		pub fn deposit_asset_2(
+			origin: OriginFor<T>,
+			pool: PoolIdOf<T>,
+			amount: T::Balance,
+			deadline: Option<T::Moment>,
+		) -> DispatchResult {
 			let caller = ensure_signed(origin)?;
-			let contract = <PalletAccountId<T>>::get().unwrap();
-			let reserves = <Reserves<T>>::get();
+			ensure_not_expired::<T>(deadline)?;
+			let reserves = <Reserves<T>>::get(pool).ok_or(Error::<T>::PairNotFound)?;
+			let contract = pool_account::<T>(pool)?;
 
-			let asset_0 = T::Asset0::get();
-			let asset_1 = T::Asset1::get();
+			let (asset_0, asset_1) = pool;
 
 			let zero = T::Balance::zero();
 
@@ -399,64 +945,658 @@ pub mod pallet {
 			transfer_tokens::<T>(&caller, &contract, asset_0, amount_0)?;
 			transfer_tokens::<T>(&caller, &contract, asset_1, amount)?;
 
-			mint::<T>(&caller, caller.clone())
+			mint::<T>(pool, &caller, caller.clone())?;
+
+			Ok(())
+		}
+
+		/// Add liquidity in the ratio of the current reserves, taking at most
+		/// `amount_0_desired` of Asset1 and `amount_1_desired` of Asset2.
+		/// Fails if less than `amount_0_min` or `amount_1_min` would be deposited.
+		#[pallet::weight(<T as Config>::WeightInfo::add_liquidity())]
+		#[transactional]
+		pub fn add_liquidity(
+			origin: OriginFor<T>,
+			pool: PoolIdOf<T>,
+			amount_0_desired: T::Balance,
+			amount_1_desired: T::Balance,
+			amount_0_min: T::Balance,
+			amount_1_min: T::Balance,
+			deadline: Option<T::Moment>,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			ensure_not_expired::<T>(deadline)?;
+			let reserves = <Reserves<T>>::get(pool).ok_or(Error::<T>::PairNotFound)?;
+			let contract = pool_account::<T>(pool)?;
+
+			let (asset_0, asset_1) = pool;
+
+			let (amount_0, amount_1) = optimal_liquidity_amounts::<T>(
+				reserves.reserve_0,
+				reserves.reserve_1,
+				amount_0_desired,
+				amount_1_desired,
+				amount_0_min,
+				amount_1_min,
+			)?;
+
+			transfer_tokens::<T>(&caller, &contract, asset_0, amount_0)?;
+			transfer_tokens::<T>(&caller, &contract, asset_1, amount_1)?;
+
+			mint::<T>(pool, &caller, caller.clone())?;
+
+			Ok(())
 		}
 
 		/// Remove Liquidity
 		#[pallet::weight(<T as Config>::WeightInfo::withdraw())]
-		pub fn withdraw(origin: OriginFor<T>, amount: T::Balance) -> DispatchResult {
+		#[transactional]
+		pub fn withdraw(
+			origin: OriginFor<T>,
+			pool: PoolIdOf<T>,
+			amount: T::Balance,
+			deadline: Option<T::Moment>,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			ensure_not_expired::<T>(deadline)?;
+			ensure!(<Reserves<T>>::contains_key(pool), Error::<T>::PairNotFound);
+			let contract = pool_account::<T>(pool)?;
+
+			ensure!(
+				Self::total_supply(pool) != T::Balance::zero(),
+				Error::<T>::WithdrawWithoutSupply
+			);
+
+			_transfer_liquidity::<T>(pool.into(), caller.clone(), contract, amount)?;
+
+			let zero = T::Balance::zero();
+			burn::<T>(pool, &caller, caller.clone(), zero, zero)?;
+
+			Ok(())
+		}
+
+		/// Remove `liquidity` and send the withdrawn assets to `to`.
+		/// Fails if less than `amount_0_min` of Asset1 or `amount_1_min` of Asset2 would be withdrawn.
+		#[pallet::weight(<T as Config>::WeightInfo::remove_liquidity())]
+		#[transactional]
+		pub fn remove_liquidity(
+			origin: OriginFor<T>,
+			pool: PoolIdOf<T>,
+			liquidity: T::Balance,
+			amount_0_min: T::Balance,
+			amount_1_min: T::Balance,
+			to: T::AccountId,
+			deadline: Option<T::Moment>,
+		) -> DispatchResult {
 			let caller = ensure_signed(origin)?;
-			let contract = <PalletAccountId<T>>::get().unwrap();
+			ensure_not_expired::<T>(deadline)?;
+			ensure!(<Reserves<T>>::contains_key(pool), Error::<T>::PairNotFound);
+			let contract = pool_account::<T>(pool)?;
 
 			ensure!(
-				<TotalSupply<T>>::get() != T::Balance::zero(),
+				Self::total_supply(pool) != T::Balance::zero(),
 				Error::<T>::WithdrawWithoutSupply
 			);
 
-			_transfer_liquidity::<T>(caller.clone(), contract, amount)?;
+			_transfer_liquidity::<T>(pool.into(), caller.clone(), contract, liquidity)?;
 
-			burn::<T>(&caller, caller.clone()).map_err(|e| DispatchError::from(e))
+			burn::<T>(pool, &to, caller, amount_0_min, amount_1_min)?;
+
+			Ok(())
 		}
 
+		/// Add liquidity holding only one of the assets of the pool.
+		/// Part of `amount` is swapped for the other asset, so that the rest can be
+		/// deposited together with the swapped amount. Fails if less than
+		/// `min_liquidity` LP tokens are minted.
+		/// The swapped part is found on the curve of the pool.
+		#[pallet::weight(<T as Config>::WeightInfo::zap_in())]
+		#[transactional]
+		pub fn zap_in(
+			origin: OriginFor<T>,
+			pool: PoolIdOf<T>,
+			asset: T::CurrencyId,
+			amount: T::Balance,
+			min_liquidity: T::Balance,
+			deadline: Option<T::Moment>,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			ensure_not_expired::<T>(deadline)?;
+			let reserves = <Reserves<T>>::get(pool).ok_or(Error::<T>::PairNotFound)?;
+			let contract = pool_account::<T>(pool)?;
+
+			let (asset_0, asset_1) = pool;
+			ensure!(asset == asset_0 || asset == asset_1, Error::<T>::InvalidDepositToken);
+			let zero_for_one = asset == asset_0;
+
+			let (reserve_in, reserve_out) = if zero_for_one {
+				(reserves.reserve_0, reserves.reserve_1)
+			} else {
+				(reserves.reserve_1, reserves.reserve_0)
+			};
+
+			let swap_amount = zap_swap_amount::<T>(pool, asset, amount, reserve_in, reserve_out)?;
+			let amount_out =
+				pool_amount_out::<T>(pool, asset, swap_amount, reserve_in, reserve_out)?;
+
+			transfer_tokens::<T>(&caller, &contract, asset, swap_amount)?;
+			if zero_for_one {
+				_swap::<T>(pool, T::Balance::zero(), amount_out, &caller, caller.clone(), &[])?;
+			} else {
+				_swap::<T>(pool, amount_out, T::Balance::zero(), &caller, caller.clone(), &[])?;
+			}
+
+			let (reserve_0, reserve_1, _) = reserves::<T>(pool);
+			let remaining = amount.saturating_sub(swap_amount);
+			let (amount_0_desired, amount_1_desired) =
+				if zero_for_one { (remaining, amount_out) } else { (amount_out, remaining) };
+			let zero = T::Balance::zero();
+			let (amount_0, amount_1) = optimal_liquidity_amounts::<T>(
+				reserve_0,
+				reserve_1,
+				amount_0_desired,
+				amount_1_desired,
+				zero,
+				zero,
+			)?;
+
+			transfer_tokens::<T>(&caller, &contract, asset_0, amount_0)?;
+			transfer_tokens::<T>(&caller, &contract, asset_1, amount_1)?;
+
+			let liquidity = mint::<T>(pool, &caller, caller.clone())?;
+			ensure!(liquidity >= min_liquidity, Error::<T>::SlippageExceeded);
+
+			Ok(())
+		}
+
+		/// Remove `liquidity` and receive only `asset`.
+		/// The withdrawn amount of the other asset is swapped for `asset`.
+		/// Fails if less than `min_amount_out` of `asset` is received in total.
+		#[pallet::weight(<T as Config>::WeightInfo::zap_out())]
+		#[transactional]
+		pub fn zap_out(
+			origin: OriginFor<T>,
+			pool: PoolIdOf<T>,
+			liquidity: T::Balance,
+			asset: T::CurrencyId,
+			min_amount_out: T::Balance,
+			deadline: Option<T::Moment>,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			ensure_not_expired::<T>(deadline)?;
+			ensure!(<Reserves<T>>::contains_key(pool), Error::<T>::PairNotFound);
+			let contract = pool_account::<T>(pool)?;
+
+			let (asset_0, asset_1) = pool;
+			ensure!(asset == asset_0 || asset == asset_1, Error::<T>::InvalidSwapToken);
+
+			ensure!(
+				Self::total_supply(pool) != T::Balance::zero(),
+				Error::<T>::WithdrawWithoutSupply
+			);
+
+			_transfer_liquidity::<T>(pool.into(), caller.clone(), contract.clone(), liquidity)?;
+
+			let zero = T::Balance::zero();
+			let (amount_0, amount_1) = burn::<T>(pool, &caller, caller.clone(), zero, zero)?;
+
+			let (reserve_0, reserve_1, _) = reserves::<T>(pool);
+			let amount_out = if asset == asset_0 {
+				let swapped = pool_amount_out::<T>(pool, asset_1, amount_1, reserve_1, reserve_0)?;
+				transfer_tokens::<T>(&caller, &contract, asset_1, amount_1)?;
+				_swap::<T>(pool, swapped, zero, &caller, caller.clone(), &[])?;
+
+				amount_0.saturating_add(swapped)
+			} else {
+				let swapped = pool_amount_out::<T>(pool, asset_0, amount_0, reserve_0, reserve_1)?;
+				transfer_tokens::<T>(&caller, &contract, asset_0, amount_0)?;
+				_swap::<T>(pool, zero, swapped, &caller, caller.clone(), &[])?;
+
+				amount_1.saturating_add(swapped)
+			};
+			ensure!(amount_out >= min_amount_out, Error::<T>::SlippageExceeded);
+
+			Ok(())
+		}
+
+		/// Swap Asset1 for exactly `amount_to_receive` of Asset2,
+		/// paying at most `max_amount_in` of Asset1.
 		#[pallet::weight(<T as Config>::WeightInfo::swap_asset_1_for_asset_2())]
+		#[transactional]
 		pub fn swap_asset_1_for_asset_2(
 			origin: OriginFor<T>,
+			pool: PoolIdOf<T>,
 			amount_to_receive: T::Balance,
+			max_amount_in: T::Balance,
+			deadline: Option<T::Moment>,
 		) -> DispatchResult {
 			let caller = ensure_signed(origin)?;
-			let contract = <PalletAccountId<T>>::get().unwrap();
-			let reserves = <Reserves<T>>::get();
+			ensure_not_expired::<T>(deadline)?;
+			let reserves = <Reserves<T>>::get(pool).ok_or(Error::<T>::PairNotFound)?;
+			let contract = pool_account::<T>(pool)?;
 
-			let amount_0_in =
-				get_amount_in::<T>(amount_to_receive, reserves.reserve_0, reserves.reserve_1)?;
+			let amount_0_in = pool_amount_in::<T>(
+				pool,
+				pool.0,
+				amount_to_receive,
+				reserves.reserve_0,
+				reserves.reserve_1,
+			)?;
+			ensure!(amount_0_in <= max_amount_in, Error::<T>::SlippageExceeded);
 
-			let asset_0 = T::Asset0::get();
+			let (asset_0, _) = pool;
 
 			transfer_tokens::<T>(&caller, &contract, asset_0, amount_0_in)?;
 
-			_swap::<T>(T::Balance::zero(), amount_to_receive, &caller, caller.clone())
+			_swap::<T>(pool, T::Balance::zero(), amount_to_receive, &caller, caller.clone(), &[])
 				.map_err(|e| DispatchError::from(e))
 		}
 
+		/// Swap Asset2 for exactly `amount_to_receive` of Asset1,
+		/// paying at most `max_amount_in` of Asset2.
 		#[pallet::weight(<T as Config>::WeightInfo::swap_asset_2_for_asset_1())]
+		#[transactional]
 		pub fn swap_asset_2_for_asset_1(
 			origin: OriginFor<T>,
+			pool: PoolIdOf<T>,
 			amount_to_receive: T::Balance,
+			max_amount_in: T::Balance,
+			deadline: Option<T::Moment>,
 		) -> DispatchResult {
 			let caller = ensure_signed(origin)?;
-			let contract = <PalletAccountId<T>>::get().unwrap();
-			let reserves = <Reserves<T>>::get();
+			ensure_not_expired::<T>(deadline)?;
+			let reserves = <Reserves<T>>::get(pool).ok_or(Error::<T>::PairNotFound)?;
+			let contract = pool_account::<T>(pool)?;
 
-			let amount_1_in =
-				get_amount_in::<T>(amount_to_receive, reserves.reserve_1, reserves.reserve_0)?;
+			let amount_1_in = pool_amount_in::<T>(
+				pool,
+				pool.1,
+				amount_to_receive,
+				reserves.reserve_1,
+				reserves.reserve_0,
+			)?;
+			ensure!(amount_1_in <= max_amount_in, Error::<T>::SlippageExceeded);
 
-			let asset_1 = T::Asset1::get();
+			let (_, asset_1) = pool;
 
 			transfer_tokens::<T>(&caller, &contract, asset_1, amount_1_in)?;
 
-			_swap::<T>(amount_to_receive, T::Balance::zero(), &caller, caller.clone())
+			_swap::<T>(pool, amount_to_receive, T::Balance::zero(), &caller, caller.clone(), &[])
 				.map_err(|e| DispatchError::from(e))
 		}
+
+		/// Swap exactly `amount_in` of Asset1 for at least `min_amount_out` of Asset2.
+		#[pallet::weight(<T as Config>::WeightInfo::swap_exact_in_asset_1_for_asset_2())]
+		#[transactional]
+		pub fn swap_exact_in_asset_1_for_asset_2(
+			origin: OriginFor<T>,
+			pool: PoolIdOf<T>,
+			amount_in: T::Balance,
+			min_amount_out: T::Balance,
+			deadline: Option<T::Moment>,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			ensure_not_expired::<T>(deadline)?;
+			let reserves = <Reserves<T>>::get(pool).ok_or(Error::<T>::PairNotFound)?;
+			let contract = pool_account::<T>(pool)?;
+
+			let amount_1_out = pool_amount_out::<T>(
+				pool,
+				pool.0,
+				amount_in,
+				reserves.reserve_0,
+				reserves.reserve_1,
+			)?;
+			ensure!(amount_1_out >= min_amount_out, Error::<T>::SlippageExceeded);
+
+			let (asset_0, _) = pool;
+
+			transfer_tokens::<T>(&caller, &contract, asset_0, amount_in)?;
+
+			_swap::<T>(pool, T::Balance::zero(), amount_1_out, &caller, caller.clone(), &[])
+		}
+
+		/// Swap exactly `amount_in` of Asset2 for at least `min_amount_out` of Asset1.
+		#[pallet::weight(<T as Config>::WeightInfo::swap_exact_in_asset_2_for_asset_1())]
+		#[transactional]
+		pub fn swap_exact_in_asset_2_for_asset_1(
+			origin: OriginFor<T>,
+			pool: PoolIdOf<T>,
+			amount_in: T::Balance,
+			min_amount_out: T::Balance,
+			deadline: Option<T::Moment>,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			ensure_not_expired::<T>(deadline)?;
+			let reserves = <Reserves<T>>::get(pool).ok_or(Error::<T>::PairNotFound)?;
+			let contract = pool_account::<T>(pool)?;
+
+			let amount_0_out = pool_amount_out::<T>(
+				pool,
+				pool.1,
+				amount_in,
+				reserves.reserve_1,
+				reserves.reserve_0,
+			)?;
+			ensure!(amount_0_out >= min_amount_out, Error::<T>::SlippageExceeded);
+
+			let (_, asset_1) = pool;
+
+			transfer_tokens::<T>(&caller, &contract, asset_1, amount_in)?;
+
+			_swap::<T>(pool, amount_0_out, T::Balance::zero(), &caller, caller.clone(), &[])
+		}
+
+		/// Swap exactly `amount_in` of the first asset of `path` for at least `min_amount_out`
+		/// of the last one, through the pools of every two neighbouring assets.
+		#[pallet::weight(
+			<T as Config>::WeightInfo::swap_exact_in_along_path(path.len().saturating_sub(1) as u32)
+		)]
+		#[transactional]
+		pub fn swap_exact_in_along_path(
+			origin: OriginFor<T>,
+			path: BoundedVec<T::CurrencyId, T::MaxPathLength>,
+			amount_in: T::Balance,
+			min_amount_out: T::Balance,
+			deadline: Option<T::Moment>,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			ensure_not_expired::<T>(deadline)?;
+
+			let amounts = get_amounts_out::<T>(amount_in, &path)?;
+			ensure!(amounts[amounts.len() - 1] >= min_amount_out, Error::<T>::SlippageExceeded);
+
+			_swap_along_path::<T>(&amounts, &path, &caller, caller.clone())
+		}
+
+		/// Swap at most `max_amount_in` of the first asset of `path` for exactly `amount_out`
+		/// of the last one, through the pools of every two neighbouring assets.
+		#[pallet::weight(
+			<T as Config>::WeightInfo::swap_exact_out_along_path(path.len().saturating_sub(1) as u32)
+		)]
+		#[transactional]
+		pub fn swap_exact_out_along_path(
+			origin: OriginFor<T>,
+			path: BoundedVec<T::CurrencyId, T::MaxPathLength>,
+			amount_out: T::Balance,
+			max_amount_in: T::Balance,
+			deadline: Option<T::Moment>,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			ensure_not_expired::<T>(deadline)?;
+
+			let amounts = get_amounts_in::<T>(amount_out, &path)?;
+			ensure!(amounts[0] <= max_amount_in, Error::<T>::SlippageExceeded);
+
+			_swap_along_path::<T>(&amounts, &path, &caller, caller.clone())
+		}
+
+		/// Send `amount_0_out` of Asset1 and `amount_1_out` of Asset2 to `to`, which have to be
+		/// paid for, including the swap fee, by the time the invariant is checked.
+		/// Without `data`, the input has to be transferred to the pool account beforehand.
+		/// With `data`, `T::FlashLoanReceiver` is called after the transfer and can pay within it.
+		/// Deposits, withdrawals and swaps of the pool fail with `Locked` during the callback.
+		#[pallet::weight(<T as Config>::WeightInfo::flash_swap())]
+		#[transactional]
+		pub fn flash_swap(
+			origin: OriginFor<T>,
+			pool: PoolIdOf<T>,
+			amount_0_out: T::Balance,
+			amount_1_out: T::Balance,
+			to: T::AccountId,
+			data: BoundedVec<u8, T::MaxFlashDataLen>,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			ensure!(<Reserves<T>>::contains_key(pool), Error::<T>::PairNotFound);
+
+			_swap::<T>(pool, amount_0_out, amount_1_out, &to, caller, &data)
+		}
+
+		/// Create a new basket pool for the given assets, of which there have to be at least two
+		/// and at most `MaxAssetsPerPool`. The order of the assets does not matter, they are
+		/// sorted before the basket is stored. All assets of a basket have the same weight.
+		#[pallet::weight(<T as Config>::WeightInfo::create_basket())]
+		pub fn create_basket(origin: OriginFor<T>, assets: AssetsOf<T>) -> DispatchResult {
+			let _ = ensure_signed(origin)?;
+
+			basket::_create_basket::<T>(assets)?;
+
+			Ok(())
+		}
+
+		/// Add liquidity to a basket in the ratio of its reserves, taking at most
+		/// `max_amounts_in` of its assets, in the order of the assets of the basket.
+		/// The first deposit takes all of `max_amounts_in` and sets the prices of the basket.
+		/// Fails if less than `min_liquidity` LP tokens are minted.
+		#[pallet::weight(<T as Config>::WeightInfo::join_basket(pool.len() as u32))]
+		#[transactional]
+		pub fn join_basket(
+			origin: OriginFor<T>,
+			pool: AssetsOf<T>,
+			max_amounts_in: AmountsOf<T>,
+			min_liquidity: T::Balance,
+			deadline: Option<T::Moment>,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			ensure_not_expired::<T>(deadline)?;
+
+			let liquidity = basket::join::<T>(&pool, &caller, &max_amounts_in)?;
+			ensure!(liquidity >= min_liquidity, Error::<T>::SlippageExceeded);
+
+			Ok(())
+		}
+
+		/// Remove `liquidity` from a basket for the same share of each of its reserves.
+		/// Fails if less than `min_amounts_out` would be withdrawn, in the order of the assets
+		/// of the basket.
+		#[pallet::weight(<T as Config>::WeightInfo::exit_basket(pool.len() as u32))]
+		#[transactional]
+		pub fn exit_basket(
+			origin: OriginFor<T>,
+			pool: AssetsOf<T>,
+			liquidity: T::Balance,
+			min_amounts_out: AmountsOf<T>,
+			deadline: Option<T::Moment>,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			ensure_not_expired::<T>(deadline)?;
+
+			basket::exit::<T>(&pool, &caller, liquidity, &min_amounts_out)?;
+
+			Ok(())
+		}
+
+		/// Add liquidity to a basket holding only `asset_in`, which pays the swap fee on the part
+		/// of `amount_in` that makes up for the other assets.
+		/// Fails if less than `min_liquidity` LP tokens are minted.
+		#[pallet::weight(<T as Config>::WeightInfo::join_basket_single())]
+		#[transactional]
+		pub fn join_basket_single(
+			origin: OriginFor<T>,
+			pool: AssetsOf<T>,
+			asset_in: T::CurrencyId,
+			amount_in: T::Balance,
+			min_liquidity: T::Balance,
+			deadline: Option<T::Moment>,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			ensure_not_expired::<T>(deadline)?;
+
+			let liquidity = basket::join_single::<T>(&pool, &caller, asset_in, amount_in)?;
+			ensure!(liquidity >= min_liquidity, Error::<T>::SlippageExceeded);
+
+			Ok(())
+		}
+
+		/// Remove `liquidity` from a basket for only `asset_out`, which pays the swap fee on
+		/// the part of it that makes up for the other assets.
+		/// Fails if less than `min_amount_out` would be withdrawn.
+		#[pallet::weight(<T as Config>::WeightInfo::exit_basket_single())]
+		#[transactional]
+		pub fn exit_basket_single(
+			origin: OriginFor<T>,
+			pool: AssetsOf<T>,
+			asset_out: T::CurrencyId,
+			liquidity: T::Balance,
+			min_amount_out: T::Balance,
+			deadline: Option<T::Moment>,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			ensure_not_expired::<T>(deadline)?;
+
+			let amount_out = basket::exit_single::<T>(&pool, &caller, asset_out, liquidity)?;
+			ensure!(amount_out >= min_amount_out, Error::<T>::SlippageExceeded);
+
+			Ok(())
+		}
+
+		/// Swap exactly `amount_in` of `asset_in` for at least `min_amount_out` of `asset_out`,
+		/// which can be any two assets of the basket.
+		#[pallet::weight(<T as Config>::WeightInfo::swap_basket())]
+		#[transactional]
+		pub fn swap_basket(
+			origin: OriginFor<T>,
+			pool: AssetsOf<T>,
+			asset_in: T::CurrencyId,
+			asset_out: T::CurrencyId,
+			amount_in: T::Balance,
+			min_amount_out: T::Balance,
+			deadline: Option<T::Moment>,
+		) -> DispatchResult {
+			let caller = ensure_signed(origin)?;
+			ensure_not_expired::<T>(deadline)?;
+
+			let amount_out = basket::swap::<T>(&pool, &caller, asset_in, asset_out, amount_in)?;
+			ensure!(amount_out >= min_amount_out, Error::<T>::SlippageExceeded);
+
+			Ok(())
+		}
+
+		/// Set the fee taken from the input amount of every swap in the basket, including the
+		/// swapped part of a single asset deposit or withdrawal.
+		#[pallet::weight(<T as Config>::WeightInfo::set_basket_swap_fee())]
+		pub fn set_basket_swap_fee(
+			origin: OriginFor<T>,
+			pool: AssetsOf<T>,
+			fee: Permill,
+		) -> DispatchResult {
+			T::AdminOrigin::ensure_origin(origin)?;
+			ensure!(<BasketReserves<T>>::contains_key(&pool), Error::<T>::BasketNotFound);
+			ensure!(fee < Permill::one(), Error::<T>::InvalidFee);
+
+			<BasketSwapFees<T>>::insert(&pool, fee);
+
+			Self::deposit_event(Event::<T>::BasketSwapFeeSet { pool, fee });
+
+			Ok(())
+		}
+	}
+
+	// Queries for the runtime API.
+	impl<T: Config> Pallet<T> {
+		/// The reserves of the pool and the time they were last updated.
+		pub fn get_reserves(pool: PoolIdOf<T>) -> Option<(T::Balance, T::Balance, T::Moment)> {
+			<Reserves<T>>::get(pool)
+				.map(|res| (res.reserve_0, res.reserve_1, res.block_timestamp_last))
+		}
+
+		/// The amount received for swapping exactly `amount_in` of `asset_in`.
+		pub fn quote_exact_in(
+			pool: PoolIdOf<T>,
+			asset_in: T::CurrencyId,
+			amount_in: T::Balance,
+		) -> Result<T::Balance, DispatchError> {
+			let (reserve_in, reserve_out) = Self::directed_reserves(pool, asset_in)?;
+
+			Ok(pool_amount_out::<T>(pool, asset_in, amount_in, reserve_in, reserve_out)?)
+		}
+
+		/// The amount of `asset_in` needed to receive exactly `amount_out` of the other asset.
+		pub fn quote_exact_out(
+			pool: PoolIdOf<T>,
+			asset_in: T::CurrencyId,
+			amount_out: T::Balance,
+		) -> Result<T::Balance, DispatchError> {
+			let (reserve_in, reserve_out) = Self::directed_reserves(pool, asset_in)?;
+
+			Ok(pool_amount_in::<T>(pool, asset_in, amount_out, reserve_in, reserve_out)?)
+		}
+
+		/// The amount of `asset_in` paid per unit of the other asset for an infinitesimal swap,
+		/// without the swap fee.
+		pub fn spot_price(
+			pool: PoolIdOf<T>,
+			asset_in: T::CurrencyId,
+		) -> Result<FixedU128, DispatchError> {
+			let (reserve_in, reserve_out) = Self::directed_reserves(pool, asset_in)?;
+
+			Ok(directed_curve::<T>(pool, asset_in).spot_price(reserve_in, reserve_out)?)
+		}
+
+		/// The amount of `asset_out` received for swapping exactly `amount_in` of `asset_in`
+		/// in a basket.
+		pub fn quote_basket(
+			pool: AssetsOf<T>,
+			asset_in: T::CurrencyId,
+			asset_out: T::CurrencyId,
+			amount_in: T::Balance,
+		) -> Result<T::Balance, DispatchError> {
+			Ok(basket::quote::<T>(&pool, asset_in, asset_out, amount_in)?)
+		}
+
+		pub fn lp_balance(pool: PoolIdOf<T>, who: T::AccountId) -> T::Balance {
+			T::LpTokens::balance(&pool.into(), &who)
+		}
+
+		pub fn total_supply(pool: PoolIdOf<T>) -> T::Balance {
+			<TotalSupply<T>>::get(LpTokenOf::<T>::from(pool))
+		}
+
+		pub fn basket_lp_balance(pool: AssetsOf<T>, who: T::AccountId) -> T::Balance {
+			T::LpTokens::balance(&LpToken::Basket(pool), &who)
+		}
+
+		pub fn basket_total_supply(pool: AssetsOf<T>) -> T::Balance {
+			<TotalSupply<T>>::get(LpTokenOf::<T>::Basket(pool))
+		}
+
+		/// The cumulative prices of Asset1 and Asset2.
+		pub fn price_cumulative(pool: PoolIdOf<T>) -> (FixedU128, FixedU128) {
+			(<Price0CumulativeLast<T>>::get(pool), <Price1CumulativeLast<T>>::get(pool))
+		}
+
+		/// The average prices of Asset1 and Asset2 over at least the last `window`,
+		/// starting at the newest observation that is old enough.
+		pub fn consult(
+			pool: PoolIdOf<T>,
+			window: T::Moment,
+		) -> Result<(FixedU128, FixedU128), DispatchError> {
+			Ok(consult::<T>(pool, window)?)
+		}
+
+		/// All pools with their reserves and swap fee.
+		pub fn pools() -> Vec<(PoolIdOf<T>, T::Balance, T::Balance, Permill)> {
+			<Reserves<T>>::iter()
+				.map(|(pool, reserves)| {
+					(pool, reserves.reserve_0, reserves.reserve_1, <SwapFees<T>>::get(pool))
+				})
+				.collect()
+		}
+
+		fn directed_reserves(
+			pool: PoolIdOf<T>,
+			asset_in: T::CurrencyId,
+		) -> Result<(T::Balance, T::Balance), Error<T>> {
+			let reserves = <Reserves<T>>::get(pool).ok_or(Error::<T>::PairNotFound)?;
+
+			if asset_in == pool.0 {
+				Ok((reserves.reserve_0, reserves.reserve_1))
+			} else if asset_in == pool.1 {
+				Ok((reserves.reserve_1, reserves.reserve_0))
+			} else {
+				Err(Error::<T>::InvalidSwapToken)
+			}
+		}
 	}
 }
 
@@ -494,3 +1634,83 @@ impl<T: Config> AmmExtension<T::AccountId, T::CurrencyId, T::Balance, T::Moment>
 		T::Balance::zero()
 	}
 }
+
+/// Keeps the LP token balances of the pools and baskets, identified by `LpToken`. The pallet
+/// checks the balances before burning or transferring, and keeps track of the total supply
+/// itself.
+pub trait LpTokens<AccountId, LpTokenId, Balance> {
+	fn balance(token: &LpTokenId, who: &AccountId) -> Balance;
+	fn mint(token: &LpTokenId, to: &AccountId, amount: Balance) -> DispatchResult;
+	fn burn(token: &LpTokenId, from: &AccountId, amount: Balance) -> DispatchResult;
+	fn transfer(
+		token: &LpTokenId,
+		from: &AccountId,
+		to: &AccountId,
+		amount: Balance,
+	) -> DispatchResult;
+}
+
+/// Keeps the LP token balances in the `LpBalances` storage of this pallet.
+pub struct LpBalancesStorage<T>(PhantomData<T>);
+
+impl<T: Config> LpTokens<T::AccountId, LpTokenOf<T>, T::Balance> for LpBalancesStorage<T> {
+	fn balance(token: &LpTokenOf<T>, who: &T::AccountId) -> T::Balance {
+		<LpBalances<T>>::get(token, who).unwrap_or_else(T::Balance::zero)
+	}
+
+	fn mint(token: &LpTokenOf<T>, to: &T::AccountId, amount: T::Balance) -> DispatchResult {
+		<LpBalances<T>>::insert(token, to, Self::balance(token, to).saturating_add(amount));
+
+		Ok(())
+	}
+
+	fn burn(token: &LpTokenOf<T>, from: &T::AccountId, amount: T::Balance) -> DispatchResult {
+		let balance = <LpBalances<T>>::get(token, from).ok_or(Error::<T>::Forbidden)?;
+		<LpBalances<T>>::insert(token, from, balance.saturating_sub(amount));
+
+		Ok(())
+	}
+
+	fn transfer(
+		token: &LpTokenOf<T>,
+		from: &T::AccountId,
+		to: &T::AccountId,
+		amount: T::Balance,
+	) -> DispatchResult {
+		<LpBalances<T>>::insert(token, from, Self::balance(token, from).saturating_sub(amount));
+		<LpBalances<T>>::insert(token, to, Self::balance(token, to).saturating_add(amount));
+
+		Ok(())
+	}
+}
+
+/// Pays for the assets sent out by `flash_swap`.
+pub trait FlashLoanReceiver<AccountId, CurrencyId, Balance> {
+	/// Called after `amount_0_out` and `amount_1_out` of the assets of `pool` were sent to `to`.
+	/// The input, including the swap fee, has to be transferred to `pool_account` before this
+	/// returns. `data` is passed on from `flash_swap`.
+	fn on_flash_swap(
+		sender: &AccountId,
+		to: &AccountId,
+		pool: (CurrencyId, CurrencyId),
+		pool_account: &AccountId,
+		amount_0_out: Balance,
+		amount_1_out: Balance,
+		data: &[u8],
+	) -> DispatchResult;
+}
+
+/// Does not pay anything, so that only flash swaps paid for beforehand succeed.
+impl<AccountId, CurrencyId, Balance> FlashLoanReceiver<AccountId, CurrencyId, Balance> for () {
+	fn on_flash_swap(
+		_sender: &AccountId,
+		_to: &AccountId,
+		_pool: (CurrencyId, CurrencyId),
+		_pool_account: &AccountId,
+		_amount_0_out: Balance,
+		_amount_1_out: Balance,
+		_data: &[u8],
+	) -> DispatchResult {
+		Ok(())
+	}
+}
@@ -1,18 +1,19 @@
 use frame_support::{
 	pallet_prelude::GenesisBuild,
 	parameter_types, sp_io,
-	traits::{ConstU128, ConstU16, ConstU64},
+	traits::{ConstU128, ConstU16, ConstU32, ConstU64},
+	PalletId,
 };
 use frame_system as system;
 use sp_runtime::{
 	testing::Header,
 	traits::{BlakeTwo256, IdentityLookup},
-	DispatchResult,
+	DispatchResult, Permill,
 };
 use std::{cell::RefCell, collections::HashMap};
 
 use crate as amm;
-use amm::{pallet::Config, AmmExtension};
+use amm::{pallet::Config, AmmExtension, FlashLoanReceiver};
 use sp_runtime::app_crypto::sp_core;
 
 pub type UncheckedExtrinsic = system::mocking::MockUncheckedExtrinsic<Test>;
@@ -50,6 +51,7 @@ const SLOT_DURATION: u64 = MILLISECS_PER_BLOCK;
 
 const EUR: [u8; 12] = [69, 85, 82, 0, 0, 0, 0, 0, 0, 0, 0, 0];
 const USDC: [u8; 12] = [85, 83, 68, 67, 0, 0, 0, 0, 0, 0, 0, 0];
+const XLM: [u8; 12] = [88, 76, 77, 0, 0, 0, 0, 0, 0, 0, 0, 0];
 
 const ISSUER: [u8; 32] = [
 	20, 209, 150, 49, 176, 55, 23, 217, 171, 154, 54, 110, 16, 50, 30, 226, 102, 231, 46, 199, 108,
@@ -60,6 +62,11 @@ pub const ASSET_0: Asset = Asset { code: EUR, issuer: ISSUER };
 
 pub const ASSET_1: Asset = Asset { code: USDC, issuer: ISSUER };
 
+pub const ASSET_2: Asset = Asset { code: XLM, issuer: ISSUER };
+
+/// The EUR/USDC pool which is created at genesis.
+pub const POOL: (Asset, Asset) = (ASSET_0, ASSET_1);
+
 // Configure a mock runtime to test the pallet.
 frame_support::construct_runtime!(
 	pub enum Test where
@@ -103,6 +110,8 @@ impl system::Config for Test {
 
 parameter_types! {
 	pub const MinimumPeriod: u64 = SLOT_DURATION / 2;
+	pub const AmmPalletId: PalletId = PalletId(*b"pen/amm_");
+	pub const AmmSwapFee: Permill = Permill::from_perthousand(3);
 }
 
 impl pallet_timestamp::Config for Test {
@@ -113,39 +122,38 @@ impl pallet_timestamp::Config for Test {
 	type WeightInfo = ();
 }
 
-parameter_types! {
-	pub Asset0: Asset = ASSET_0;
-	pub Asset1: Asset = ASSET_1;
-}
-
 impl Config for Test {
 	type Event = Event;
 	type WeightInfo = amm::weights::WeightInfo<Test>;
 	type Balance = Balance;
 	type CurrencyId = Asset;
 	type AmmExtension = Extension;
+	type LpTokens = amm::LpBalancesStorage<Test>;
+	type FlashLoanReceiver = FlashReceiver;
+	type PalletId = AmmPalletId;
 	type MinimumLiquidity = ConstU128<1000>;
 	type MintFee = ConstU128<5>;
-	type BaseFee = ConstU128<3>;
-	type Asset0 = Asset0;
-	type Asset1 = Asset1;
+	type DefaultSwapFee = AmmSwapFee;
+	type AdminOrigin = system::EnsureRoot<AccountId>;
+	type MaxObservations = ConstU32<4>;
+	type MaxPathLength = ConstU32<4>;
+	type MaxFlashDataLen = ConstU32<64>;
+	type MinRampTime = ConstU64<86_400_000>;
+	type MaxAssetsPerPool = ConstU32<4>;
 }
 
 thread_local! {
-	pub static ASSETSMAP0: RefCell<HashMap<AccountId,Balance>> = RefCell::new(HashMap::new());
-	pub static ASSETSMAP1: RefCell<HashMap<AccountId,Balance>> = RefCell::new(HashMap::new());
+	pub static BALANCES: RefCell<HashMap<(AccountId, Asset), Balance>> = RefCell::new(HashMap::new());
+	/// Called once by the next transfer, like a malicious extension calling back into the pallet.
+	pub static REENTRANT_CALL: RefCell<Option<fn() -> DispatchResult>> = RefCell::new(None);
 }
 
 pub fn new_test_ext() -> sp_io::TestExternalities {
 	let mut system_cfg = system::GenesisConfig::default().build_storage::<Test>().unwrap();
 
-	amm::GenesisConfig::<Test> {
-		contract_id: Some(1),
-		zero_account: Some(0),
-		fee_to_setter: Some(2),
-	}
-	.assimilate_storage(&mut system_cfg)
-	.unwrap();
+	amm::GenesisConfig::<Test> { fee_to_setter: Some(2), pools: vec![POOL] }
+		.assimilate_storage(&mut system_cfg)
+		.unwrap();
 
 	system_cfg.into()
 }
@@ -154,17 +162,7 @@ pub struct Extension;
 
 impl AmmExtension<AccountId, Asset, Balance, Moment> for Extension {
 	fn fetch_balance(owner: &AccountId, asset: Asset) -> Balance {
-		if asset == ASSET_0 {
-			ASSETSMAP0.with(|assets| {
-				let asset_map = assets.borrow();
-				*asset_map.get(owner).unwrap_or(&0)
-			})
-		} else {
-			ASSETSMAP1.with(|assets| {
-				let asset_map = assets.borrow();
-				*asset_map.get(owner).unwrap_or(&0)
-			})
-		}
+		BALANCES.with(|balances| *balances.borrow().get(&(*owner, asset)).unwrap_or(&0))
 	}
 
 	fn transfer_balance(
@@ -173,35 +171,23 @@ impl AmmExtension<AccountId, Asset, Balance, Moment> for Extension {
 		asset: Asset,
 		amount: Balance,
 	) -> DispatchResult {
-		if asset == ASSET_0 {
-			ASSETSMAP0.with(|assets| {
-				let mut asset_map = assets.borrow_mut();
-
-				if let Some(bal) = asset_map.get(from) {
-					let from_bal = bal.checked_sub(amount).unwrap_or(0u128);
-
-					let to_bal = asset_map.get(to).unwrap_or(&0u128);
-					let to_bal = to_bal.checked_add(amount).unwrap_or(*to_bal);
-
-					asset_map.insert(to.clone(), to_bal);
-					asset_map.insert(from.clone(), from_bal);
-				}
-			})
-		} else {
-			ASSETSMAP1.with(|assets| {
-				let mut asset_map = assets.borrow_mut();
-
-				if let Some(bal) = asset_map.get(from) {
-					let new_bal = bal.checked_sub(amount).unwrap_or(0u128);
-
-					let to_bal = asset_map.get(to).unwrap_or(&0u128);
-					let to_bal = to_bal.checked_add(amount).unwrap_or(*to_bal);
-
-					asset_map.insert(to.clone(), to_bal);
-					asset_map.insert(from.clone(), new_bal);
-				}
-			})
+		if let Some(call) = REENTRANT_CALL.with(|call| call.borrow_mut().take()) {
+			call()?;
 		}
+
+		BALANCES.with(|balances| {
+			let mut balance_map = balances.borrow_mut();
+
+			if let Some(bal) = balance_map.get(&(*from, asset)) {
+				let from_bal = bal.checked_sub(amount).unwrap_or(0u128);
+
+				let to_bal = balance_map.get(&(*to, asset)).unwrap_or(&0u128);
+				let to_bal = to_bal.checked_add(amount).unwrap_or(*to_bal);
+
+				balance_map.insert((*to, asset), to_bal);
+				balance_map.insert((*from, asset), from_bal);
+			}
+		});
 		Ok(())
 	}
 
@@ -209,3 +195,38 @@ impl AmmExtension<AccountId, Asset, Balance, Moment> for Extension {
 		Balance::from(moment)
 	}
 }
+
+/// What `FlashReceiver` does with the borrowed assets, encoded as the `data` of `flash_swap`.
+#[derive(codec::Encode, codec::Decode)]
+pub enum FlashAction {
+	/// Pay the given amounts of both assets back to the pool.
+	Repay(Balance, Balance),
+	/// Deposit the given amount of Asset1 into the same pool.
+	Deposit(Balance),
+}
+
+pub struct FlashReceiver;
+
+impl FlashLoanReceiver<AccountId, Asset, Balance> for FlashReceiver {
+	fn on_flash_swap(
+		_sender: &AccountId,
+		to: &AccountId,
+		pool: (Asset, Asset),
+		pool_account: &AccountId,
+		_amount_0_out: Balance,
+		_amount_1_out: Balance,
+		mut data: &[u8],
+	) -> DispatchResult {
+		let action = <FlashAction as codec::Decode>::decode(&mut data)
+			.map_err(|_| sp_runtime::DispatchError::Other("invalid flash action"))?;
+
+		match action {
+			FlashAction::Repay(amount_0, amount_1) => {
+				Extension::transfer_balance(to, pool_account, pool.0, amount_0)?;
+				Extension::transfer_balance(to, pool_account, pool.1, amount_1)
+			},
+			FlashAction::Deposit(amount) =>
+				Amm::deposit_asset_1(Origin::signed(*to), pool, amount, None),
+		}
+	}
+}
@@ -0,0 +1,101 @@
+//! Prices of the pools for other pallets.
+//!
+//! A pallet that needs prices takes the oracle as an associated type of its `Config`:
+//!
+//! ```ignore
+//! type Oracle: AmmOracle<Self::CurrencyId, Self::Balance, Self::Moment>;
+//! ```
+//!
+//! and the runtime sets it to the AMM pallet, while its tests can use `ConstantPriceOracle`.
+
+use crate::{
+	curve::Curve,
+	helper::{directed_curve, sorted_pool},
+	pallet::{Config, Error, Pallet},
+};
+use frame_support::traits::Get;
+use sp_runtime::{traits::Zero, DispatchError, FixedPointNumber, FixedPointOperand, FixedU128};
+use sp_std::marker::PhantomData;
+
+/// Spot prices, average prices and liquidity of the pools.
+pub trait AmmOracle<CurrencyId, Balance, Moment> {
+	/// The price of `base` in `quote` at the current reserves of their pool.
+	fn spot_price(base: CurrencyId, quote: CurrencyId) -> Result<FixedU128, DispatchError>;
+
+	/// The average price of `base` in `quote` over at least the last `window`.
+	fn twap(
+		base: CurrencyId,
+		quote: CurrencyId,
+		window: Moment,
+	) -> Result<FixedU128, DispatchError>;
+
+	/// The reserves of `base` and `quote` in their pool.
+	fn liquidity(base: CurrencyId, quote: CurrencyId) -> Result<(Balance, Balance), DispatchError>;
+}
+
+impl<T: Config> AmmOracle<T::CurrencyId, T::Balance, T::Moment> for Pallet<T> {
+	fn spot_price(base: T::CurrencyId, quote: T::CurrencyId) -> Result<FixedU128, DispatchError> {
+		let (reserve_base, reserve_quote) = Self::liquidity(base, quote)?;
+		if reserve_base.is_zero() || reserve_quote.is_zero() {
+			return Err(Error::<T>::InsufficientLiquidity.into())
+		}
+
+		// the amount of `quote` paid per unit of `base`
+		let pool = sorted_pool::<T>(base, quote)?;
+		Ok(directed_curve::<T>(pool, quote).spot_price(reserve_quote, reserve_base)?)
+	}
+
+	fn twap(
+		base: T::CurrencyId,
+		quote: T::CurrencyId,
+		window: T::Moment,
+	) -> Result<FixedU128, DispatchError> {
+		let pool = sorted_pool::<T>(base, quote)?;
+		let (price_0, price_1) = Self::consult(pool, window)?;
+
+		Ok(if base == pool.0 { price_0 } else { price_1 })
+	}
+
+	fn liquidity(
+		base: T::CurrencyId,
+		quote: T::CurrencyId,
+	) -> Result<(T::Balance, T::Balance), DispatchError> {
+		let pool = sorted_pool::<T>(base, quote)?;
+		let (reserve_0, reserve_1, _) = Self::get_reserves(pool).ok_or(Error::<T>::PairNotFound)?;
+
+		Ok(if base == pool.0 { (reserve_0, reserve_1) } else { (reserve_1, reserve_0) })
+	}
+}
+
+/// An oracle for tests, with the same `Price` of every asset in every other asset
+/// and a reserve of `Reserve` of every base asset.
+pub struct ConstantPriceOracle<Price, Reserve>(PhantomData<(Price, Reserve)>);
+
+impl<CurrencyId, Balance, Moment, Price, Reserve> AmmOracle<CurrencyId, Balance, Moment>
+	for ConstantPriceOracle<Price, Reserve>
+where
+	Balance: FixedPointOperand,
+	Price: Get<FixedU128>,
+	Reserve: Get<Balance>,
+{
+	fn spot_price(_base: CurrencyId, _quote: CurrencyId) -> Result<FixedU128, DispatchError> {
+		Ok(Price::get())
+	}
+
+	fn twap(
+		_base: CurrencyId,
+		_quote: CurrencyId,
+		_window: Moment,
+	) -> Result<FixedU128, DispatchError> {
+		Ok(Price::get())
+	}
+
+	fn liquidity(
+		_base: CurrencyId,
+		_quote: CurrencyId,
+	) -> Result<(Balance, Balance), DispatchError> {
+		let reserve = Reserve::get();
+
+		Ok((reserve, Price::get().saturating_mul_int(reserve)))
+	}
+}
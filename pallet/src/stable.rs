@@ -0,0 +1,122 @@
+//! The StableSwap invariant of Curve for pools of two assets with reserves `x` and `y`:
+//!
+//! `A * n^n * (x + y) + D = A * n^n * D + D^(n + 1) / (n^n * x * y)`, with `n = 2`.
+//!
+//! The amplification `A` is given like in the pools of Curve, where `A * n^n` is computed as
+//! `A * n`. The larger it is, the closer the curve is to a constant sum `x + y = D` around
+//! the balanced point, while it is still the constant product `x * y = (D / 2)^2` for `A = 0`.
+
+use sp_core::U256;
+
+/// The largest amplification a pool may have.
+pub const MAX_AMPLIFICATION: u32 = 1_000_000;
+
+/// The factor by which a single ramp may increase or decrease the amplification.
+pub const MAX_AMPLIFICATION_CHANGE: u32 = 10;
+
+/// The number of assets in a pool.
+const N: u32 = 2;
+
+/// The limit of Newton's method. It converges in a few iterations for sane reserves.
+const MAX_ITERATIONS: u32 = 255;
+
+/// Solves the invariant for `D` by Newton's method.
+/// `None` if a reserve is zero, the calculation overflows or it does not converge.
+pub fn get_d(x: U256, y: U256, amplification: u32) -> Option<U256> {
+	let n = U256::from(N);
+	let sum = x.checked_add(y)?;
+	if sum.is_zero() {
+		return Some(U256::zero())
+	}
+
+	let ann = U256::from(amplification).checked_mul(n)?;
+	let mut d = sum;
+
+	for _ in 0..MAX_ITERATIONS {
+		// D^(n + 1) / (n^n * x * y)
+		let d_p = d
+			.checked_mul(d)?
+			.checked_div(x.checked_mul(n)?)?
+			.checked_mul(d)?
+			.checked_div(y.checked_mul(n)?)?;
+
+		let d_prev = d;
+		let numerator = ann.checked_mul(sum)?.checked_add(d_p.checked_mul(n)?)?.checked_mul(d)?;
+		let denominator = ann
+			.checked_sub(U256::one())?
+			.checked_mul(d)?
+			.checked_add(n.checked_add(U256::one())?.checked_mul(d_p)?)?;
+		d = numerator.checked_div(denominator)?;
+
+		if abs_diff(d, d_prev) <= U256::one() {
+			return Some(d)
+		}
+	}
+
+	None
+}
+
+/// Solves the invariant with the given `d` for the reserve of one asset,
+/// if the reserve of the other asset is `x`.
+/// `None` if `x` is zero, the calculation overflows or it does not converge.
+pub fn get_y(x: U256, d: U256, amplification: u32) -> Option<U256> {
+	let n = U256::from(N);
+	let ann = U256::from(amplification).checked_mul(n)?;
+
+	// D^(n + 1) / (n^n * x * A * n^n)
+	let c = d
+		.checked_mul(d)?
+		.checked_div(x.checked_mul(n)?)?
+		.checked_mul(d)?
+		.checked_div(ann.checked_mul(n)?)?;
+	let b = x.checked_add(d.checked_div(ann)?)?;
+
+	let mut y = d;
+
+	for _ in 0..MAX_ITERATIONS {
+		let y_prev = y;
+		let numerator = y.checked_mul(y)?.checked_add(c)?;
+		let denominator = y.checked_mul(n)?.checked_add(b)?.checked_sub(d)?;
+		y = numerator.checked_div(denominator)?;
+
+		if abs_diff(y, y_prev) <= U256::one() {
+			return Some(y)
+		}
+	}
+
+	None
+}
+
+/// The amount of the asset with reserve `x` paid per unit of the asset with reserve `y`
+/// for an infinitesimal swap, with 18 decimals:
+/// `x * (A * n^n * y + D_P) / (y * (A * n^n * x + D_P))` with `D_P = D^(n + 1) / (n^n * x * y)`.
+/// `None` if a reserve is zero or the calculation overflows.
+pub fn spot_price(x: U256, y: U256, amplification: u32) -> Option<U256> {
+	let n = U256::from(N);
+	let one = U256::exp10(18);
+	let ann = U256::from(amplification).checked_mul(n)?;
+
+	let d = get_d(x, y, amplification)?;
+	let d_p = d
+		.checked_mul(d)?
+		.checked_div(x.checked_mul(n)?)?
+		.checked_mul(d)?
+		.checked_div(y.checked_mul(n)?)?;
+
+	let ratio = x.checked_mul(one)?.checked_div(y)?;
+	let factor = ann
+		.checked_mul(y)?
+		.checked_add(d_p)?
+		.checked_mul(one)?
+		.checked_div(ann.checked_mul(x)?.checked_add(d_p)?)?;
+
+	ratio.checked_mul(factor)?.checked_div(one)
+}
+
+fn abs_diff(a: U256, b: U256) -> U256 {
+	if a > b {
+		a - b
+	} else {
+		b - a
+	}
+}
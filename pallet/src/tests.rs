@@ -1,15 +1,23 @@
-use crate::{helper::balance_of, mock, mock::*, reserves, Error, Event};
-use frame_support::assert_err;
+use crate::{
+	curve::{ConstantProduct, Curve, PoolCurve, StableSwap},
+	helper::{balance_of, basket_account, mint, pool_account, quote},
+	mock,
+	mock::*,
+	oracle::ConstantPriceOracle,
+	reserves, stable, weighted, AmmExtension, AmmOracle, Amplification, Error, Event, Locks,
+	LpToken, LpTokenOf,
+};
+use codec::Encode;
+use frame_support::{assert_err, assert_noop, traits::ConstU32, BoundedVec};
+use sp_core::U256;
+use sp_runtime::{DispatchError, FixedPointNumber, FixedU128, Permill};
 
 fn add_supply_for_account(account_id: AccountId, supply: Balance) {
-	ASSETSMAP0.with(|assets| {
-		let mut assets_map = assets.borrow_mut();
-		assets_map.insert(account_id, supply);
-	});
-
-	ASSETSMAP1.with(|assets| {
-		let mut assets_map = assets.borrow_mut();
-		assets_map.insert(account_id, supply);
+	BALANCES.with(|balances| {
+		let mut balance_map = balances.borrow_mut();
+		balance_map.insert((account_id, ASSET_0), supply);
+		balance_map.insert((account_id, ASSET_1), supply);
+		balance_map.insert((account_id, ASSET_2), supply);
 	});
 }
 
@@ -18,7 +26,7 @@ fn gained_lp_from_event(expected_event_order: usize) -> Balance {
 	let transfer_event = event.get(expected_event_order).unwrap();
 
 	match &transfer_event.event {
-		mock::Event::Amm(Event::Transfer { from: _, to: _, value }) => *value,
+		mock::Event::Amm(Event::Transfer { value, .. }) => *value,
 		_ => {
 			assert!(false);
 			0
@@ -34,16 +42,19 @@ fn swap_test(
 ) {
 	add_supply_for_account(origin, initial_supply);
 
-	Amm::deposit_asset_1(Origin::signed(origin), deposit_amount).expect("Deposit should work");
+	Amm::deposit_asset_1(Origin::signed(origin), POOL, deposit_amount, None)
+		.expect("Deposit should work");
 
 	let user_balance_0_pre_swap = balance_of::<Test>(&origin, ASSET_0);
 
-	Amm::swap_asset_2_for_asset_1(Origin::signed(origin), swap_amount).expect("Swap should work");
+	Amm::swap_asset_2_for_asset_1(Origin::signed(origin), POOL, swap_amount, Balance::MAX, None)
+		.expect("Swap should work");
 	let user_balance_0_post_swap = balance_of::<Test>(&origin, ASSET_0);
 	assert_eq!(user_balance_0_post_swap, user_balance_0_pre_swap + swap_amount);
 
 	let user_balance_1_pre_swap = balance_of::<Test>(&origin, ASSET_1);
-	Amm::swap_asset_1_for_asset_2(Origin::signed(origin), swap_amount).expect("Swap 2 should work");
+	Amm::swap_asset_1_for_asset_2(Origin::signed(origin), POOL, swap_amount, Balance::MAX, None)
+		.expect("Swap 2 should work");
 
 	let user_balance_1_post_swap = balance_of::<Test>(&origin, ASSET_1);
 	assert_eq!(user_balance_1_post_swap, user_balance_1_pre_swap + swap_amount);
@@ -71,7 +82,8 @@ fn deposit_works_for_balanced_pair() {
 		let user_balance_0_pre_deposit = balance_of::<Test>(&to, ASSET_0);
 		let user_balance_1_pre_deposit = balance_of::<Test>(&to, ASSET_1);
 
-		Amm::deposit_asset_1(Origin::signed(to), deposit_amount).expect("deposit should work");
+		Amm::deposit_asset_1(Origin::signed(to), POOL, deposit_amount, None)
+			.expect("deposit should work");
 
 		let user_balance_0_post_deposit = balance_of::<Test>(&to, ASSET_0);
 		let user_balance_1_post_deposit = balance_of::<Test>(&to, ASSET_1);
@@ -86,7 +98,7 @@ fn deposit_works_for_balanced_pair() {
 
 		// check contract balances
 		let (contract_balance_0_post_deposit, contract_balance_1_post_deposit, _) =
-			reserves::<Test>();
+			reserves::<Test>(POOL);
 		assert_eq!(contract_balance_0_post_deposit, contract_balance_1_post_deposit);
 		assert_eq!(deposit_amount, contract_balance_0_post_deposit);
 	})
@@ -101,16 +113,19 @@ fn deposit_works_for_unbalanced_pair() {
 
 		// execute initial deposit
 		let deposit_amount = 10_000;
-		Amm::deposit_asset_1(Origin::signed(to), deposit_amount).expect("deposit should work");
+		Amm::deposit_asset_1(Origin::signed(to), POOL, deposit_amount, None)
+			.expect("deposit should work");
 
 		// swap to make it unbalanced
-		Amm::swap_asset_2_for_asset_1(Origin::signed(to), 10).expect("swap should work");
+		Amm::swap_asset_2_for_asset_1(Origin::signed(to), POOL, 10, Balance::MAX, None)
+			.expect("swap should work");
 
 		let user_balance_0_pre_deposit = balance_of::<Test>(&to, ASSET_0);
 		let user_balance_1_pre_deposit = balance_of::<Test>(&to, ASSET_1);
 
 		// deposit on unbalanced pair
-		Amm::deposit_asset_1(Origin::signed(to), deposit_amount).expect("2nd deposit should work");
+		Amm::deposit_asset_1(Origin::signed(to), POOL, deposit_amount, None)
+			.expect("2nd deposit should work");
 
 		let user_balance_0_post_deposit = balance_of::<Test>(&to, ASSET_0);
 		let user_balance_1_post_deposit = balance_of::<Test>(&to, ASSET_1);
@@ -133,23 +148,24 @@ fn withdraw_without_lp_fails() {
 		add_supply_for_account(origin_to, initial_supply);
 
 		assert_err!(
-			Amm::withdraw(Origin::signed(origin_to), 1),
+			Amm::withdraw(Origin::signed(origin_to), POOL, 1, None),
 			Error::<Test>::WithdrawWithoutSupply
 		);
 
 		System::set_block_number(1); // to initialize the system, generating the events
 
-		Amm::deposit_asset_1(Origin::signed(origin_to), 5_000).expect("deposit should work");
+		Amm::deposit_asset_1(Origin::signed(origin_to), POOL, 5_000, None)
+			.expect("deposit should work");
 
 		let event = <frame_system::Pallet<Test>>::events();
 		let transfer_event = event.get(1).unwrap();
 
 		match &transfer_event.event {
-			mock::Event::Amm(Event::Transfer { from: _, to: _, value }) => {
+			mock::Event::Amm(Event::Transfer { value, .. }) => {
 				let gained_lp = *value;
 				assert_err!(
 					// try withdrawing more LP than account has
-					Amm::withdraw(Origin::signed(origin_to), gained_lp + 2),
+					Amm::withdraw(Origin::signed(origin_to), POOL, gained_lp + 2, None),
 					Error::<Test>::InsufficientBalance
 				)
 			},
@@ -168,22 +184,22 @@ fn withdraw_works() {
 		System::set_block_number(1); // to initialize the system, generating the events
 
 		let deposit_amount = 50_000;
-		Amm::deposit_asset_1(Origin::signed(origin_to), deposit_amount)
+		Amm::deposit_asset_1(Origin::signed(origin_to), POOL, deposit_amount, None)
 			.expect("deposit should work");
 
 		let user_balance_0_pre_withdraw = balance_of::<Test>(&origin_to, ASSET_0);
 		let user_balance_1_pre_withdraw = balance_of::<Test>(&origin_to, ASSET_1);
 
 		let gained_lp = gained_lp_from_event(1);
-		Amm::withdraw(Origin::signed(origin_to), gained_lp - 1).expect("withdraw should work");
+		Amm::withdraw(Origin::signed(origin_to), POOL, gained_lp - 1, None)
+			.expect("withdraw should work");
 
 		let (amount_0, amount_1) = {
 			let mut event = <frame_system::Pallet<Test>>::events();
 			let burn_event = event.last_mut().unwrap();
 
 			match &burn_event.event {
-				mock::Event::Amm(Event::Burn { sender: _, to: _, amount_0, amount_1 }) =>
-					(*amount_0, *amount_1),
+				mock::Event::Amm(Event::Burn { amounts, .. }) => (amounts[0], amounts[1]),
 				_ => {
 					assert!(false);
 					(0, 0)
@@ -210,27 +226,28 @@ fn deposit_and_withdraw_work() {
 
 		let deposit_amount = 500_000;
 		// do initial deposit which initiates total_supply
-		Amm::deposit_asset_1(Origin::signed(origin_to), deposit_amount)
+		Amm::deposit_asset_1(Origin::signed(origin_to), POOL, deposit_amount, None)
 			.expect("Deposit should work");
 
 		// do second deposit
-		Amm::deposit_asset_1(Origin::signed(origin_to), deposit_amount)
+		Amm::deposit_asset_1(Origin::signed(origin_to), POOL, deposit_amount, None)
 			.expect("2nd deposit should work");
 		let gained_lp = gained_lp_from_event(4);
 
-		Amm::withdraw(Origin::signed(origin_to), gained_lp).expect("withdraw should work");
+		Amm::withdraw(Origin::signed(origin_to), POOL, gained_lp, None)
+			.expect("withdraw should work");
 
 		let mut event = <frame_system::Pallet<Test>>::events();
 		let burn_event = event.last_mut().unwrap();
 
 		match &burn_event.event {
-			mock::Event::Amm(Event::Burn { sender: _, to: _, amount_0, amount_1 }) => {
+			mock::Event::Amm(Event::Burn { amounts, .. }) => {
 				assert_eq!(
-					amount_0, &deposit_amount,
+					&amounts[0], &deposit_amount,
 					"expected withdrawn amount_0 to be == to deposited amount"
 				);
 				assert_eq!(
-					amount_1, &deposit_amount,
+					&amounts[1], &deposit_amount,
 					"expected withdrawn amount_1 to be == to deposited amount"
 				);
 			},
@@ -259,3 +276,1970 @@ fn swap_works_with_large_amount() {
 		);
 	})
 }
+
+#[test]
+fn create_pool_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+
+		// the assets are passed in descending order and get sorted
+		Amm::create_pool(Origin::signed(2), ASSET_2, ASSET_0).expect("create pool should work");
+
+		let pool = (ASSET_0, ASSET_2);
+		let event = <frame_system::Pallet<Test>>::events();
+		match &event.last().unwrap().event {
+			mock::Event::Amm(Event::PoolCreated { pool: created, .. }) =>
+				assert_eq!(created, &pool),
+			_ => assert!(false),
+		}
+
+		let origin_to = 2;
+		add_supply_for_account(origin_to, 100_000);
+		Amm::deposit_asset_1(Origin::signed(origin_to), pool, 10_000, None)
+			.expect("deposit should work");
+
+		assert_eq!(reserves::<Test>(pool).0, 10_000);
+		assert_eq!(reserves::<Test>(POOL).0, 0);
+	})
+}
+
+#[test]
+fn create_pool_fails_for_identical_assets() {
+	new_test_ext().execute_with(|| {
+		assert_err!(
+			Amm::create_pool(Origin::signed(2), ASSET_2, ASSET_2),
+			Error::<Test>::IdenticalAddress
+		);
+	})
+}
+
+#[test]
+fn create_pool_fails_for_existing_pair() {
+	new_test_ext().execute_with(|| {
+		assert_err!(
+			Amm::create_pool(Origin::signed(2), ASSET_1, ASSET_0),
+			Error::<Test>::PairExists
+		);
+	})
+}
+
+#[test]
+fn calls_fail_for_unknown_pool() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		add_supply_for_account(origin_to, 100_000);
+
+		let pool = (ASSET_1, ASSET_2);
+		assert_err!(
+			Amm::deposit_asset_1(Origin::signed(origin_to), pool, 10_000, None),
+			Error::<Test>::PairNotFound
+		);
+		assert_err!(
+			Amm::swap_asset_1_for_asset_2(Origin::signed(origin_to), pool, 10, Balance::MAX, None),
+			Error::<Test>::PairNotFound
+		);
+		assert_err!(
+			Amm::withdraw(Origin::signed(origin_to), pool, 1, None),
+			Error::<Test>::PairNotFound
+		);
+	})
+}
+
+#[test]
+fn pools_sharing_an_asset_hold_separate_reserves() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		add_supply_for_account(origin_to, 1_000_000);
+
+		let other_pool = (ASSET_0, ASSET_2);
+		Amm::create_pool(Origin::signed(origin_to), ASSET_0, ASSET_2)
+			.expect("create pool should work");
+
+		let account = pool_account::<Test>(POOL).expect("account should be derived");
+		let other_account = pool_account::<Test>(other_pool).expect("account should be derived");
+		assert_ne!(account, other_account);
+
+		Amm::deposit_asset_1(Origin::signed(origin_to), POOL, 10_000, None)
+			.expect("deposit should work");
+		Amm::deposit_asset_1(Origin::signed(origin_to), other_pool, 20_000, None)
+			.expect("deposit should work");
+
+		assert_eq!(balance_of::<Test>(&account, ASSET_0), 10_000);
+		assert_eq!(balance_of::<Test>(&other_account, ASSET_0), 20_000);
+
+		// syncing one pool must not pick up the balance of the other one
+		Amm::sync(Origin::signed(origin_to), POOL).expect("sync should work");
+		assert_eq!(reserves::<Test>(POOL).0, 10_000);
+		assert_eq!(reserves::<Test>(other_pool).0, 20_000);
+	})
+}
+
+#[test]
+fn swap_exact_in_works() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		add_supply_for_account(origin_to, 1_000_000);
+
+		Amm::deposit_asset_1(Origin::signed(origin_to), POOL, 100_000, None)
+			.expect("deposit should work");
+
+		let amount_in = 1_000;
+		let user_balance_0_pre_swap = balance_of::<Test>(&origin_to, ASSET_0);
+		let user_balance_1_pre_swap = balance_of::<Test>(&origin_to, ASSET_1);
+
+		Amm::swap_exact_in_asset_1_for_asset_2(
+			Origin::signed(origin_to),
+			POOL,
+			amount_in,
+			980,
+			None,
+		)
+		.expect("swap should work");
+
+		let user_balance_0_post_swap = balance_of::<Test>(&origin_to, ASSET_0);
+		let user_balance_1_post_swap = balance_of::<Test>(&origin_to, ASSET_1);
+
+		assert_eq!(user_balance_0_post_swap, user_balance_0_pre_swap - amount_in);
+		// 1000 * 997 * 100_000 / (100_000 * 1000 + 1000 * 997)
+		assert_eq!(user_balance_1_post_swap, user_balance_1_pre_swap + 987);
+
+		let user_balance_0_pre_swap = user_balance_0_post_swap;
+		Amm::swap_exact_in_asset_2_for_asset_1(
+			Origin::signed(origin_to),
+			POOL,
+			amount_in,
+			980,
+			None,
+		)
+		.expect("swap should work");
+		assert!(balance_of::<Test>(&origin_to, ASSET_0) > user_balance_0_pre_swap);
+	})
+}
+
+#[test]
+fn swap_exact_in_fails_below_min_amount_out() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		add_supply_for_account(origin_to, 1_000_000);
+
+		Amm::deposit_asset_1(Origin::signed(origin_to), POOL, 100_000, None)
+			.expect("deposit should work");
+
+		assert_err!(
+			Amm::swap_exact_in_asset_1_for_asset_2(
+				Origin::signed(origin_to),
+				POOL,
+				1_000,
+				988,
+				None
+			),
+			Error::<Test>::SlippageExceeded
+		);
+		assert_err!(
+			Amm::swap_exact_in_asset_2_for_asset_1(
+				Origin::signed(origin_to),
+				POOL,
+				1_000,
+				988,
+				None
+			),
+			Error::<Test>::SlippageExceeded
+		);
+	})
+}
+
+#[test]
+fn swap_exact_out_fails_above_max_amount_in() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		add_supply_for_account(origin_to, 1_000_000);
+
+		Amm::deposit_asset_1(Origin::signed(origin_to), POOL, 100_000, None)
+			.expect("deposit should work");
+
+		// receiving 987 requires an input of 1000
+		assert_err!(
+			Amm::swap_asset_1_for_asset_2(Origin::signed(origin_to), POOL, 987, 999, None),
+			Error::<Test>::SlippageExceeded
+		);
+		Amm::swap_asset_1_for_asset_2(Origin::signed(origin_to), POOL, 987, 1_000, None)
+			.expect("swap should work");
+	})
+}
+
+/// Adds liquidity to `POOL` and creates the USDC/XLM pool with the same reserves.
+fn setup_path_pools(origin: AccountId) {
+	add_supply_for_account(origin, 1_000_000);
+
+	Amm::add_liquidity(Origin::signed(origin), POOL, 100_000, 100_000, 0, 0, None)
+		.expect("add liquidity should work");
+
+	Amm::create_pool(Origin::signed(origin), ASSET_1, ASSET_2).expect("create pool should work");
+	Amm::add_liquidity(Origin::signed(origin), (ASSET_1, ASSET_2), 100_000, 100_000, 0, 0, None)
+		.expect("add liquidity should work");
+}
+
+fn path(assets: Vec<Asset>) -> BoundedVec<Asset, ConstU32<4>> {
+	assets.try_into().expect("path should not be too long")
+}
+
+fn basket(assets: Vec<Asset>) -> BoundedVec<Asset, ConstU32<4>> {
+	assets.try_into().expect("basket should not have too many assets")
+}
+
+fn amounts(amounts: Vec<Balance>) -> BoundedVec<Balance, ConstU32<4>> {
+	amounts.try_into().expect("basket should not have too many assets")
+}
+
+fn flash_data(data: Vec<u8>) -> BoundedVec<u8, ConstU32<64>> {
+	data.try_into().expect("flash swap data should not be too long")
+}
+
+#[test]
+fn swap_exact_in_along_path_works() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		setup_path_pools(origin_to);
+
+		Amm::swap_exact_in_along_path(
+			Origin::signed(origin_to),
+			path(vec![ASSET_0, ASSET_1, ASSET_2]),
+			1_000,
+			975,
+			None,
+		)
+		.expect("swap should work");
+
+		// 987 USDC go from the first pool to the second one
+		assert_eq!(balance_of::<Test>(&origin_to, ASSET_0), 1_000_000 - 100_000 - 1_000);
+		assert_eq!(balance_of::<Test>(&origin_to, ASSET_1), 1_000_000 - 200_000);
+		assert_eq!(balance_of::<Test>(&origin_to, ASSET_2), 1_000_000 - 100_000 + 975);
+		assert_eq!(reserves::<Test>(POOL).1, 100_000 - 987);
+		assert_eq!(reserves::<Test>((ASSET_1, ASSET_2)).0, 100_000 + 987);
+	})
+}
+
+#[test]
+fn swap_exact_out_along_path_works() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		setup_path_pools(origin_to);
+
+		Amm::swap_exact_out_along_path(
+			Origin::signed(origin_to),
+			path(vec![ASSET_0, ASSET_1, ASSET_2]),
+			975,
+			1_002,
+			None,
+		)
+		.expect("swap should work");
+
+		assert_eq!(balance_of::<Test>(&origin_to, ASSET_0), 1_000_000 - 100_000 - 1_002);
+		assert_eq!(balance_of::<Test>(&origin_to, ASSET_1), 1_000_000 - 200_000);
+		assert_eq!(balance_of::<Test>(&origin_to, ASSET_2), 1_000_000 - 100_000 + 975);
+		assert_eq!(reserves::<Test>((ASSET_1, ASSET_2)).0, 100_000 + 988);
+	})
+}
+
+#[test]
+fn exact_out_quotes_fail_beyond_the_reserves() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		setup_path_pools(origin_to);
+
+		assert_err!(
+			Amm::quote_exact_out(POOL, ASSET_0, 100_000),
+			Error::<Test>::InsufficientLiquidity
+		);
+		assert_err!(
+			Amm::swap_exact_out_along_path(
+				Origin::signed(origin_to),
+				path(vec![ASSET_0, ASSET_1, ASSET_2]),
+				100_000,
+				Balance::MAX,
+				None,
+			),
+			Error::<Test>::InsufficientLiquidity
+		);
+	})
+}
+
+#[test]
+fn swap_along_path_fails_for_invalid_paths() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		setup_path_pools(origin_to);
+
+		assert_err!(
+			Amm::swap_exact_in_along_path(
+				Origin::signed(origin_to),
+				path(vec![ASSET_0]),
+				1_000,
+				0,
+				None
+			),
+			Error::<Test>::InvalidPath
+		);
+		assert_err!(
+			Amm::swap_exact_in_along_path(
+				Origin::signed(origin_to),
+				path(vec![ASSET_0, ASSET_2]),
+				1_000,
+				0,
+				None
+			),
+			Error::<Test>::PairNotFound
+		);
+		assert_err!(
+			Amm::swap_exact_in_along_path(
+				Origin::signed(origin_to),
+				path(vec![ASSET_0, ASSET_1, ASSET_2]),
+				1_000,
+				976,
+				None
+			),
+			Error::<Test>::SlippageExceeded
+		);
+		assert_err!(
+			Amm::swap_exact_out_along_path(
+				Origin::signed(origin_to),
+				path(vec![ASSET_0, ASSET_1, ASSET_2]),
+				975,
+				1_001,
+				None
+			),
+			Error::<Test>::SlippageExceeded
+		);
+	})
+}
+
+#[test]
+fn fee_to_setter_can_be_handed_over() {
+	new_test_ext().execute_with(|| {
+		let fee_to_setter = 2;
+		let new_fee_to_setter = 3;
+
+		System::set_block_number(1); // to initialize the system, generating the events
+
+		assert_err!(
+			Amm::set_fee_to_setter(Origin::signed(new_fee_to_setter), new_fee_to_setter),
+			Error::<Test>::Forbidden
+		);
+
+		Amm::set_fee_to_setter(Origin::signed(fee_to_setter), new_fee_to_setter)
+			.expect("handing over should work");
+		assert_eq!(Amm::fee_to_setter(), Some(new_fee_to_setter));
+		System::assert_last_event(mock::Event::Amm(Event::FeeToSetterChanged {
+			fee_to_setter: new_fee_to_setter,
+		}));
+
+		// only the new setter can set `fee_to` now
+		assert_err!(
+			Amm::set_fee_to(Origin::signed(fee_to_setter), fee_to_setter),
+			Error::<Test>::Forbidden
+		);
+		Amm::set_fee_to(Origin::signed(new_fee_to_setter), 4).expect("set fee_to should work");
+		assert_eq!(Amm::fee_to(), Some(4));
+		System::assert_last_event(mock::Event::Amm(Event::FeeToChanged { fee_to: Some(4) }));
+	})
+}
+
+#[test]
+fn force_set_fee_to_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1); // to initialize the system, generating the events
+
+		assert_err!(Amm::force_set_fee_to(Origin::signed(2), Some(4)), DispatchError::BadOrigin);
+
+		Amm::force_set_fee_to(Origin::root(), Some(4)).expect("force set fee_to should work");
+		assert_eq!(Amm::fee_to(), Some(4));
+
+		Amm::force_set_fee_to(Origin::root(), None).expect("force set fee_to should work");
+		assert_eq!(Amm::fee_to(), None);
+		System::assert_last_event(mock::Event::Amm(Event::FeeToChanged { fee_to: None }));
+	})
+}
+
+#[test]
+fn set_swap_fee_works() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		let fee = Permill::from_percent(1);
+
+		System::set_block_number(1); // to initialize the system, generating the events
+
+		assert_eq!(Amm::swap_fee(POOL), Permill::from_perthousand(3));
+
+		assert_err!(
+			Amm::set_swap_fee(Origin::signed(origin_to), POOL, fee),
+			DispatchError::BadOrigin
+		);
+		assert_err!(
+			Amm::set_swap_fee(Origin::root(), (ASSET_1, ASSET_2), fee),
+			Error::<Test>::PairNotFound
+		);
+		assert_err!(
+			Amm::set_swap_fee(Origin::root(), POOL, Permill::one()),
+			Error::<Test>::InvalidFee
+		);
+
+		Amm::set_swap_fee(Origin::root(), POOL, fee).expect("setting the fee should work");
+		assert_eq!(Amm::swap_fee(POOL), fee);
+		assert_eq!(Amm::swap_fee((ASSET_0, ASSET_2)), Permill::from_perthousand(3));
+
+		System::assert_last_event(mock::Event::Amm(Event::SwapFeeSet { pool: POOL, fee }));
+	})
+}
+
+#[test]
+fn swaps_use_the_swap_fee_of_the_pool() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		add_supply_for_account(origin_to, 1_000_000);
+
+		Amm::deposit_asset_1(Origin::signed(origin_to), POOL, 100_000, None)
+			.expect("deposit should work");
+		Amm::set_swap_fee(Origin::root(), POOL, Permill::from_percent(1))
+			.expect("setting the fee should work");
+
+		// 990 * 100_000 / (100_000 + 990)
+		assert_eq!(Amm::quote_exact_in(POOL, ASSET_0, 1_000), Ok(980));
+		assert_eq!(Amm::quote_exact_out(POOL, ASSET_0, 980), Ok(1_000));
+
+		let user_balance_1_pre_swap = balance_of::<Test>(&origin_to, ASSET_1);
+		Amm::swap_exact_in_asset_1_for_asset_2(Origin::signed(origin_to), POOL, 1_000, 980, None)
+			.expect("swap should work");
+		assert_eq!(balance_of::<Test>(&origin_to, ASSET_1), user_balance_1_pre_swap + 980);
+
+		// the quoted input passes the invariant check of the swap
+		let amount_in = Amm::quote_exact_out(POOL, ASSET_1, 500).expect("quote should work");
+		let user_balance_0_pre_swap = balance_of::<Test>(&origin_to, ASSET_0);
+		let user_balance_1_pre_swap = balance_of::<Test>(&origin_to, ASSET_1);
+		Amm::swap_asset_2_for_asset_1(Origin::signed(origin_to), POOL, 500, amount_in, None)
+			.expect("swap should work");
+		assert_eq!(balance_of::<Test>(&origin_to, ASSET_0), user_balance_0_pre_swap + 500);
+		assert_eq!(balance_of::<Test>(&origin_to, ASSET_1), user_balance_1_pre_swap - amount_in);
+	})
+}
+
+#[test]
+fn calls_fail_after_deadline() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		add_supply_for_account(origin_to, 1_000_000);
+
+		Timestamp::set_timestamp(1_000);
+
+		Amm::deposit_asset_1(Origin::signed(origin_to), POOL, 10_000, Some(1_000))
+			.expect("deposit should work before the deadline");
+
+		assert_err!(
+			Amm::deposit_asset_1(Origin::signed(origin_to), POOL, 10_000, Some(999)),
+			Error::<Test>::Expired
+		);
+		assert_err!(
+			Amm::deposit_asset_2(Origin::signed(origin_to), POOL, 10_000, Some(999)),
+			Error::<Test>::Expired
+		);
+		assert_err!(
+			Amm::withdraw(Origin::signed(origin_to), POOL, 1, Some(999)),
+			Error::<Test>::Expired
+		);
+		assert_err!(
+			Amm::swap_asset_1_for_asset_2(
+				Origin::signed(origin_to),
+				POOL,
+				10,
+				Balance::MAX,
+				Some(999)
+			),
+			Error::<Test>::Expired
+		);
+		assert_err!(
+			Amm::swap_asset_2_for_asset_1(
+				Origin::signed(origin_to),
+				POOL,
+				10,
+				Balance::MAX,
+				Some(999)
+			),
+			Error::<Test>::Expired
+		);
+		assert_err!(
+			Amm::swap_exact_in_asset_1_for_asset_2(
+				Origin::signed(origin_to),
+				POOL,
+				10,
+				0,
+				Some(999)
+			),
+			Error::<Test>::Expired
+		);
+		assert_err!(
+			Amm::swap_exact_in_asset_2_for_asset_1(
+				Origin::signed(origin_to),
+				POOL,
+				10,
+				0,
+				Some(999)
+			),
+			Error::<Test>::Expired
+		);
+
+		Amm::swap_asset_1_for_asset_2(Origin::signed(origin_to), POOL, 10, Balance::MAX, None)
+			.expect("swap without deadline should work");
+	})
+}
+
+#[test]
+fn cumulative_prices_keep_fractions() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		add_supply_for_account(origin_to, 100_000);
+
+		Amm::add_liquidity(Origin::signed(origin_to), POOL, 10_000, 30_000, 0, 0, None)
+			.expect("add liquidity should work");
+
+		Timestamp::set_timestamp(1_000);
+		Amm::sync(Origin::signed(origin_to), POOL).expect("sync should work");
+
+		// 30_000 / 10_000 and 10_000 / 30_000 for 1_000 milliseconds
+		assert_eq!(Amm::price_0_cumulative_last(POOL), FixedU128::saturating_from_integer(3_000));
+		assert_eq!(
+			Amm::price_1_cumulative_last(POOL),
+			FixedU128::from_inner(333_333_333_333_333_333 * 1_000)
+		);
+
+		Timestamp::set_timestamp(1_500);
+		Amm::sync(Origin::signed(origin_to), POOL).expect("sync should work");
+		assert_eq!(Amm::price_0_cumulative_last(POOL), FixedU128::saturating_from_integer(4_500));
+	})
+}
+
+#[test]
+fn sync_fails_when_the_spot_price_overflows() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		add_supply_for_account(origin_to, 10u128.pow(24));
+
+		// the price of Asset1 is 10^21, which does not fit into a FixedU128
+		Amm::add_liquidity(Origin::signed(origin_to), POOL, 1_000, 10u128.pow(24), 0, 0, None)
+			.expect("add liquidity should work");
+
+		Timestamp::set_timestamp(1_000);
+		assert_err!(Amm::sync(Origin::signed(origin_to), POOL), Error::<Test>::Overflow);
+		assert_eq!(Amm::price_0_cumulative_last(POOL), FixedU128::from_inner(0));
+	})
+}
+
+#[test]
+fn consult_returns_time_weighted_average_prices() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		add_supply_for_account(origin_to, 100_000);
+
+		Amm::add_liquidity(Origin::signed(origin_to), POOL, 10_000, 30_000, 0, 0, None)
+			.expect("add liquidity should work");
+
+		Timestamp::set_timestamp(1_000);
+		Amm::sync(Origin::signed(origin_to), POOL).expect("sync should work");
+
+		// the price of Asset1 drops from 3 to 1.5
+		Timestamp::set_timestamp(2_000);
+		let pool_account = pool_account::<Test>(POOL).unwrap();
+		Extension::transfer_balance(&origin_to, &pool_account, ASSET_0, 10_000)
+			.expect("transfer should work");
+		Amm::sync(Origin::signed(origin_to), POOL).expect("sync should work");
+
+		// the last 1_000 milliseconds are not yet in the cumulative prices
+		Timestamp::set_timestamp(3_000);
+		assert_eq!(
+			Amm::consult(POOL, 2_000),
+			Ok((
+				FixedU128::saturating_from_rational(9, 4),
+				FixedU128::from_inner(499_999_999_999_999_999)
+			))
+		);
+		assert_eq!(
+			Amm::consult(POOL, 500),
+			Ok((
+				FixedU128::saturating_from_rational(3, 2),
+				FixedU128::from_inner(666_666_666_666_666_666)
+			))
+		);
+
+		assert_eq!(Amm::consult(POOL, 2_001), Err(Error::<Test>::InsufficientHistory.into()));
+		assert_eq!(Amm::consult((ASSET_0, ASSET_2), 500), Err(Error::<Test>::PairNotFound.into()));
+	})
+}
+
+#[test]
+fn observations_overwrite_the_oldest_entry() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		add_supply_for_account(origin_to, 100_000);
+
+		Amm::deposit_asset_1(Origin::signed(origin_to), POOL, 10_000, None)
+			.expect("deposit should work");
+
+		for timestamp in (1..=6).map(|i| i * 1_000) {
+			Timestamp::set_timestamp(timestamp);
+			Amm::sync(Origin::signed(origin_to), POOL).expect("sync should work");
+		}
+
+		// `MaxObservations` is 4 in the mock
+		let timestamps: Vec<u64> = Amm::observations(POOL)
+			.iter()
+			.map(|observation| observation.timestamp)
+			.collect();
+		assert_eq!(timestamps, vec![5_000, 6_000, 3_000, 4_000]);
+
+		assert_eq!(Amm::consult(POOL, 3_000), Ok((FixedU128::one(), FixedU128::one())));
+		assert_eq!(Amm::consult(POOL, 3_001), Err(Error::<Test>::InsufficientHistory.into()));
+	})
+}
+
+#[test]
+fn oracle_reports_prices_and_liquidity() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		add_supply_for_account(origin_to, 100_000);
+
+		Amm::add_liquidity(Origin::signed(origin_to), POOL, 10_000, 30_000, 0, 0, None)
+			.expect("add liquidity should work");
+
+		assert_eq!(
+			<Amm as AmmOracle<_, _, _>>::spot_price(ASSET_0, ASSET_1),
+			Ok(FixedU128::saturating_from_integer(3))
+		);
+		assert_eq!(
+			<Amm as AmmOracle<_, _, _>>::spot_price(ASSET_1, ASSET_0),
+			Ok(FixedU128::from_inner(333_333_333_333_333_333))
+		);
+		assert_eq!(<Amm as AmmOracle<_, _, _>>::liquidity(ASSET_1, ASSET_0), Ok((30_000, 10_000)));
+
+		Timestamp::set_timestamp(1_000);
+		Amm::sync(Origin::signed(origin_to), POOL).expect("sync should work");
+		Timestamp::set_timestamp(2_000);
+		assert_eq!(
+			<Amm as AmmOracle<_, _, _>>::twap(ASSET_0, ASSET_1, 1_000),
+			Ok(FixedU128::saturating_from_integer(3))
+		);
+		assert_eq!(
+			<Amm as AmmOracle<_, _, _>>::twap(ASSET_1, ASSET_0, 1_000),
+			Ok(FixedU128::from_inner(333_333_333_333_333_333))
+		);
+
+		assert_eq!(
+			<Amm as AmmOracle<_, _, _>>::spot_price(ASSET_0, ASSET_0),
+			Err(Error::<Test>::IdenticalAddress.into())
+		);
+		assert_eq!(
+			<Amm as AmmOracle<_, _, _>>::liquidity(ASSET_2, ASSET_0),
+			Err(Error::<Test>::PairNotFound.into())
+		);
+	})
+}
+
+#[test]
+fn constant_price_oracle_works() {
+	frame_support::parameter_types! {
+		pub const Price: FixedU128 = FixedU128::from_inner(2_000_000_000_000_000_000);
+		pub const Reserve: Balance = 1_000;
+	}
+	type Oracle = ConstantPriceOracle<Price, Reserve>;
+
+	assert_eq!(
+		<Oracle as AmmOracle<Asset, Balance, u64>>::spot_price(ASSET_0, ASSET_1),
+		Ok(FixedU128::saturating_from_integer(2))
+	);
+	assert_eq!(
+		<Oracle as AmmOracle<Asset, Balance, u64>>::twap(ASSET_0, ASSET_1, 1_000),
+		Ok(FixedU128::saturating_from_integer(2))
+	);
+	assert_eq!(
+		<Oracle as AmmOracle<Asset, Balance, u64>>::liquidity(ASSET_0, ASSET_1),
+		Ok((1_000, 2_000))
+	);
+}
+
+#[test]
+fn add_liquidity_works() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		add_supply_for_account(origin_to, 1_000_000);
+		let contract = pool_account::<Test>(POOL).unwrap();
+
+		// the first deposit sets the price
+		Amm::add_liquidity(Origin::signed(origin_to), POOL, 10_000, 20_000, 0, 0, None)
+			.expect("add liquidity should work");
+		assert_eq!(reserves::<Test>(POOL).0, 10_000);
+		assert_eq!(reserves::<Test>(POOL).1, 20_000);
+
+		// asset 2 is capped by the desired amount of asset 1
+		Amm::add_liquidity(Origin::signed(origin_to), POOL, 1_000, 5_000, 0, 0, None)
+			.expect("add liquidity should work");
+		assert_eq!(balance_of::<Test>(&contract, ASSET_0), 11_000);
+		assert_eq!(balance_of::<Test>(&contract, ASSET_1), 22_000);
+
+		// asset 1 is capped by the desired amount of asset 2
+		Amm::add_liquidity(Origin::signed(origin_to), POOL, 1_000, 1_000, 0, 0, None)
+			.expect("add liquidity should work");
+		assert_eq!(balance_of::<Test>(&contract, ASSET_0), 11_500);
+		assert_eq!(balance_of::<Test>(&contract, ASSET_1), 23_000);
+
+		assert_eq!(balance_of::<Test>(&origin_to, ASSET_0), 1_000_000 - 11_500);
+		assert_eq!(balance_of::<Test>(&origin_to, ASSET_1), 1_000_000 - 23_000);
+	})
+}
+
+#[test]
+fn add_liquidity_fails_below_min_amounts() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		add_supply_for_account(origin_to, 1_000_000);
+
+		Amm::add_liquidity(Origin::signed(origin_to), POOL, 10_000, 20_000, 0, 0, None)
+			.expect("add liquidity should work");
+
+		assert_err!(
+			Amm::add_liquidity(Origin::signed(origin_to), POOL, 1_000, 5_000, 0, 2_001, None),
+			Error::<Test>::SlippageExceeded
+		);
+		assert_err!(
+			Amm::add_liquidity(Origin::signed(origin_to), POOL, 1_000, 1_000, 501, 0, None),
+			Error::<Test>::SlippageExceeded
+		);
+	})
+}
+
+#[test]
+fn add_liquidity_fails_without_minting_anything() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		add_supply_for_account(origin_to, 1_000_000);
+
+		// the first deposit is not more than the locked minimum liquidity
+		assert_noop!(
+			Amm::add_liquidity(Origin::signed(origin_to), POOL, 1_000, 1_000, 0, 0, None),
+			Error::<Test>::InsufficientLiquidityMinted
+		);
+		assert_eq!(Amm::total_supply(POOL), 0);
+	})
+}
+
+#[test]
+fn remove_liquidity_works() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		let recipient = 3;
+		add_supply_for_account(origin_to, 1_000_000);
+
+		Amm::deposit_asset_1(Origin::signed(origin_to), POOL, 10_000, None)
+			.expect("deposit should work");
+		assert_eq!(Amm::lp_balance(POOL, origin_to), 9_000);
+
+		Amm::remove_liquidity(
+			Origin::signed(origin_to),
+			POOL,
+			4_500,
+			4_500,
+			4_500,
+			recipient,
+			None,
+		)
+		.expect("remove liquidity should work");
+
+		assert_eq!(Amm::lp_balance(POOL, origin_to), 4_500);
+		assert_eq!(balance_of::<Test>(&recipient, ASSET_0), 4_500);
+		assert_eq!(balance_of::<Test>(&recipient, ASSET_1), 4_500);
+		assert_eq!(balance_of::<Test>(&origin_to, ASSET_0), 1_000_000 - 10_000);
+	})
+}
+
+#[test]
+fn remove_liquidity_fails_below_min_amounts() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		add_supply_for_account(origin_to, 1_000_000);
+
+		Amm::deposit_asset_1(Origin::signed(origin_to), POOL, 10_000, None)
+			.expect("deposit should work");
+
+		assert_noop!(
+			Amm::remove_liquidity(
+				Origin::signed(origin_to),
+				POOL,
+				4_500,
+				4_501,
+				0,
+				origin_to,
+				None
+			),
+			Error::<Test>::SlippageExceeded
+		);
+		// neither the LP tokens nor the reserves moved
+		assert_eq!(Amm::lp_balance(POOL, origin_to), 9_000);
+		assert_eq!(reserves::<Test>(POOL).0, 10_000);
+
+		assert_noop!(
+			Amm::remove_liquidity(
+				Origin::signed(origin_to),
+				POOL,
+				4_500,
+				0,
+				4_501,
+				origin_to,
+				None
+			),
+			Error::<Test>::SlippageExceeded
+		);
+	})
+}
+
+#[test]
+fn zap_in_works() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		add_supply_for_account(origin_to, 1_000_000);
+
+		Amm::deposit_asset_1(Origin::signed(origin_to), POOL, 100_000, None)
+			.expect("deposit should work");
+		let lp_pre_zap = Amm::lp_balance(POOL, origin_to);
+
+		assert_err!(
+			Amm::zap_in(Origin::signed(origin_to), POOL, ASSET_2, 10_000, 0, None),
+			Error::<Test>::InvalidDepositToken
+		);
+
+		Amm::zap_in(Origin::signed(origin_to), POOL, ASSET_0, 10_000, 4_871, None)
+			.expect("zap in should work");
+
+		// 4_888 are swapped for 4_646, which are deposited with 5_110 of the rest
+		assert_eq!(Amm::lp_balance(POOL, origin_to), lp_pre_zap + 4_871);
+		assert_eq!(balance_of::<Test>(&origin_to, ASSET_0), 1_000_000 - 100_000 - 9_998);
+		assert_eq!(balance_of::<Test>(&origin_to, ASSET_1), 1_000_000 - 100_000);
+	})
+}
+
+#[test]
+fn zap_in_fails_below_min_liquidity() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		add_supply_for_account(origin_to, 1_000_000);
+
+		Amm::deposit_asset_1(Origin::signed(origin_to), POOL, 100_000, None)
+			.expect("deposit should work");
+
+		assert_err!(
+			Amm::zap_in(Origin::signed(origin_to), POOL, ASSET_0, 10_000, 4_872, None),
+			Error::<Test>::SlippageExceeded
+		);
+	})
+}
+
+#[test]
+fn zap_in_follows_the_curve_of_the_pool() {
+	new_test_ext().execute_with(|| {
+		let origin = 2;
+		let stable_pool = (ASSET_0, ASSET_2);
+		let weighted_pool = (ASSET_1, ASSET_2);
+		add_supply_for_account(origin, 10_000_000);
+
+		Amm::create_stable_pool(Origin::signed(origin), ASSET_0, ASSET_2, 100)
+			.expect("pool should be created");
+		Amm::deposit_asset_1(Origin::signed(origin), stable_pool, 1_000_000, None)
+			.expect("deposit should work");
+		Amm::create_weighted_pool(
+			Origin::signed(origin),
+			ASSET_1,
+			ASSET_2,
+			Permill::from_percent(80),
+		)
+		.expect("pool should be created");
+		Amm::deposit_asset_1(Origin::signed(origin), weighted_pool, 1_000_000, None)
+			.expect("deposit should work");
+
+		// all of the swapped amount is deposited, and only rounding dust of the rest is left
+		let balance_0_before = balance_of::<Test>(&origin, ASSET_0);
+		let balance_2_before = balance_of::<Test>(&origin, ASSET_2);
+		Amm::zap_in(Origin::signed(origin), stable_pool, ASSET_0, 100_000, 0, None)
+			.expect("zap in should work");
+		assert!(balance_of::<Test>(&origin, ASSET_0) <= balance_0_before - 99_990);
+		assert_eq!(balance_of::<Test>(&origin, ASSET_2), balance_2_before);
+
+		let balance_1_before = balance_of::<Test>(&origin, ASSET_1);
+		let balance_2_before = balance_of::<Test>(&origin, ASSET_2);
+		Amm::zap_in(Origin::signed(origin), weighted_pool, ASSET_2, 100_000, 0, None)
+			.expect("zap in should work");
+		assert_eq!(balance_of::<Test>(&origin, ASSET_1), balance_1_before);
+		assert!(balance_of::<Test>(&origin, ASSET_2) <= balance_2_before - 99_990);
+	})
+}
+
+#[test]
+fn zap_out_works() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		add_supply_for_account(origin_to, 1_000_000);
+
+		Amm::deposit_asset_1(Origin::signed(origin_to), POOL, 100_000, None)
+			.expect("deposit should work");
+
+		assert_err!(
+			Amm::zap_out(Origin::signed(origin_to), POOL, 10_000, ASSET_2, 0, None),
+			Error::<Test>::InvalidSwapToken
+		);
+
+		Amm::zap_out(Origin::signed(origin_to), POOL, 10_000, ASSET_0, 18_975, None)
+			.expect("zap out should work");
+
+		// 10_000 of each asset are withdrawn, and 10_000 of Asset2 are swapped for 8_975
+		assert_eq!(balance_of::<Test>(&origin_to, ASSET_0), 1_000_000 - 100_000 + 18_975);
+		assert_eq!(balance_of::<Test>(&origin_to, ASSET_1), 1_000_000 - 100_000);
+	})
+}
+
+#[test]
+fn zap_out_fails_below_min_amount_out() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		add_supply_for_account(origin_to, 1_000_000);
+
+		Amm::deposit_asset_1(Origin::signed(origin_to), POOL, 100_000, None)
+			.expect("deposit should work");
+
+		assert_err!(
+			Amm::zap_out(Origin::signed(origin_to), POOL, 10_000, ASSET_0, 18_976, None),
+			Error::<Test>::SlippageExceeded
+		);
+	})
+}
+
+#[test]
+fn transfer_lp_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let origin_to = 2;
+		let receiver = 3;
+		add_supply_for_account(origin_to, 1_000_000);
+
+		Amm::deposit_asset_1(Origin::signed(origin_to), POOL, 100_000, None)
+			.expect("deposit should work");
+
+		Amm::transfer_lp(Origin::signed(origin_to), POOL.into(), receiver, 1_000)
+			.expect("transfer should work");
+		assert_eq!(Amm::lp_balance(POOL, origin_to), 98_000);
+		assert_eq!(Amm::lp_balance(POOL, receiver), 1_000);
+		System::assert_last_event(mock::Event::Amm(Event::Transfer {
+			pool: POOL.into(),
+			from: Some(origin_to),
+			to: Some(receiver),
+			value: 1_000,
+		}));
+
+		assert_err!(
+			Amm::transfer_lp(Origin::signed(receiver), POOL.into(), origin_to, 1_001),
+			Error::<Test>::InsufficientBalance
+		);
+		assert_err!(
+			Amm::transfer_lp(Origin::signed(origin_to), (ASSET_0, ASSET_2).into(), receiver, 1_000),
+			Error::<Test>::PairNotFound
+		);
+	})
+}
+
+#[test]
+fn transfer_lp_from_respects_allowance() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let owner = 2;
+		let spender = 3;
+		let token: LpTokenOf<Test> = POOL.into();
+		add_supply_for_account(owner, 1_000_000);
+
+		Amm::deposit_asset_1(Origin::signed(owner), POOL, 100_000, None)
+			.expect("deposit should work");
+
+		Amm::approve_lp(Origin::signed(owner), token.clone(), spender, 500)
+			.expect("approve should work");
+		assert_eq!(Amm::allowance((token.clone(), owner, spender)), 500);
+		System::assert_last_event(mock::Event::Amm(Event::Approval {
+			pool: token.clone(),
+			owner,
+			spender,
+			value: 500,
+		}));
+
+		assert_err!(
+			Amm::transfer_lp_from(Origin::signed(spender), token.clone(), owner, spender, 501),
+			Error::<Test>::InsufficientAllowance
+		);
+
+		Amm::transfer_lp_from(Origin::signed(spender), token.clone(), owner, spender, 200)
+			.expect("transfer should work");
+		assert_eq!(Amm::lp_balance(POOL, owner), 98_800);
+		assert_eq!(Amm::lp_balance(POOL, spender), 200);
+		assert_eq!(Amm::allowance((token.clone(), owner, spender)), 300);
+
+		// the maximum allowance is never used up
+		Amm::approve_lp(Origin::signed(owner), token.clone(), spender, Balance::MAX)
+			.expect("approve should work");
+		Amm::transfer_lp_from(Origin::signed(spender), token.clone(), owner, spender, 800)
+			.expect("transfer should work");
+		assert_eq!(Amm::allowance((token.clone(), owner, spender)), Balance::MAX);
+	})
+}
+
+#[test]
+fn flash_swap_works_when_repaid_with_fee() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let origin_to = 2;
+		let borrower = 3;
+		add_supply_for_account(origin_to, 1_000_000);
+		add_supply_for_account(borrower, 1_000_000);
+
+		Amm::deposit_asset_1(Origin::signed(origin_to), POOL, 100_000, None)
+			.expect("deposit should work");
+
+		Amm::flash_swap(
+			Origin::signed(borrower),
+			POOL,
+			0,
+			1_000,
+			borrower,
+			flash_data(FlashAction::Repay(0, 1_003).encode()),
+		)
+		.expect("flash swap should work");
+		assert_eq!(balance_of::<Test>(&borrower, ASSET_1), 1_000_000 - 3);
+		assert_eq!(reserves::<Test>(POOL).1, 100_003);
+		System::assert_last_event(mock::Event::Amm(Event::Swap {
+			pool: basket(vec![ASSET_0, ASSET_1]),
+			sender: borrower,
+			to: borrower,
+			amounts_in: amounts(vec![0, 1_003]),
+			amounts_out: amounts(vec![0, 1_000]),
+		}));
+
+		// 1002 - 1002 * 0.3% is less than the 1000 borrowed
+		assert_err!(
+			Amm::flash_swap(
+				Origin::signed(borrower),
+				POOL,
+				0,
+				1_000,
+				borrower,
+				flash_data(FlashAction::Repay(0, 1_002).encode()),
+			),
+			Error::<Test>::InvalidK
+		);
+	})
+}
+
+#[test]
+fn flash_swap_without_data_needs_input_beforehand() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		add_supply_for_account(origin_to, 1_000_000);
+
+		Amm::deposit_asset_1(Origin::signed(origin_to), POOL, 100_000, None)
+			.expect("deposit should work");
+
+		assert_err!(
+			Amm::flash_swap(
+				Origin::signed(origin_to),
+				POOL,
+				0,
+				1_000,
+				origin_to,
+				flash_data(vec![])
+			),
+			Error::<Test>::InsufficientInputAmount
+		);
+
+		let contract = pool_account::<Test>(POOL).unwrap();
+		Extension::transfer_balance(&origin_to, &contract, ASSET_0, 1_020)
+			.expect("transfer should work");
+		Amm::flash_swap(Origin::signed(origin_to), POOL, 0, 1_000, origin_to, flash_data(vec![]))
+			.expect("flash swap should work");
+
+		assert_err!(
+			Amm::flash_swap(
+				Origin::signed(origin_to),
+				(ASSET_0, ASSET_2),
+				0,
+				1,
+				origin_to,
+				flash_data(vec![])
+			),
+			Error::<Test>::PairNotFound
+		);
+	})
+}
+
+#[test]
+fn flash_swap_locks_the_pool_during_the_callback() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		let borrower = 3;
+		add_supply_for_account(origin_to, 1_000_000);
+		add_supply_for_account(borrower, 1_000_000);
+
+		Amm::deposit_asset_1(Origin::signed(origin_to), POOL, 100_000, None)
+			.expect("deposit should work");
+
+		assert_err!(
+			Amm::flash_swap(
+				Origin::signed(borrower),
+				POOL,
+				0,
+				1_000,
+				borrower,
+				flash_data(FlashAction::Deposit(1_000).encode()),
+			),
+			Error::<Test>::Locked
+		);
+		assert!(!Locks::<Test>::get(POOL));
+	})
+}
+
+#[test]
+fn reentrant_calls_from_the_extension_fail() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		add_supply_for_account(origin_to, 1_000_000);
+
+		Amm::deposit_asset_1(Origin::signed(origin_to), POOL, 100_000, None)
+			.expect("deposit should work");
+
+		// the withdrawn assets are transferred while the pool is locked
+		REENTRANT_CALL
+			.with(|call| *call.borrow_mut() = Some(|| Amm::sync(Origin::signed(4), POOL)));
+		assert_err!(
+			Amm::withdraw(Origin::signed(origin_to), POOL, 1_000, None),
+			Error::<Test>::Locked
+		);
+		assert!(!Locks::<Test>::get(POOL));
+
+		let contract = pool_account::<Test>(POOL).unwrap();
+		Extension::transfer_balance(&origin_to, &contract, ASSET_0, 1_020)
+			.expect("transfer should work");
+
+		REENTRANT_CALL.with(|call| {
+			*call.borrow_mut() = Some(|| Amm::deposit_asset_1(Origin::signed(4), POOL, 1_000, None))
+		});
+		assert_err!(
+			Amm::flash_swap(
+				Origin::signed(origin_to),
+				POOL,
+				0,
+				1_000,
+				origin_to,
+				flash_data(vec![])
+			),
+			Error::<Test>::Locked
+		);
+		assert!(!Locks::<Test>::get(POOL));
+
+		// other pools can still be used
+		REENTRANT_CALL.with(|call| {
+			*call.borrow_mut() = Some(|| Amm::create_pool(Origin::signed(4), ASSET_0, ASSET_2))
+		});
+		Amm::flash_swap(Origin::signed(origin_to), POOL, 0, 1_000, origin_to, flash_data(vec![]))
+			.expect("flash swap should work");
+	})
+}
+
+#[test]
+fn failed_locked_calls_revert_their_writes() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		add_supply_for_account(origin_to, 1_000_000);
+
+		let contract = pool_account::<Test>(POOL).unwrap();
+		Extension::transfer_balance(&origin_to, &contract, ASSET_0, 1_000)
+			.expect("transfer should work");
+		Extension::transfer_balance(&origin_to, &contract, ASSET_1, 1_000)
+			.expect("transfer should work");
+
+		// the minimum liquidity is minted before the minted amount is found to be zero
+		assert_err!(
+			mint::<Test>(POOL, &origin_to, origin_to),
+			Error::<Test>::InsufficientLiquidityMinted
+		);
+		assert_eq!(Amm::total_supply(POOL), 0);
+		assert!(!Locks::<Test>::get(POOL));
+	})
+}
+
+#[test]
+fn large_balances_do_not_overflow() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		let supply = 10u128.pow(25);
+		let deposit = 10u128.pow(24);
+		add_supply_for_account(origin_to, supply);
+
+		// the product of the amounts does not fit into a u128
+		Amm::deposit_asset_1(Origin::signed(origin_to), POOL, deposit, None)
+			.expect("deposit should work");
+		assert_eq!(Amm::lp_balance(POOL, origin_to), deposit - 1000);
+
+		// paying almost nothing for a tenth of the reserve breaks the invariant
+		let contract = pool_account::<Test>(POOL).unwrap();
+		Extension::transfer_balance(&origin_to, &contract, ASSET_0, 1)
+			.expect("transfer should work");
+		assert_err!(
+			Amm::flash_swap(
+				Origin::signed(origin_to),
+				POOL,
+				0,
+				deposit / 10,
+				origin_to,
+				flash_data(vec![])
+			),
+			Error::<Test>::InvalidK
+		);
+
+		assert_err!(quote::<Test>(Balance::MAX, 1, 2), Error::<Test>::Overflow);
+	})
+}
+
+#[test]
+fn stable_math_solves_the_invariant() {
+	let reserve = U256::from(1_000_000u32);
+
+	// balanced reserves are on the constant sum part of the curve
+	assert_eq!(stable::get_d(reserve, reserve, 100), Some(reserve * 2));
+	assert_eq!(stable::get_d(U256::zero(), U256::zero(), 100), Some(U256::zero()));
+	assert_eq!(stable::get_d(reserve, U256::zero(), 100), None);
+
+	let x = U256::from(1_234_567u32);
+	let y = U256::from(765_432u32);
+	let d = stable::get_d(x, y, 100).unwrap();
+	let solved = stable::get_y(x, d, 100).unwrap();
+	assert!(solved.max(y) - solved.min(y) <= U256::one());
+
+	// the higher the amplification, the closer a swap is to 1:1
+	let x = reserve + U256::from(100_000u32);
+	let out = |amplification| reserve - stable::get_y(x, reserve * 2, amplification).unwrap();
+	assert!(out(1) < out(100));
+	assert!(out(1_000) > U256::from(99_000u32));
+}
+
+#[test]
+fn create_stable_pool_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		Timestamp::set_timestamp(1_000);
+		let pool = (ASSET_0, ASSET_2);
+
+		assert_err!(
+			Amm::create_stable_pool(Origin::signed(1), ASSET_0, ASSET_2, 0),
+			Error::<Test>::InvalidAmplification
+		);
+		assert_err!(
+			Amm::create_stable_pool(Origin::signed(1), ASSET_0, ASSET_1, 100),
+			Error::<Test>::PairExists
+		);
+
+		Amm::create_stable_pool(Origin::signed(1), ASSET_2, ASSET_0, 100)
+			.expect("pool should be created");
+
+		let amplification =
+			Amplification { initial: 100, future: 100, initial_time: 1_000, future_time: 1_000 };
+		System::assert_last_event(mock::Event::Amm(Event::AmplificationSet {
+			pool,
+			amplification: amplification.clone(),
+		}));
+		assert_eq!(Amm::amplification(pool), Some(amplification));
+		assert_eq!(Amm::amplification(POOL), None);
+	})
+}
+
+#[test]
+fn stable_pool_swaps_close_to_one_to_one() {
+	new_test_ext().execute_with(|| {
+		let origin = 2;
+		let pool = (ASSET_0, ASSET_2);
+		add_supply_for_account(origin, 10_000_000);
+
+		Amm::create_stable_pool(Origin::signed(origin), ASSET_0, ASSET_2, 100)
+			.expect("pool should be created");
+		Amm::deposit_asset_1(Origin::signed(origin), pool, 1_000_000, None)
+			.expect("deposit should work");
+		Amm::deposit_asset_1(Origin::signed(origin), POOL, 1_000_000, None)
+			.expect("deposit should work");
+
+		// the LP tokens follow `D`, which is the sum of balanced reserves
+		assert_eq!(Amm::lp_balance(pool, origin), 2_000_000 - 1_000);
+
+		// a constant product pool of the same size pays out 90_661
+		assert_eq!(Amm::quote_exact_in(POOL, ASSET_0, 100_000), Ok(90_661));
+		assert_eq!(Amm::quote_exact_in(pool, ASSET_0, 100_000), Ok(99_599));
+
+		let balance_before = balance_of::<Test>(&origin, ASSET_2);
+		Amm::swap_exact_in_asset_1_for_asset_2(Origin::signed(origin), pool, 100_000, 99_599, None)
+			.expect("swap should work");
+		assert_eq!(balance_of::<Test>(&origin, ASSET_2), balance_before + 99_599);
+
+		let amount_in = Amm::quote_exact_out(pool, ASSET_2, 50_000).unwrap();
+		let balance_before = balance_of::<Test>(&origin, ASSET_2);
+		Amm::swap_asset_2_for_asset_1(Origin::signed(origin), pool, 50_000, amount_in, None)
+			.expect("swap should work");
+		assert_eq!(balance_of::<Test>(&origin, ASSET_2), balance_before - amount_in);
+
+		// taking out more than the curve allows breaks the invariant
+		let contract = pool_account::<Test>(pool).unwrap();
+		Extension::transfer_balance(&origin, &contract, ASSET_0, 10_000)
+			.expect("transfer should work");
+		assert_err!(
+			Amm::flash_swap(Origin::signed(origin), pool, 0, 10_000, origin, flash_data(vec![])),
+			Error::<Test>::InvalidK
+		);
+	})
+}
+
+#[test]
+fn stable_pool_prices_follow_the_curve() {
+	new_test_ext().execute_with(|| {
+		let origin = 2;
+		let pool = (ASSET_0, ASSET_2);
+		add_supply_for_account(origin, 10_000_000);
+
+		Amm::create_stable_pool(Origin::signed(origin), ASSET_0, ASSET_2, 100)
+			.expect("pool should be created");
+		Amm::deposit_asset_1(Origin::signed(origin), pool, 1_000_000, None)
+			.expect("deposit should work");
+		Amm::swap_exact_in_asset_1_for_asset_2(Origin::signed(origin), pool, 300_000, 0, None)
+			.expect("swap should work");
+
+		let (reserve_0, reserve_2) =
+			<Amm as AmmOracle<_, _, _>>::liquidity(ASSET_0, ASSET_2).unwrap();
+		let ratio = FixedU128::saturating_from_rational(reserve_2, reserve_0);
+		let price = Amm::spot_price(pool, ASSET_2).unwrap();
+
+		// the curve keeps the price close to 1 where the reserve ratio is not
+		assert!(ratio < price && price < FixedU128::one());
+		assert_eq!(<Amm as AmmOracle<_, _, _>>::spot_price(ASSET_0, ASSET_2), Ok(price));
+		assert_eq!(
+			<Amm as AmmOracle<_, _, _>>::spot_price(ASSET_2, ASSET_0),
+			Amm::spot_price(pool, ASSET_0)
+		);
+
+		Timestamp::set_timestamp(1_000);
+		Amm::sync(Origin::signed(origin), pool).expect("sync should work");
+		Timestamp::set_timestamp(2_000);
+		assert_eq!(<Amm as AmmOracle<_, _, _>>::twap(ASSET_0, ASSET_2, 1_000), Ok(price));
+	})
+}
+
+#[test]
+fn ramp_amplification_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		Timestamp::set_timestamp(1_000);
+		let pool = (ASSET_0, ASSET_2);
+		let day = 86_400_000;
+
+		Amm::create_stable_pool(Origin::signed(1), ASSET_0, ASSET_2, 100)
+			.expect("pool should be created");
+
+		assert_err!(
+			Amm::ramp_amplification(Origin::signed(1), pool, 1_000, 1_000 + day),
+			DispatchError::BadOrigin
+		);
+		assert_err!(
+			Amm::ramp_amplification(Origin::root(), POOL, 1_000, 1_000 + day),
+			Error::<Test>::NotStableSwapPool
+		);
+		assert_err!(
+			Amm::ramp_amplification(Origin::root(), pool, 1_000, day),
+			Error::<Test>::InvalidAmplification
+		);
+		assert_err!(
+			Amm::ramp_amplification(Origin::root(), pool, 1_001, 1_000 + day),
+			Error::<Test>::InvalidAmplification
+		);
+		assert_err!(
+			Amm::ramp_amplification(Origin::root(), pool, 9, 1_000 + day),
+			Error::<Test>::InvalidAmplification
+		);
+
+		Amm::ramp_amplification(Origin::root(), pool, 1_000, 1_000 + 2 * day)
+			.expect("ramp should work");
+
+		let amplification = Amplification {
+			initial: 100,
+			future: 1_000,
+			initial_time: 1_000,
+			future_time: 1_000 + 2 * day,
+		};
+		System::assert_last_event(mock::Event::Amm(Event::AmplificationSet {
+			pool,
+			amplification: amplification.clone(),
+		}));
+
+		assert_eq!(amplification.at(1_000 + day), 550);
+		assert_eq!(amplification.at(1_000 + 3 * day), 1_000);
+
+		// a new ramp starts from the current amplification
+		Timestamp::set_timestamp(1_000 + day);
+		Amm::ramp_amplification(Origin::root(), pool, 100, 1_000 + 2 * day)
+			.expect("ramp should work");
+		assert_eq!(Amm::amplification(pool).map(|a| a.initial), Some(550));
+	})
+}
+
+#[test]
+fn curves_share_the_pool_accounting() {
+	let fee = Permill::from_perthousand(3);
+	let reserve = 1_000_000;
+	let constant_product = ConstantProduct;
+	let stable_swap = StableSwap { amplification: 100 };
+
+	assert_eq!(
+		Curve::<Test>::amount_out(&constant_product, 100_000, reserve, reserve, fee),
+		Ok(90_661)
+	);
+	assert_eq!(Curve::<Test>::amount_out(&stable_swap, 100_000, reserve, reserve, fee), Ok(99_599));
+
+	// the first deposit mints the square root of the invariant
+	assert_eq!(
+		Curve::<Test>::mint_amount(&constant_product, reserve, reserve, 0, 0, 0),
+		Ok(1_000_000)
+	);
+	assert_eq!(Curve::<Test>::mint_amount(&stable_swap, reserve, reserve, 0, 0, 0), Ok(2_000_000));
+
+	// later deposits and withdrawals are proportional for every curve
+	for curve in [PoolCurve::ConstantProduct(constant_product), PoolCurve::StableSwap(stable_swap)]
+	{
+		assert_eq!(
+			Curve::<Test>::mint_amount(&curve, 1_000, 2_000, reserve, reserve, 500_000),
+			Ok(500)
+		);
+		assert_eq!(
+			Curve::<Test>::burn_amounts(&curve, 5_000, reserve, reserve * 2, 500_000),
+			Ok((10_000, 20_000))
+		);
+		assert_err!(
+			Curve::<Test>::ensure_invariant(
+				&curve,
+				reserve + 1_000,
+				reserve - 1_010,
+				reserve,
+				reserve
+			),
+			Error::<Test>::InvalidK
+		);
+	}
+}
+
+#[test]
+fn weighted_math_is_exact() {
+	let ten = U256::from(10u32);
+	let one = U256::one();
+
+	assert_eq!(weighted::ln(ten, one), Some(2_302_585_092_994_045_684));
+	assert_eq!(weighted::ln(one, ten), Some(-2_302_585_092_994_045_684));
+	assert_eq!(weighted::ln(one, one), Some(0));
+	assert_eq!(weighted::ln(U256::zero(), one), None);
+
+	assert_eq!(
+		weighted::exp(weighted::ONE as i128),
+		Some(U256::from(2_718_281_828_459_045_234u128))
+	);
+	assert_eq!(
+		weighted::exp(-(weighted::ONE as i128)),
+		Some(U256::from(367_879_441_171_442_321u128))
+	);
+	assert_eq!(weighted::exp(200 * weighted::ONE as i128), None);
+
+	// the square root of 1/2
+	assert_eq!(
+		weighted::pow(one, U256::from(2u32), weighted::ONE / 2),
+		Some(U256::from(707_106_781_186_547_524u128))
+	);
+}
+
+#[test]
+fn create_weighted_pool_works() {
+	new_test_ext().execute_with(|| {
+		System::set_block_number(1);
+		let pool = (ASSET_0, ASSET_2);
+
+		for weight in [Permill::zero(), Permill::from_parts(5_000), Permill::one()] {
+			assert_err!(
+				Amm::create_weighted_pool(Origin::signed(1), ASSET_0, ASSET_2, weight),
+				Error::<Test>::InvalidWeight
+			);
+		}
+
+		// the weight is that of the first asset given, which is the second asset of the pool
+		Amm::create_weighted_pool(Origin::signed(1), ASSET_2, ASSET_0, Permill::from_percent(20))
+			.expect("pool should be created");
+
+		System::assert_last_event(mock::Event::Amm(Event::PoolWeightsSet {
+			pool,
+			weight_0: Permill::from_percent(80),
+			weight_1: Permill::from_percent(20),
+		}));
+		assert_eq!(Amm::weight(pool), Some(Permill::from_percent(80)));
+		assert_eq!(Amm::weight(POOL), None);
+	})
+}
+
+#[test]
+fn weighted_pool_prices_follow_the_weights() {
+	new_test_ext().execute_with(|| {
+		let origin = 2;
+		let pool = (ASSET_0, ASSET_2);
+		add_supply_for_account(origin, 10_000_000);
+
+		Amm::create_weighted_pool(
+			Origin::signed(origin),
+			ASSET_0,
+			ASSET_2,
+			Permill::from_percent(80),
+		)
+		.expect("pool should be created");
+		Amm::deposit_asset_1(Origin::signed(origin), pool, 1_000_000, None)
+			.expect("deposit should work");
+
+		// equal reserves, but Asset1 is worth 4 times as much as Asset2
+		assert_eq!(
+			<Amm as AmmOracle<_, _, _>>::liquidity(ASSET_0, ASSET_2),
+			Ok((1_000_000, 1_000_000))
+		);
+		assert_eq!(
+			<Amm as AmmOracle<_, _, _>>::spot_price(ASSET_0, ASSET_2),
+			Ok(FixedU128::from(4))
+		);
+		assert_eq!(
+			<Amm as AmmOracle<_, _, _>>::spot_price(ASSET_2, ASSET_0),
+			Ok(FixedU128::saturating_from_rational(1, 4))
+		);
+
+		Timestamp::set_timestamp(1_000);
+		Amm::sync(Origin::signed(origin), pool).expect("sync should work");
+		Timestamp::set_timestamp(2_000);
+		assert_eq!(
+			Amm::consult(pool, 1_000),
+			Ok((FixedU128::from(4), FixedU128::saturating_from_rational(1, 4)))
+		);
+	})
+}
+
+#[test]
+fn weighted_pool_swaps_by_weights() {
+	new_test_ext().execute_with(|| {
+		let origin = 2;
+		let pool = (ASSET_0, ASSET_2);
+		add_supply_for_account(origin, 10_000_000);
+
+		Amm::create_weighted_pool(
+			Origin::signed(origin),
+			ASSET_0,
+			ASSET_2,
+			Permill::from_percent(80),
+		)
+		.expect("pool should be created");
+		Amm::deposit_asset_1(Origin::signed(origin), pool, 1_000_000, None)
+			.expect("deposit should work");
+
+		// the LP tokens follow the weighted product of the reserves, which is rounded down
+		assert_eq!(Amm::lp_balance(pool, origin), 1_000_000 - 1 - 1_000);
+
+		// equal reserves mean that Asset1 is worth 4 times as much as Asset2
+		assert_eq!(Amm::spot_price(pool, ASSET_0), Ok(FixedU128::saturating_from_rational(1, 4)));
+		assert_eq!(Amm::spot_price(pool, ASSET_2), Ok(FixedU128::from(4)));
+
+		assert_eq!(Amm::quote_exact_in(pool, ASSET_0, 100_000), Ok(316_240));
+		let balance_before = balance_of::<Test>(&origin, ASSET_2);
+		Amm::swap_exact_in_asset_1_for_asset_2(
+			Origin::signed(origin),
+			pool,
+			100_000,
+			316_240,
+			None,
+		)
+		.expect("swap should work");
+		assert_eq!(balance_of::<Test>(&origin, ASSET_2), balance_before + 316_240);
+
+		assert_eq!(Amm::quote_exact_out(pool, ASSET_2, 50_000), Ok(140_263));
+		let balance_before = balance_of::<Test>(&origin, ASSET_2);
+		Amm::swap_asset_2_for_asset_1(Origin::signed(origin), pool, 50_000, 140_263, None)
+			.expect("swap should work");
+		assert_eq!(balance_of::<Test>(&origin, ASSET_2), balance_before - 140_263);
+
+		// a swap may take at most 30% of a reserve
+		assert_err!(Amm::quote_exact_in(pool, ASSET_0, 400_000), Error::<Test>::SwapTooLarge);
+
+		let contract = pool_account::<Test>(pool).unwrap();
+		Extension::transfer_balance(&origin, &contract, ASSET_0, 1_000)
+			.expect("transfer should work");
+		assert_err!(
+			Amm::flash_swap(Origin::signed(origin), pool, 0, 10_000, origin, flash_data(vec![])),
+			Error::<Test>::InvalidK
+		);
+	})
+}
+
+#[test]
+fn runtime_api_queries_work() {
+	new_test_ext().execute_with(|| {
+		let origin_to = 2;
+		add_supply_for_account(origin_to, 1_000_000);
+
+		assert_eq!(Amm::get_reserves((ASSET_0, ASSET_2)), None);
+		assert_eq!(Amm::get_reserves(POOL), Some((0, 0, 0)));
+
+		Amm::deposit_asset_1(Origin::signed(origin_to), POOL, 100_000, None)
+			.expect("deposit should work");
+
+		assert_eq!(Amm::get_reserves(POOL).map(|(r0, r1, _)| (r0, r1)), Some((100_000, 100_000)));
+		assert_eq!(Amm::quote_exact_in(POOL, ASSET_0, 1_000), Ok(987));
+		assert_eq!(Amm::quote_exact_in(POOL, ASSET_1, 1_000), Ok(987));
+		assert_eq!(Amm::quote_exact_out(POOL, ASSET_0, 987), Ok(1_000));
+		assert_eq!(
+			Amm::quote_exact_in(POOL, ASSET_2, 1_000),
+			Err(Error::<Test>::InvalidSwapToken.into())
+		);
+		assert_eq!(Amm::lp_balance(POOL, origin_to), 99_000);
+		assert_eq!(Amm::total_supply(POOL), 100_000);
+		assert_eq!(Amm::price_cumulative(POOL), (FixedU128::zero(), FixedU128::zero()));
+		assert_eq!(Amm::pools(), vec![(POOL, 100_000, 100_000, Permill::from_perthousand(3))]);
+	})
+}
+
+/// A basket of all three assets, with reserves of 1, 2 and 4 million.
+fn setup_basket(origin: AccountId) -> BoundedVec<Asset, ConstU32<4>> {
+	add_supply_for_account(origin, 10_000_000);
+
+	Amm::create_basket(Origin::signed(origin), basket(vec![ASSET_2, ASSET_0, ASSET_1]))
+		.expect("create basket should work");
+
+	let pool = basket(vec![ASSET_0, ASSET_1, ASSET_2]);
+	Amm::join_basket(
+		Origin::signed(origin),
+		pool.clone(),
+		amounts(vec![1_000_000, 2_000_000, 4_000_000]),
+		0,
+		None,
+	)
+	.expect("join should work");
+
+	pool
+}
+
+#[test]
+fn create_basket_works() {
+	new_test_ext().execute_with(|| {
+		let origin = 2;
+		System::set_block_number(1);
+
+		Amm::create_basket(Origin::signed(origin), basket(vec![ASSET_2, ASSET_0, ASSET_1]))
+			.expect("create basket should work");
+
+		let pool = basket(vec![ASSET_0, ASSET_1, ASSET_2]);
+		assert_eq!(Amm::basket_reserves(&pool), Some(amounts(vec![0, 0, 0])));
+		System::assert_last_event(mock::Event::Amm(Event::BasketCreated {
+			pool: pool.clone(),
+			account: basket_account::<Test>(&pool).unwrap(),
+		}));
+
+		assert_err!(
+			Amm::create_basket(Origin::signed(origin), basket(vec![ASSET_1, ASSET_2, ASSET_0])),
+			Error::<Test>::PairExists
+		);
+		assert_err!(
+			Amm::create_basket(Origin::signed(origin), basket(vec![ASSET_0, ASSET_2, ASSET_0])),
+			Error::<Test>::IdenticalAddress
+		);
+		assert_err!(
+			Amm::create_basket(Origin::signed(origin), basket(vec![ASSET_0])),
+			Error::<Test>::InvalidAssetCount
+		);
+		assert_err!(
+			Amm::join_basket(
+				Origin::signed(origin),
+				basket(vec![ASSET_0, ASSET_2]),
+				amounts(vec![1_000, 1_000]),
+				0,
+				None
+			),
+			Error::<Test>::BasketNotFound
+		);
+	})
+}
+
+#[test]
+fn basket_join_and_exit_work() {
+	new_test_ext().execute_with(|| {
+		let origin = 2;
+		let joiner = 3;
+		System::set_block_number(1);
+		let pool = setup_basket(origin);
+
+		// the geometric mean of the first deposit is rounded down from 2_000_000
+		assert_eq!(Amm::basket_total_supply(pool.clone()), 1_999_999);
+		assert_eq!(Amm::basket_lp_balance(pool.clone(), origin), 1_998_999);
+		System::assert_last_event(mock::Event::Amm(Event::Mint {
+			pool: pool.clone(),
+			sender: origin,
+			amounts: amounts(vec![1_000_000, 2_000_000, 4_000_000]),
+		}));
+
+		// the amounts are in the ratio of the reserves, limited by the first and last asset
+		add_supply_for_account(joiner, 1_000_000);
+		Amm::join_basket(
+			Origin::signed(joiner),
+			pool.clone(),
+			amounts(vec![100_000, 300_000, 400_000]),
+			199_999,
+			None,
+		)
+		.expect("join should work");
+		assert_eq!(Amm::basket_lp_balance(pool.clone(), joiner), 199_999);
+		assert_eq!(balance_of::<Test>(&joiner, ASSET_1), 1_000_000 - 200_000);
+		assert_eq!(
+			Amm::basket_reserves(&pool),
+			Some(amounts(vec![1_100_000, 2_200_000, 4_399_999]))
+		);
+
+		assert_err!(
+			Amm::exit_basket(
+				Origin::signed(joiner),
+				pool.clone(),
+				199_999,
+				amounts(vec![100_000, 0, 0]),
+				None
+			),
+			Error::<Test>::SlippageExceeded
+		);
+
+		Amm::exit_basket(
+			Origin::signed(joiner),
+			pool.clone(),
+			199_999,
+			amounts(vec![0, 0, 0]),
+			None,
+		)
+		.expect("exit should work");
+		assert_eq!(Amm::basket_lp_balance(pool.clone(), joiner), 0);
+		assert_eq!(balance_of::<Test>(&joiner, ASSET_2), 1_000_000 - 1);
+		System::assert_last_event(mock::Event::Amm(Event::Burn {
+			pool: pool.clone(),
+			sender: joiner,
+			to: joiner,
+			amounts: amounts(vec![99_999, 199_999, 399_998]),
+		}));
+
+		assert_err!(
+			Amm::exit_basket(Origin::signed(joiner), pool, 1, amounts(vec![0, 0, 0]), None),
+			Error::<Test>::InsufficientLiquidityBalance
+		);
+	})
+}
+
+#[test]
+fn basket_swaps_any_to_any() {
+	new_test_ext().execute_with(|| {
+		let origin = 2;
+		System::set_block_number(1);
+		let pool = setup_basket(origin);
+
+		// the same as in a constant product pool of the two reserves
+		assert_eq!(Amm::quote_basket(pool.clone(), ASSET_2, ASSET_0, 100_000), Ok(24_318));
+		let balance_before = balance_of::<Test>(&origin, ASSET_0);
+		Amm::swap_basket(
+			Origin::signed(origin),
+			pool.clone(),
+			ASSET_2,
+			ASSET_0,
+			100_000,
+			24_318,
+			None,
+		)
+		.expect("swap should work");
+		assert_eq!(balance_of::<Test>(&origin, ASSET_0), balance_before + 24_318);
+		System::assert_last_event(mock::Event::Amm(Event::Swap {
+			pool: pool.clone(),
+			sender: origin,
+			to: origin,
+			amounts_in: amounts(vec![0, 0, 100_000]),
+			amounts_out: amounts(vec![24_318, 0, 0]),
+		}));
+
+		assert_err!(
+			Amm::swap_basket(
+				Origin::signed(origin),
+				pool.clone(),
+				ASSET_0,
+				ASSET_1,
+				100_000,
+				185_423,
+				None
+			),
+			Error::<Test>::SlippageExceeded
+		);
+		Amm::swap_basket(
+			Origin::signed(origin),
+			pool.clone(),
+			ASSET_0,
+			ASSET_1,
+			100_000,
+			185_422,
+			None,
+		)
+		.expect("swap should work");
+		assert_eq!(
+			Amm::basket_reserves(&pool),
+			Some(amounts(vec![1_075_682, 1_814_578, 4_100_000]))
+		);
+
+		assert_err!(
+			Amm::quote_basket(pool, ASSET_1, ASSET_1, 1_000),
+			Error::<Test>::IdenticalAddress
+		);
+	})
+}
+
+#[test]
+fn basket_lp_tokens_can_be_transferred() {
+	new_test_ext().execute_with(|| {
+		let origin = 2;
+		let receiver = 3;
+		System::set_block_number(1);
+		let pool = setup_basket(origin);
+		let token = LpToken::Basket(pool.clone());
+
+		Amm::transfer_lp(Origin::signed(origin), token.clone(), receiver, 1_000)
+			.expect("transfer should work");
+		assert_eq!(Amm::basket_lp_balance(pool.clone(), origin), 1_997_999);
+		assert_eq!(Amm::basket_lp_balance(pool.clone(), receiver), 1_000);
+		System::assert_last_event(mock::Event::Amm(Event::Transfer {
+			pool: token,
+			from: Some(origin),
+			to: Some(receiver),
+			value: 1_000,
+		}));
+
+		Amm::exit_basket(Origin::signed(receiver), pool.clone(), 1_000, amounts(vec![0; 3]), None)
+			.expect("exit should work");
+		assert_eq!(Amm::basket_lp_balance(pool.clone(), receiver), 0);
+		assert_eq!(Amm::basket_total_supply(pool), 1_998_999);
+
+		// a basket of two assets has its own LP token, apart from that of the pool
+		Amm::deposit_asset_1(Origin::signed(origin), POOL, 100_000, None)
+			.expect("deposit should work");
+		Amm::create_basket(Origin::signed(origin), basket(vec![ASSET_0, ASSET_1]))
+			.expect("create basket should work");
+		assert_err!(
+			Amm::transfer_lp(
+				Origin::signed(origin),
+				LpToken::Basket(basket(vec![ASSET_0, ASSET_1])),
+				receiver,
+				1
+			),
+			Error::<Test>::InsufficientBalance
+		);
+		assert_err!(
+			Amm::transfer_lp(
+				Origin::signed(origin),
+				LpToken::Basket(basket(vec![ASSET_1, ASSET_2])),
+				receiver,
+				1
+			),
+			Error::<Test>::BasketNotFound
+		);
+	})
+}
+
+#[test]
+fn set_basket_swap_fee_works() {
+	new_test_ext().execute_with(|| {
+		let origin = 2;
+		let fee = Permill::from_percent(1);
+		System::set_block_number(1);
+		let pool = setup_basket(origin);
+
+		assert_eq!(Amm::basket_swap_fee(&pool), Permill::from_perthousand(3));
+
+		assert_err!(
+			Amm::set_basket_swap_fee(Origin::signed(origin), pool.clone(), fee),
+			DispatchError::BadOrigin
+		);
+		assert_err!(
+			Amm::set_basket_swap_fee(Origin::root(), basket(vec![ASSET_0, ASSET_1]), fee),
+			Error::<Test>::BasketNotFound
+		);
+		assert_err!(
+			Amm::set_basket_swap_fee(Origin::root(), pool.clone(), Permill::one()),
+			Error::<Test>::InvalidFee
+		);
+
+		Amm::set_basket_swap_fee(Origin::root(), pool.clone(), fee)
+			.expect("setting the fee should work");
+		assert_eq!(Amm::basket_swap_fee(&pool), fee);
+		System::assert_last_event(mock::Event::Amm(Event::BasketSwapFeeSet {
+			pool: pool.clone(),
+			fee,
+		}));
+
+		// 99_000 * 1_000_000 / (4_000_000 + 99_000)
+		assert_eq!(Amm::quote_basket(pool, ASSET_2, ASSET_0, 100_000), Ok(24_152));
+	})
+}
+
+#[test]
+fn basket_single_asset_join_and_exit_work() {
+	new_test_ext().execute_with(|| {
+		let origin = 2;
+		let joiner = 3;
+		System::set_block_number(1);
+		let pool = setup_basket(origin);
+		add_supply_for_account(joiner, 1_000_000);
+
+		// supply * ((1 + 99_800 / 1_000_000)^(1/3) - 1), with the fee on two thirds of the deposit
+		assert_err!(
+			Amm::join_basket_single(
+				Origin::signed(joiner),
+				pool.clone(),
+				ASSET_0,
+				100_000,
+				64_436,
+				None
+			),
+			Error::<Test>::SlippageExceeded
+		);
+		Amm::join_basket_single(
+			Origin::signed(joiner),
+			pool.clone(),
+			ASSET_0,
+			100_000,
+			64_435,
+			None,
+		)
+		.expect("join should work");
+		assert_eq!(Amm::basket_lp_balance(pool.clone(), joiner), 64_435);
+		System::assert_last_event(mock::Event::Amm(Event::Mint {
+			pool: pool.clone(),
+			sender: joiner,
+			amounts: amounts(vec![100_000, 0, 0]),
+		}));
+
+		// exiting right away returns the deposit less the fee of both ways
+		Amm::exit_basket_single(Origin::signed(joiner), pool.clone(), ASSET_0, 64_435, 0, None)
+			.expect("exit should work");
+		assert_eq!(balance_of::<Test>(&joiner, ASSET_0), 1_000_000 - 100_000 + 99_618);
+		assert_eq!(Amm::basket_lp_balance(pool.clone(), joiner), 0);
+
+		// a single asset may make up at most 30% of its reserve
+		assert_err!(
+			Amm::join_basket_single(Origin::signed(joiner), pool, ASSET_0, 400_000, 0, None),
+			Error::<Test>::SwapTooLarge
+		);
+	})
+}
@@ -0,0 +1,169 @@
+//! Fixed-point math for the weighted pools of Balancer, with the invariant `x^w_0 * y^w_1`.
+//!
+//! Values have 18 decimals like `FixedU128`. `ln` and `exp` work with 36 decimals internally
+//! and only use integer operations, so they are deterministic and exact to the last digit.
+
+use sp_core::U256;
+use sp_runtime::Permill;
+
+/// `1` with 18 decimals.
+pub const ONE: u128 = 1_000_000_000_000_000_000;
+
+/// The smallest weight of an asset. The larger the ratio of the weights, the less accurate
+/// the powers in the swap formulas are.
+pub const MIN_WEIGHT: Permill = Permill::from_percent(1);
+
+/// The largest part of a reserve which a single swap may take in or out.
+pub const MAX_SWAP_RATIO: Permill = Permill::from_percent(30);
+
+/// The largest relative error of `pow`, with 18 decimals.
+const MAX_POW_RELATIVE_ERROR: u128 = 10_000;
+
+/// `1` with 36 decimals.
+const ONE_36: u128 = ONE * ONE;
+
+/// `ln(2)` with 36 decimals.
+const LN_2: u128 = 693_147_180_559_945_309_417_232_121_458_176_568;
+
+/// `ln(n / d)`, with 18 decimals and rounded towards zero.
+/// `None` if `n` or `d` is zero or larger than 128 bits.
+pub fn ln(n: U256, d: U256) -> Option<i128> {
+	if n.is_zero() || d.is_zero() || n.bits() > 128 || d.bits() > 128 {
+		return None
+	}
+
+	let one = U256::from(ONE_36);
+
+	// n / d = m * 2^k with 1 <= m < 2
+	let mut k = n.bits() as i32 - d.bits() as i32;
+	let mut m = mantissa(n, d, k)?;
+	if m < one {
+		k -= 1;
+		m = mantissa(n, d, k)?;
+	}
+
+	// ln(m) = 2 * (z + z^3 / 3 + z^5 / 5 + ...) with z = (m - 1) / (m + 1) < 1 / 3
+	let z = (m - one) * one / (m + one);
+	let z_squared = z * z / one;
+	let mut sum = z;
+	let mut term = z;
+	let mut i = 3u32;
+	while !term.is_zero() {
+		term = term * z_squared / one;
+		sum += term / U256::from(i);
+		i += 2;
+	}
+	let ln_m = sum * U256::from(2u32);
+
+	let ln_2k = U256::from(k.unsigned_abs()) * U256::from(LN_2);
+	let ln = if k >= 0 { ln_2k + ln_m } else { ln_2k - ln_m };
+	let ln = i128::try_from((ln / U256::from(ONE)).low_u128()).ok()?;
+
+	Some(if k >= 0 { ln } else { -ln })
+}
+
+/// `e^x` for `x` with 18 decimals, with 18 decimals and rounded down.
+/// `None` if it does not fit into 256 bits.
+pub fn exp(x: i128) -> Option<U256> {
+	let one = U256::from(ONE);
+	let (k, e_r) = exp_reduced(U256::from(x.unsigned_abs()) * one);
+
+	if x >= 0 {
+		shl(e_r / one, k)
+	} else if k >= 256 {
+		Some(U256::zero())
+	} else {
+		let one_36 = U256::from(ONE_36);
+		Some(((one_36 * one_36 / e_r) >> k) / one)
+	}
+}
+
+/// `(n / d)^exponent` for `exponent` with 18 decimals, with 18 decimals.
+/// `None` if `ln` or `exp` fails.
+pub fn pow(n: U256, d: U256, exponent: u128) -> Option<U256> {
+	let ln = ln(n, d)?;
+
+	let x = U256::from(ln.unsigned_abs()).checked_mul(U256::from(exponent))? / U256::from(ONE);
+	if x > U256::from(i128::MAX as u128) {
+		return None
+	}
+	let x = x.low_u128() as i128;
+
+	exp(if ln >= 0 { x } else { -x })
+}
+
+/// `pow` rounded up by its largest error, so that swaps never favour the caller.
+pub fn pow_up(n: U256, d: U256, exponent: u128) -> Option<U256> {
+	let power = pow(n, d, exponent)?;
+	let error = power.checked_mul(U256::from(MAX_POW_RELATIVE_ERROR))? / U256::from(ONE);
+
+	power.checked_add(error)?.checked_add(U256::one())
+}
+
+/// `pow` rounded down by its largest error, the counterpart of `pow_up`.
+pub fn pow_down(n: U256, d: U256, exponent: u128) -> Option<U256> {
+	let power = pow(n, d, exponent)?;
+	let error = power.checked_mul(U256::from(MAX_POW_RELATIVE_ERROR))? / U256::from(ONE);
+
+	Some(power.saturating_sub(error).saturating_sub(U256::one()))
+}
+
+/// The geometric mean of `values`, which is the invariant of a pool with equal weights.
+/// Rounded down, and `None` if `values` is empty or a value is zero or too large for `ln`.
+pub fn geometric_mean(values: &[U256]) -> Option<U256> {
+	let mut sum = 0i128;
+	for value in values {
+		sum = sum.checked_add(ln(*value, U256::one())?)?;
+	}
+
+	let count = i128::try_from(values.len()).ok().filter(|count| *count > 0)?;
+
+	Some(exp(sum / count)? / U256::from(ONE))
+}
+
+/// `n / (d * 2^k)` with 36 decimals.
+fn mantissa(n: U256, d: U256, k: i32) -> Option<U256> {
+	let one = U256::from(ONE_36);
+
+	if k >= 0 {
+		n.checked_mul(one)?.checked_div(shl(d, k as usize)?)
+	} else {
+		shl(n, k.unsigned_abs() as usize)?.checked_mul(one)?.checked_div(d)
+	}
+}
+
+/// `e^x = 2^k * e^r` for `x` with 36 decimals, where `0 <= r < ln(2)`.
+/// Returns `k` and `e^r` with 36 decimals.
+fn exp_reduced(x: U256) -> (usize, U256) {
+	let one = U256::from(ONE_36);
+	let ln_2 = U256::from(LN_2);
+
+	let k = x / ln_2;
+	let r = x - k * ln_2;
+
+	// e^r = 1 + r + r^2 / 2! + r^3 / 3! + ...
+	let mut sum = one;
+	let mut term = one;
+	let mut i = 1u32;
+	while !term.is_zero() {
+		term = term * r / one / U256::from(i);
+		sum += term;
+		i += 1;
+	}
+
+	// `k` is less than 2^256 / ln(2) / 10^36 < 2^137
+	let k = if k > U256::from(usize::MAX) { usize::MAX } else { k.as_usize() };
+	(k, sum)
+}
+
+/// `x * 2^k`, `None` if it does not fit into 256 bits.
+fn shl(x: U256, k: usize) -> Option<U256> {
+	if x.is_zero() {
+		return Some(x)
+	}
+	if x.bits().saturating_add(k) > 256 {
+		return None
+	}
+
+	Some(x << k)
+}
@@ -31,93 +31,356 @@ use sp_std::marker::PhantomData;
 /// Weight functions for `pallet_pendulum_amm`.
 pub struct WeightInfo<T>(PhantomData<T>);
 impl<T: frame_system::Config> crate::WeightInfo for WeightInfo<T> {
-	// Storage: AmmEURUSDC FeeToSetter (r:1 w:0)
-	// Storage: AmmEURUSDC FeeTo (r:0 w:1)
+	// Storage: Amm Reserves (r:1 w:1)
+	fn create_pool() -> Weight {
+		(14_212_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: Amm Reserves (r:1 w:1)
+	// Storage: Timestamp Now (r:1 w:0)
+	// Storage: Amm Amplifications (r:0 w:1)
+	fn create_stable_pool() -> Weight {
+		(16_034_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(2 as Weight))
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+	// Storage: Amm Amplifications (r:1 w:1)
+	// Storage: Timestamp Now (r:1 w:0)
+	fn ramp_amplification() -> Weight {
+		(11_207_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(2 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: Amm Reserves (r:1 w:1)
+	// Storage: Amm Weights (r:0 w:1)
+	fn create_weighted_pool() -> Weight {
+		(15_486_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+	// Storage: Amm FeeToSetter (r:1 w:0)
+	// Storage: Amm FeeTo (r:0 w:1)
 	fn set_fee_to() -> Weight {
 		(5_148_000 as Weight)
 			.saturating_add(T::DbWeight::get().reads(1 as Weight))
 			.saturating_add(T::DbWeight::get().writes(1 as Weight))
 	}
-	// Storage: AmmEURUSDC PalletAccountId (r:1 w:0)
-	// Storage: AmmEURUSDC Reserves (r:1 w:0)
+	// Storage: Amm FeeToSetter (r:1 w:1)
+	fn set_fee_to_setter() -> Weight {
+		(5_312_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: Amm FeeTo (r:0 w:1)
+	fn force_set_fee_to() -> Weight {
+		(3_486_000 as Weight)
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: Amm Reserves (r:1 w:0)
+	// Storage: Amm SwapFees (r:0 w:1)
+	fn set_swap_fee() -> Weight {
+		(6_023_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: Amm Reserves (r:1 w:0)
+	// Storage: Amm LpBalances (r:2 w:2)
+	fn transfer_lp() -> Weight {
+		(18_214_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
+			.saturating_add(T::DbWeight::get().writes(2 as Weight))
+	}
+	// Storage: Amm Reserves (r:1 w:0)
+	// Storage: Amm Allowances (r:0 w:1)
+	fn approve_lp() -> Weight {
+		(13_652_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: Amm Reserves (r:1 w:0)
+	// Storage: Amm Allowances (r:1 w:1)
+	// Storage: Amm LpBalances (r:2 w:2)
+	fn transfer_lp_from() -> Weight {
+		(23_907_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(4 as Weight))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+	}
+	// Storage: Amm Reserves (r:1 w:0)
 	// Storage: Tokens Accounts (r:2 w:0)
 	fn skim() -> Weight {
 		(16_809_000 as Weight)
-			.saturating_add(T::DbWeight::get().reads(4 as Weight))
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
 	}
-	// Storage: AmmEURUSDC PalletAccountId (r:1 w:0)
-	// Storage: AmmEURUSDC Reserves (r:1 w:1)
+	// Storage: Amm Reserves (r:1 w:1)
 	// Storage: Tokens Accounts (r:2 w:0)
 	// Storage: Timestamp Now (r:1 w:0)
-	// Storage: AmmEURUSDC Price0CumulativeLast (r:1 w:1)
-	// Storage: AmmEURUSDC Price1CumulativeLast (r:1 w:1)
+	// Storage: Amm Price0CumulativeLast (r:1 w:1)
+	// Storage: Amm Price1CumulativeLast (r:1 w:1)
+	// Storage: Amm Observations (r:1 w:1)
+	// Storage: Amm ObservationIndex (r:1 w:1)
 	fn sync() -> Weight {
 		(36_359_000 as Weight)
-			.saturating_add(T::DbWeight::get().reads(7 as Weight))
-			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+			.saturating_add(T::DbWeight::get().reads(8 as Weight))
+			.saturating_add(T::DbWeight::get().writes(5 as Weight))
 	}
-	// Storage: AmmEURUSDC PalletAccountId (r:1 w:0)
-	// Storage: AmmEURUSDC Reserves (r:1 w:1)
+	// Storage: Amm Reserves (r:1 w:1)
 	// Storage: Tokens Accounts (r:4 w:4)
 	// Storage: System Account (r:2 w:1)
-	// Storage: AmmEURUSDC KLast (r:1 w:0)
-	// Storage: AmmEURUSDC FeeTo (r:1 w:0)
-	// Storage: AmmEURUSDC TotalSupply (r:1 w:1)
-	// Storage: AmmEURUSDC AddressZero (r:1 w:0)
-	// Storage: AmmEURUSDC LpBalances (r:2 w:2)
+	// Storage: Amm KLast (r:1 w:0)
+	// Storage: Amm FeeTo (r:1 w:0)
+	// Storage: Amm TotalSupply (r:1 w:1)
+	// Storage: Amm LpBalances (r:2 w:2)
 	// Storage: Timestamp Now (r:1 w:0)
+	// Storage: Amm Observations (r:1 w:1)
+	// Storage: Amm ObservationIndex (r:1 w:1)
 	fn deposit_asset_1() -> Weight {
 		(131_333_000 as Weight)
 			.saturating_add(T::DbWeight::get().reads(15 as Weight))
-			.saturating_add(T::DbWeight::get().writes(9 as Weight))
+			.saturating_add(T::DbWeight::get().writes(11 as Weight))
+	}
+	// Storage: Amm Reserves (r:1 w:1)
+	// Storage: Tokens Accounts (r:4 w:4)
+	// Storage: System Account (r:2 w:1)
+	// Storage: Amm KLast (r:1 w:0)
+	// Storage: Amm FeeTo (r:1 w:0)
+	// Storage: Amm TotalSupply (r:1 w:1)
+	// Storage: Amm LpBalances (r:2 w:2)
+	// Storage: Timestamp Now (r:1 w:0)
+	// Storage: Amm Observations (r:1 w:1)
+	// Storage: Amm ObservationIndex (r:1 w:1)
+	fn add_liquidity() -> Weight {
+		(132_106_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(15 as Weight))
+			.saturating_add(T::DbWeight::get().writes(11 as Weight))
 	}
-	// Storage: AmmEURUSDC PalletAccountId (r:1 w:0)
-	// Storage: AmmEURUSDC Reserves (r:1 w:1)
+	// Storage: Amm Reserves (r:1 w:1)
 	// Storage: Tokens Accounts (r:4 w:4)
 	// Storage: System Account (r:2 w:1)
-	// Storage: AmmEURUSDC KLast (r:1 w:0)
-	// Storage: AmmEURUSDC FeeTo (r:1 w:0)
-	// Storage: AmmEURUSDC TotalSupply (r:1 w:1)
-	// Storage: AmmEURUSDC AddressZero (r:1 w:0)
-	// Storage: AmmEURUSDC LpBalances (r:2 w:2)
+	// Storage: Amm KLast (r:1 w:0)
+	// Storage: Amm FeeTo (r:1 w:0)
+	// Storage: Amm TotalSupply (r:1 w:1)
+	// Storage: Amm LpBalances (r:2 w:2)
 	// Storage: Timestamp Now (r:1 w:0)
+	// Storage: Amm Observations (r:1 w:1)
+	// Storage: Amm ObservationIndex (r:1 w:1)
 	fn deposit_asset_2() -> Weight {
 		(138_123_000 as Weight)
 			.saturating_add(T::DbWeight::get().reads(15 as Weight))
-			.saturating_add(T::DbWeight::get().writes(9 as Weight))
+			.saturating_add(T::DbWeight::get().writes(11 as Weight))
 	}
-	// Storage: AmmEURUSDC PalletAccountId (r:1 w:0)
-	// Storage: AmmEURUSDC TotalSupply (r:1 w:1)
-	// Storage: AmmEURUSDC LpBalances (r:2 w:2)
-	// Storage: AmmEURUSDC Reserves (r:1 w:1)
+	// Storage: Amm TotalSupply (r:1 w:1)
+	// Storage: Amm LpBalances (r:2 w:2)
+	// Storage: Amm Reserves (r:1 w:1)
 	// Storage: Tokens Accounts (r:4 w:4)
-	// Storage: AmmEURUSDC KLast (r:1 w:1)
-	// Storage: AmmEURUSDC FeeTo (r:1 w:0)
+	// Storage: Amm KLast (r:1 w:1)
+	// Storage: Amm FeeTo (r:1 w:0)
 	// Storage: System Account (r:1 w:0)
 	// Storage: Timestamp Now (r:1 w:0)
+	// Storage: Amm Observations (r:1 w:1)
+	// Storage: Amm ObservationIndex (r:1 w:1)
 	fn withdraw() -> Weight {
 		(137_320_000 as Weight)
-			.saturating_add(T::DbWeight::get().reads(13 as Weight))
-			.saturating_add(T::DbWeight::get().writes(9 as Weight))
+			.saturating_add(T::DbWeight::get().reads(14 as Weight))
+			.saturating_add(T::DbWeight::get().writes(11 as Weight))
+	}
+	// Storage: Amm TotalSupply (r:1 w:1)
+	// Storage: Amm LpBalances (r:2 w:2)
+	// Storage: Amm Reserves (r:1 w:1)
+	// Storage: Tokens Accounts (r:4 w:4)
+	// Storage: Amm KLast (r:1 w:1)
+	// Storage: Amm FeeTo (r:1 w:0)
+	// Storage: System Account (r:2 w:1)
+	// Storage: Timestamp Now (r:1 w:0)
+	// Storage: Amm Observations (r:1 w:1)
+	// Storage: Amm ObservationIndex (r:1 w:1)
+	fn remove_liquidity() -> Weight {
+		(138_452_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(15 as Weight))
+			.saturating_add(T::DbWeight::get().writes(12 as Weight))
+	}
+	// Storage: Amm Reserves (r:1 w:1)
+	// Storage: Tokens Accounts (r:4 w:4)
+	// Storage: System Account (r:2 w:1)
+	// Storage: Amm KLast (r:1 w:0)
+	// Storage: Amm FeeTo (r:1 w:0)
+	// Storage: Amm TotalSupply (r:1 w:1)
+	// Storage: Amm LpBalances (r:2 w:2)
+	// Storage: Timestamp Now (r:1 w:0)
+	// Storage: Amm Observations (r:1 w:1)
+	// Storage: Amm ObservationIndex (r:1 w:1)
+	fn zap_in() -> Weight {
+		(231_417_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(15 as Weight))
+			.saturating_add(T::DbWeight::get().writes(11 as Weight))
 	}
-	// Storage: AmmEURUSDC PalletAccountId (r:1 w:0)
-	// Storage: AmmEURUSDC Reserves (r:1 w:1)
+	// Storage: Amm TotalSupply (r:1 w:1)
+	// Storage: Amm LpBalances (r:2 w:2)
+	// Storage: Amm Reserves (r:1 w:1)
 	// Storage: Tokens Accounts (r:4 w:4)
+	// Storage: Amm KLast (r:1 w:1)
+	// Storage: Amm FeeTo (r:1 w:0)
 	// Storage: System Account (r:2 w:0)
 	// Storage: Timestamp Now (r:1 w:0)
+	// Storage: Amm Observations (r:1 w:1)
+	// Storage: Amm ObservationIndex (r:1 w:1)
+	fn zap_out() -> Weight {
+		(236_809_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(15 as Weight))
+			.saturating_add(T::DbWeight::get().writes(11 as Weight))
+	}
+	// Storage: Amm Reserves (r:1 w:1)
+	// Storage: Tokens Accounts (r:4 w:4)
+	// Storage: System Account (r:2 w:0)
+	// Storage: Timestamp Now (r:1 w:0)
+	// Storage: Amm Observations (r:1 w:1)
+	// Storage: Amm ObservationIndex (r:1 w:1)
 	fn swap_asset_1_for_asset_2() -> Weight {
 		(106_882_000 as Weight)
-			.saturating_add(T::DbWeight::get().reads(9 as Weight))
-			.saturating_add(T::DbWeight::get().writes(5 as Weight))
+			.saturating_add(T::DbWeight::get().reads(10 as Weight))
+			.saturating_add(T::DbWeight::get().writes(7 as Weight))
 	}
-	// Storage: AmmEURUSDC PalletAccountId (r:1 w:0)
-	// Storage: AmmEURUSDC Reserves (r:1 w:1)
+	// Storage: Amm Reserves (r:1 w:1)
 	// Storage: Tokens Accounts (r:4 w:4)
 	// Storage: System Account (r:2 w:0)
 	// Storage: Timestamp Now (r:1 w:0)
+	// Storage: Amm Observations (r:1 w:1)
+	// Storage: Amm ObservationIndex (r:1 w:1)
 	fn swap_asset_2_for_asset_1() -> Weight {
 		(106_573_000 as Weight)
-			.saturating_add(T::DbWeight::get().reads(9 as Weight))
-			.saturating_add(T::DbWeight::get().writes(5 as Weight))
+			.saturating_add(T::DbWeight::get().reads(10 as Weight))
+			.saturating_add(T::DbWeight::get().writes(7 as Weight))
+	}
+	// Storage: Amm Reserves (r:1 w:1)
+	// Storage: Tokens Accounts (r:4 w:4)
+	// Storage: System Account (r:2 w:0)
+	// Storage: Timestamp Now (r:1 w:0)
+	// Storage: Amm Observations (r:1 w:1)
+	// Storage: Amm ObservationIndex (r:1 w:1)
+	fn swap_exact_in_asset_1_for_asset_2() -> Weight {
+		(105_914_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(10 as Weight))
+			.saturating_add(T::DbWeight::get().writes(7 as Weight))
+	}
+	// Storage: Amm Reserves (r:1 w:1)
+	// Storage: Tokens Accounts (r:4 w:4)
+	// Storage: System Account (r:2 w:0)
+	// Storage: Timestamp Now (r:1 w:0)
+	// Storage: Amm Observations (r:1 w:1)
+	// Storage: Amm ObservationIndex (r:1 w:1)
+	fn swap_exact_in_asset_2_for_asset_1() -> Weight {
+		(106_235_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(10 as Weight))
+			.saturating_add(T::DbWeight::get().writes(7 as Weight))
+	}
+	// Storage: Amm Reserves (r:1 w:1)
+	// Storage: Tokens Accounts (r:4 w:4)
+	// Storage: System Account (r:2 w:0)
+	// Storage: Timestamp Now (r:1 w:0)
+	// Storage: Amm Observations (r:1 w:1)
+	// Storage: Amm ObservationIndex (r:1 w:1)
+	fn swap_exact_in_along_path(n: u32, ) -> Weight {
+		(11_406_000 as Weight)
+			// Standard Error: 28_000
+			.saturating_add((101_873_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads((10 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(T::DbWeight::get().writes((7 as Weight).saturating_mul(n as Weight)))
+	}
+	// Storage: Amm Reserves (r:1 w:1)
+	// Storage: Tokens Accounts (r:4 w:4)
+	// Storage: System Account (r:2 w:0)
+	// Storage: Timestamp Now (r:1 w:0)
+	// Storage: Amm Observations (r:1 w:1)
+	// Storage: Amm ObservationIndex (r:1 w:1)
+	fn swap_exact_out_along_path(n: u32, ) -> Weight {
+		(12_218_000 as Weight)
+			// Standard Error: 31_000
+			.saturating_add((103_095_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads((10 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(T::DbWeight::get().writes((7 as Weight).saturating_mul(n as Weight)))
+	}
+	// Storage: Amm Reserves (r:1 w:1)
+	// Storage: Amm Locks (r:1 w:1)
+	// Storage: Tokens Accounts (r:2 w:2)
+	// Storage: System Account (r:1 w:0)
+	// Storage: Timestamp Now (r:1 w:0)
+	// Storage: Amm Observations (r:1 w:1)
+	// Storage: Amm ObservationIndex (r:1 w:1)
+	fn flash_swap() -> Weight {
+		(84_517_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(8 as Weight))
+			.saturating_add(T::DbWeight::get().writes(6 as Weight))
+	}
+	// Storage: Amm BasketReserves (r:1 w:1)
+	fn create_basket() -> Weight {
+		(16_032_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
+	}
+	// Storage: Amm BasketReserves (r:1 w:1)
+	// Storage: Amm TotalSupply (r:1 w:1)
+	// Storage: Amm LpBalances (r:2 w:2)
+	// Storage: Tokens Accounts (r:2 w:2)
+	// Storage: System Account (r:1 w:1)
+	fn join_basket(n: u32, ) -> Weight {
+		(48_906_000 as Weight)
+			// Standard Error: 21_000
+			.saturating_add((31_204_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads(4 as Weight))
+			.saturating_add(T::DbWeight::get().reads((3 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(T::DbWeight::get().writes(4 as Weight))
+			.saturating_add(T::DbWeight::get().writes((3 as Weight).saturating_mul(n as Weight)))
+	}
+	// Storage: Amm BasketReserves (r:1 w:1)
+	// Storage: Amm TotalSupply (r:1 w:1)
+	// Storage: Amm LpBalances (r:1 w:1)
+	// Storage: Tokens Accounts (r:2 w:2)
+	// Storage: System Account (r:1 w:1)
+	fn exit_basket(n: u32, ) -> Weight {
+		(27_718_000 as Weight)
+			// Standard Error: 19_000
+			.saturating_add((30_482_000 as Weight).saturating_mul(n as Weight))
+			.saturating_add(T::DbWeight::get().reads(3 as Weight))
+			.saturating_add(T::DbWeight::get().reads((3 as Weight).saturating_mul(n as Weight)))
+			.saturating_add(T::DbWeight::get().writes(3 as Weight))
+			.saturating_add(T::DbWeight::get().writes((3 as Weight).saturating_mul(n as Weight)))
+	}
+	// Storage: Amm BasketReserves (r:1 w:1)
+	// Storage: Amm BasketSwapFees (r:1 w:0)
+	// Storage: Amm TotalSupply (r:1 w:1)
+	// Storage: Amm LpBalances (r:1 w:1)
+	// Storage: Tokens Accounts (r:2 w:2)
+	// Storage: System Account (r:1 w:1)
+	fn join_basket_single() -> Weight {
+		(63_104_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(7 as Weight))
+			.saturating_add(T::DbWeight::get().writes(6 as Weight))
+	}
+	// Storage: Amm BasketReserves (r:1 w:1)
+	// Storage: Amm BasketSwapFees (r:1 w:0)
+	// Storage: Amm TotalSupply (r:1 w:1)
+	// Storage: Amm LpBalances (r:1 w:1)
+	// Storage: Tokens Accounts (r:2 w:2)
+	// Storage: System Account (r:1 w:1)
+	fn exit_basket_single() -> Weight {
+		(64_918_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(7 as Weight))
+			.saturating_add(T::DbWeight::get().writes(6 as Weight))
+	}
+	// Storage: Amm BasketReserves (r:1 w:1)
+	// Storage: Amm BasketSwapFees (r:1 w:0)
+	// Storage: Tokens Accounts (r:4 w:4)
+	// Storage: System Account (r:2 w:1)
+	fn swap_basket() -> Weight {
+		(59_976_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(8 as Weight))
+			.saturating_add(T::DbWeight::get().writes(6 as Weight))
+	}
+	// Storage: Amm BasketReserves (r:1 w:0)
+	// Storage: Amm BasketSwapFees (r:0 w:1)
+	fn set_basket_swap_fee() -> Weight {
+		(7_218_000 as Weight)
+			.saturating_add(T::DbWeight::get().reads(1 as Weight))
+			.saturating_add(T::DbWeight::get().writes(1 as Weight))
 	}
 }
@@ -273,9 +273,14 @@ pub mod amm {
 		util::{asset_from_string, trim_zeros},
 		Asset,
 	};
-	use ink_prelude::string::String;
+	use ink_env::{
+		call::{build_call, Call, ExecutionInput, Selector},
+		ReturnFlags,
+	};
+	use ink_prelude::{string::String, vec::Vec};
+	use ink_primitives::Key;
 	use ink_storage::{traits::SpreadAllocate, Mapping};
-	use num_integer::sqrt;
+	use primitive_types::U256;
 
 	/// The ERC-20 error types.
 	#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
@@ -293,6 +298,8 @@ pub mod amm {
 		InsufficientLiquidityBurned,
 		InsufficientInputAmount,
 		InsufficientOutputAmount,
+		/// Returned if the price moved beyond the bounds given by the caller.
+		SlippageExceeded,
 		InvalidDepositToken,
 		InvalidSwapToken,
 		InvalidTo,
@@ -301,6 +308,14 @@ pub mod amm {
 		PairExists,
 		AddressGenerationFailed,
 		WithdrawWithoutSupply,
+		/// Returned if the deadline given by the caller has passed.
+		Expired,
+		/// Returned if the `flash_callback` of the receiver of a swap failed.
+		FlashCallbackFailed,
+		/// Returned if the pair is entered again while it is being updated.
+		Locked,
+		/// Returned if the result of a calculation does not fit into a `Balance`.
+		Overflow,
 
 		// -- mod errors
 		InvalidStellarKeyEncoding,
@@ -320,6 +335,51 @@ pub mod amm {
 
 	const MINIMUM_LIQUIDITY: u128 = 1000;
 
+	/// The selector of the `flash_callback` message which `swap` calls on the receiver,
+	/// as ink! derives it from the name of the message.
+	const FLASH_CALLBACK_SELECTOR: [u8; 4] = [0xda, 0xfd, 0x78, 0x53];
+
+	/// The storage key of the lock of the pair. Unlike the fields of `Pair`, which are only
+	/// written at the end of a message, it is written right away, so that calls back into the
+	/// pair during a call out already see it.
+	const LOCK_KEY: [u8; 32] = [0xff; 32];
+
+	/// The cumulative prices are fixed-point numbers with 18 decimals,
+	/// the same representation as `FixedU128` in the AMM pallet.
+	const PRICE_ACCURACY: u128 = 1_000_000_000_000_000_000;
+
+	/// `numerator / denominator` as a fixed-point number with 18 decimals, rounded down.
+	fn fixed_from_rational(numerator: Balance, denominator: Balance) -> Balance {
+		if denominator == 0 {
+			return 0
+		}
+
+		let integer = numerator / denominator;
+		let remainder = numerator % denominator;
+		// only overflows if `denominator` is larger than `PRICE_ACCURACY`
+		let fraction = match remainder.checked_mul(PRICE_ACCURACY) {
+			Some(scaled) => scaled / denominator,
+			None => remainder / (denominator / PRICE_ACCURACY),
+		};
+
+		integer.saturating_mul(PRICE_ACCURACY).saturating_add(fraction)
+	}
+
+	/// The product of two balances, which always fits into a `U256`.
+	fn product(a: Balance, b: Balance) -> U256 {
+		U256::from(a).saturating_mul(U256::from(b))
+	}
+
+	/// Narrows an intermediate result back to a balance.
+	fn to_balance(value: U256) -> Result<Balance> {
+		Balance::try_from(value).map_err(|_| Error::Overflow)
+	}
+
+	/// `a * b / c`, rounded down, without overflowing in the product. Zero if `c` is zero.
+	fn mul_div(a: Balance, b: Balance, c: Balance) -> Result<Balance> {
+		product(a, b).checked_div(U256::from(c)).map_or(Ok(0), to_balance)
+	}
+
 	/// Event emitted when a token transfer occurs.
 	#[ink(event)]
 	pub struct Transfer {
@@ -331,6 +391,17 @@ pub mod amm {
 		value: Balance,
 	}
 
+	/// Event emitted when an approval occurs that `spender` is allowed to withdraw
+	/// up to the amount of `value` LP tokens from `owner`.
+	#[ink(event)]
+	pub struct Approval {
+		#[ink(topic)]
+		owner: AccountId,
+		#[ink(topic)]
+		spender: AccountId,
+		value: Balance,
+	}
+
 	#[ink(event)]
 	pub struct Mint {
 		#[ink(topic)]
@@ -371,6 +442,21 @@ pub mod amm {
 		reserve_1: Balance,
 	}
 
+	/// Event emitted when the recipient of the protocol fee changes.
+	/// `None` means the fee is disabled.
+	#[ink(event)]
+	pub struct FeeToChanged {
+		#[ink(topic)]
+		fee_to: Option<AccountId>,
+	}
+
+	/// Event emitted when the account allowed to set `fee_to` changes.
+	#[ink(event)]
+	pub struct FeeToSetterChanged {
+		#[ink(topic)]
+		fee_to_setter: AccountId,
+	}
+
 	#[ink(storage)]
 	#[derive(SpreadAllocate)]
 	pub struct Pair {
@@ -383,7 +469,8 @@ pub mod amm {
 
 		price_0_cumulative_last: Balance,
 		price_1_cumulative_last: Balance,
-		k_last: Balance,
+		/// The limbs of the `U256` product of the reserves after the last change of liquidity.
+		k_last: [u64; 4],
 
 		fee_to: Option<AccountId>,
 		fee_to_setter: AccountId,
@@ -391,6 +478,9 @@ pub mod amm {
 		total_supply: Balance,
 		/// Mapping from owner to number of owned token.
 		lp_balances: Mapping<AccountId, Balance>,
+		/// Mapping of the LP token amount which an account is allowed to withdraw
+		/// from another account.
+		allowances: Mapping<(AccountId, AccountId), Balance>,
 	}
 
 	impl Pair {
@@ -402,7 +492,7 @@ pub mod amm {
 			issuer_1: String,
 		) -> Self {
 			let caller = Self::env().caller();
-			// TODO maybe change fee_to_setter to other address
+			// can be handed over with `set_fee_to_setter`
 			let fee_to_setter = caller;
 
 			let asset_code_0 =
@@ -431,7 +521,7 @@ pub mod amm {
 				contract.block_timestamp_last = 0;
 				contract.price_0_cumulative_last = 0;
 				contract.price_1_cumulative_last = 0;
-				contract.k_last = 0;
+				contract.k_last = [0; 4];
 				contract.fee_to = None;
 				contract.fee_to_setter = fee_to_setter;
 				contract.total_supply = 0;
@@ -456,6 +546,60 @@ pub mod amm {
 			self.lp_balances.get(&owner).unwrap_or(0)
 		}
 
+		/// Returns the amount of LP tokens which `spender` is still allowed to withdraw
+		/// from `owner`.
+		///
+		/// Returns `0` if no allowance has been set.
+		#[ink(message)]
+		pub fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+			self.allowances.get((&owner, &spender)).unwrap_or(0)
+		}
+
+		/// Transfers `value` LP tokens from the caller's account to `to`.
+		///
+		/// Returns `InsufficientBalance` if the caller's LP balance is too low.
+		#[ink(message)]
+		pub fn transfer_lp(&mut self, to: AccountId, value: Balance) -> Result<()> {
+			let from = self.env().caller();
+			self._transfer_liquidity(from, to, value)
+		}
+
+		/// Allows `spender` to withdraw from the caller's account multiple times,
+		/// up to `value` LP tokens in total.
+		///
+		/// An existing allowance is overwritten.
+		#[ink(message)]
+		pub fn approve_lp(&mut self, spender: AccountId, value: Balance) -> Result<()> {
+			let owner = self.env().caller();
+			self.allowances.insert((&owner, &spender), &value);
+			self.env().emit_event(Approval { owner, spender, value });
+			Ok(())
+		}
+
+		/// Transfers `value` LP tokens on behalf of `from` to `to`.
+		///
+		/// Returns `InsufficientAllowance` if the caller may not withdraw `value` from `from`,
+		/// and `InsufficientBalance` if the LP balance of `from` is too low.
+		/// An allowance of `Balance::MAX` is never decreased.
+		#[ink(message)]
+		pub fn transfer_lp_from(
+			&mut self,
+			from: AccountId,
+			to: AccountId,
+			value: Balance,
+		) -> Result<()> {
+			let caller = self.env().caller();
+			let allowance = self.allowance(from, caller);
+			if allowance < value {
+				return Err(Error::InsufficientAllowance)
+			}
+			self._transfer_liquidity(from, to, value)?;
+			if allowance != Balance::MAX {
+				self.allowances.insert((&from, &caller), &(allowance - value));
+			}
+			Ok(())
+		}
+
 		#[ink(message)]
 		pub fn asset_1(&self) -> String {
 			String::from_utf8(trim_zeros(&self.asset_0.1).to_vec()).unwrap()
@@ -492,19 +636,32 @@ pub mod amm {
 			return (self.reserve_0, self.reserve_1, self.block_timestamp_last)
 		}
 
+		/// The price of Asset1 in Asset2 with 18 decimals, summed up for every millisecond.
+		/// Only the difference between two readings is meaningful, since the sum wraps on overflow.
 		#[ink(message)]
 		pub fn price_0_cumulative_last(&self) -> u128 {
 			return self.price_0_cumulative_last
 		}
 
+		/// The price of Asset2 in Asset1, summed up like `price_0_cumulative_last`.
 		#[ink(message)]
 		pub fn price_1_cumulative_last(&self) -> u128 {
 			return self.price_1_cumulative_last
 		}
 
 		#[ink(message)]
-		pub fn k_last(&self) -> u128 {
-			return self.k_last
+		pub fn k_last(&self) -> U256 {
+			return U256(self.k_last)
+		}
+
+		#[ink(message)]
+		pub fn fee_to(&self) -> Option<AccountId> {
+			self.fee_to
+		}
+
+		#[ink(message)]
+		pub fn fee_to_setter(&self) -> AccountId {
+			self.fee_to_setter
 		}
 
 		#[ink(message)]
@@ -514,38 +671,74 @@ pub mod amm {
 				return Err(Error::Forbidden)
 			}
 			self.fee_to = Some(fee_to);
+			self.env().emit_event(FeeToChanged { fee_to: Some(fee_to) });
 			Ok(())
 		}
 
+		/// Hand over the right to set `fee_to` to another account.
 		#[ink(message)]
-		/// Force balances to match reserves
-		pub fn skim(&mut self, to: AccountId) -> Result<()> {
-			let contract = self.env().account_id();
-			let amount_0_calc = self.balance_of(contract, self.asset_0).checked_sub(self.reserve_0);
-			if let Some(amount_0) = amount_0_calc {
-				self.transfer_tokens(contract, to, self.asset_0, amount_0)?;
+		pub fn set_fee_to_setter(&mut self, fee_to_setter: AccountId) -> Result<()> {
+			let caller = self.env().caller();
+			if !(caller == self.fee_to_setter) {
+				return Err(Error::Forbidden)
 			}
+			self.fee_to_setter = fee_to_setter;
+			self.env().emit_event(FeeToSetterChanged { fee_to_setter });
+			Ok(())
+		}
 
-			let amount_1_calc = self.balance_of(contract, self.asset_1).checked_sub(self.reserve_1);
-			if let Some(amount_1) = amount_1_calc {
-				self.transfer_tokens(contract, to, self.asset_1, amount_1)?;
+		/// Stop minting the protocol fee.
+		#[ink(message)]
+		pub fn disable_fee(&mut self) -> Result<()> {
+			let caller = self.env().caller();
+			if !(caller == self.fee_to_setter) {
+				return Err(Error::Forbidden)
 			}
+			self.fee_to = None;
+			self.env().emit_event(FeeToChanged { fee_to: None });
 			Ok(())
 		}
 
+		#[ink(message)]
+		/// Force balances to match reserves
+		pub fn skim(&mut self, to: AccountId) -> Result<()> {
+			self.lock(|pair| {
+				let contract = pair.env().account_id();
+				let amount_0_calc =
+					pair.balance_of(contract, pair.asset_0).checked_sub(pair.reserve_0);
+				if let Some(amount_0) = amount_0_calc {
+					pair.transfer_tokens(contract, to, pair.asset_0, amount_0)?;
+				}
+
+				let amount_1_calc =
+					pair.balance_of(contract, pair.asset_1).checked_sub(pair.reserve_1);
+				if let Some(amount_1) = amount_1_calc {
+					pair.transfer_tokens(contract, to, pair.asset_1, amount_1)?;
+				}
+				Ok(())
+			})
+		}
+
 		#[ink(message)]
 		pub fn sync(&mut self) -> Result<()> {
-			let contract = self.env().account_id();
-			let balance_0 = self.balance_of(contract, self.asset_0);
-			let balance_1 = self.balance_of(contract, self.asset_1);
-			self._update(balance_0, balance_1, self.reserve_0, self.reserve_1)?;
-			Ok(())
+			self.lock(|pair| {
+				let contract = pair.env().account_id();
+				let balance_0 = pair.balance_of(contract, pair.asset_0);
+				let balance_1 = pair.balance_of(contract, pair.asset_1);
+				pair._update(balance_0, balance_1, pair.reserve_0, pair.reserve_1)?;
+				Ok(())
+			})
 		}
 
 		/// Add liquidity
 		#[ink(message)]
-		pub fn deposit_asset_1(&mut self, amount: Balance) -> Result<Balance> {
+		pub fn deposit_asset_1(
+			&mut self,
+			amount: Balance,
+			deadline: Option<u64>,
+		) -> Result<Balance> {
 			let caller = self.env().caller();
+			self.ensure_not_expired(deadline)?;
 			let contract = self.env().account_id();
 
 			let (reserve_0, reserve_1, _) = self.get_reserves();
@@ -567,8 +760,13 @@ pub mod amm {
 
 		/// Add liquidity
 		#[ink(message)]
-		pub fn deposit_asset_2(&mut self, amount: Balance) -> Result<Balance> {
+		pub fn deposit_asset_2(
+			&mut self,
+			amount: Balance,
+			deadline: Option<u64>,
+		) -> Result<Balance> {
 			let caller = self.env().caller();
+			self.ensure_not_expired(deadline)?;
 			let contract = self.env().account_id();
 
 			let (reserve_0, reserve_1, _) = self.get_reserves();
@@ -588,7 +786,81 @@ pub mod amm {
 			self.mint(caller)
 		}
 
+		/// Add liquidity in the ratio of the current reserves, taking at most
+		/// `amount_0_desired` of asset 1 and `amount_1_desired` of asset 2.
+		/// Returns the minted amount of LP tokens.
+		#[ink(message)]
+		pub fn add_liquidity(
+			&mut self,
+			amount_0_desired: Balance,
+			amount_1_desired: Balance,
+			amount_0_min: Balance,
+			amount_1_min: Balance,
+			deadline: Option<u64>,
+		) -> Result<Balance> {
+			let caller = self.env().caller();
+			self.ensure_not_expired(deadline)?;
+			let contract = self.env().account_id();
+
+			let (reserve_0, reserve_1, _) = self.get_reserves();
+			let (amount_0, amount_1) = self.optimal_liquidity_amounts(
+				reserve_0,
+				reserve_1,
+				amount_0_desired,
+				amount_1_desired,
+				amount_0_min,
+				amount_1_min,
+			)?;
+
+			self.transfer_tokens(caller, contract, self.asset_0, amount_0)?;
+			self.transfer_tokens(caller, contract, self.asset_1, amount_1)?;
+
+			self.mint(caller)
+		}
+
+		fn optimal_liquidity_amounts(
+			&self,
+			reserve_0: Balance,
+			reserve_1: Balance,
+			amount_0_desired: Balance,
+			amount_1_desired: Balance,
+			amount_0_min: Balance,
+			amount_1_min: Balance,
+		) -> Result<(Balance, Balance)> {
+			if reserve_0 == 0 && reserve_1 == 0 {
+				return Ok((amount_0_desired, amount_1_desired))
+			}
+
+			let amount_1_optimal = self.quote(amount_0_desired, reserve_0, reserve_1)?;
+			if amount_1_optimal <= amount_1_desired {
+				if amount_1_optimal < amount_1_min {
+					return Err(Error::SlippageExceeded)
+				}
+				return Ok((amount_0_desired, amount_1_optimal))
+			}
+
+			let amount_0_optimal = self.quote(amount_1_desired, reserve_1, reserve_0)?;
+			if amount_0_optimal > amount_0_desired || amount_0_optimal < amount_0_min {
+				return Err(Error::SlippageExceeded)
+			}
+			Ok((amount_0_optimal, amount_1_desired))
+		}
+
+		/// Fails if the `deadline` is set and the current block is past it.
+		fn ensure_not_expired(&self, deadline: Option<u64>) -> Result<()> {
+			if let Some(deadline) = deadline {
+				if self.env().block_timestamp() > deadline {
+					return Err(Error::Expired)
+				}
+			}
+			Ok(())
+		}
+
 		fn mint(&mut self, to: AccountId) -> Result<Balance> {
+			self.lock(|pair| pair.mint_locked(to))
+		}
+
+		fn mint_locked(&mut self, to: AccountId) -> Result<Balance> {
 			let contract = self.env().account_id();
 			let (reserve_0, reserve_1, _) = self.get_reserves();
 
@@ -601,14 +873,14 @@ pub mod amm {
 			let total_supply = self.total_supply;
 			let liquidity: Balance;
 			if total_supply == 0 {
-				liquidity =
-					sqrt(amount_0.saturating_mul(amount_1)).saturating_sub(MINIMUM_LIQUIDITY);
+				liquidity = to_balance(product(amount_0, amount_1).integer_sqrt())?
+					.saturating_sub(MINIMUM_LIQUIDITY);
 				let address_zero = AccountId::from([0x0; 32]);
 				self._mint(address_zero, MINIMUM_LIQUIDITY)?; // permanently lock first liquidity tokens
 			} else {
 				liquidity = core::cmp::min(
-					amount_0.saturating_mul(total_supply).saturating_div(reserve_0),
-					amount_1.saturating_mul(total_supply).saturating_div(reserve_1),
+					mul_div(amount_0, total_supply, reserve_0)?,
+					mul_div(amount_1, total_supply, reserve_1)?,
 				);
 			}
 
@@ -620,7 +892,7 @@ pub mod amm {
 
 			self._update(balance_0, balance_1, reserve_0, reserve_1)?;
 			if fee_on {
-				self.k_last = reserve_0.saturating_mul(reserve_1);
+				self.k_last = product(reserve_0, reserve_1).0;
 			}
 
 			self.env().emit_event(Mint { sender: self.env().caller(), amount_0, amount_1 });
@@ -630,8 +902,13 @@ pub mod amm {
 
 		/// Remove Liquidity
 		#[ink(message)]
-		pub fn withdraw(&mut self, amount: Balance) -> Result<(Balance, Balance)> {
+		pub fn withdraw(
+			&mut self,
+			amount: Balance,
+			deadline: Option<u64>,
+		) -> Result<(Balance, Balance)> {
 			let caller = self.env().caller();
+			self.ensure_not_expired(deadline)?;
 			let contract = self.env().account_id();
 
 			let total_supply = self.total_supply;
@@ -645,6 +922,10 @@ pub mod amm {
 		}
 
 		fn burn(&mut self, to: AccountId) -> Result<(Balance, Balance)> {
+			self.lock(|pair| pair.burn_locked(to))
+		}
+
+		fn burn_locked(&mut self, to: AccountId) -> Result<(Balance, Balance)> {
 			let contract = self.env().account_id();
 			let (reserve_0, reserve_1, _) = self.get_reserves();
 			let asset_0 = self.asset_0;
@@ -655,8 +936,8 @@ pub mod amm {
 
 			let fee_on = self._mint_fee(reserve_0, reserve_1)?;
 			let total_supply = self.total_supply;
-			let amount_0 = liquidity.saturating_mul(balance_0).saturating_div(total_supply);
-			let amount_1 = liquidity.saturating_mul(balance_1).saturating_div(total_supply);
+			let amount_0 = mul_div(liquidity, balance_0, total_supply)?;
+			let amount_1 = mul_div(liquidity, balance_1, total_supply)?;
 
 			if !(amount_0 > 0 && amount_1 > 0) {
 				return Err(Error::InsufficientLiquidityBurned)
@@ -671,7 +952,7 @@ pub mod amm {
 			self._update(balance_0, balance_1, reserve_0, reserve_1)?;
 
 			if fee_on {
-				self.k_last = reserve_0.saturating_mul(reserve_1);
+				self.k_last = product(reserve_0, reserve_1).0;
 			}
 
 			self.env()
@@ -679,30 +960,252 @@ pub mod amm {
 			Ok((amount_0, amount_1))
 		}
 
+		/// Add liquidity holding only one of the assets.
+		/// Part of `amount` is swapped for the other asset, so that the rest can be
+		/// deposited together with the swapped amount. Returns the minted amount of LP tokens.
+		#[ink(message)]
+		pub fn zap_in(
+			&mut self,
+			asset: Asset,
+			amount: Balance,
+			min_liquidity: Balance,
+			deadline: Option<u64>,
+		) -> Result<Balance> {
+			let caller = self.env().caller();
+			self.ensure_not_expired(deadline)?;
+			let contract = self.env().account_id();
+
+			let zero_for_one = if asset == self.asset_0 {
+				true
+			} else if asset == self.asset_1 {
+				false
+			} else {
+				return Err(Error::InvalidDepositToken)
+			};
+
+			let (reserve_0, reserve_1, _) = self.get_reserves();
+			let (reserve_in, reserve_out) =
+				if zero_for_one { (reserve_0, reserve_1) } else { (reserve_1, reserve_0) };
+
+			let swap_amount = self.zap_swap_amount(amount, reserve_in)?;
+			let amount_out = self.get_amount_out(swap_amount, reserve_in, reserve_out)?;
+
+			// Errors do not revert the state of the contract,
+			// so the outcome is checked before any tokens are moved.
+			let reserve_in = reserve_in.saturating_add(swap_amount);
+			let reserve_out = reserve_out.saturating_sub(amount_out);
+			let (deposit_in, deposit_out) = self.optimal_liquidity_amounts(
+				reserve_in,
+				reserve_out,
+				amount.saturating_sub(swap_amount),
+				amount_out,
+				0,
+				0,
+			)?;
+			let (reserve_0, reserve_1) =
+				if zero_for_one { (reserve_in, reserve_out) } else { (reserve_out, reserve_in) };
+			let total_supply =
+				self.total_supply.saturating_add(self.fee_liquidity(reserve_0, reserve_1)?);
+			let liquidity = core::cmp::min(
+				mul_div(deposit_in, total_supply, reserve_in)?,
+				mul_div(deposit_out, total_supply, reserve_out)?,
+			);
+			if liquidity < min_liquidity {
+				return Err(Error::SlippageExceeded)
+			}
+
+			self.transfer_tokens(caller, contract, asset, swap_amount)?;
+			let asset_out = if zero_for_one {
+				self._swap(0, amount_out, caller, &[])?;
+				self.asset_1
+			} else {
+				self._swap(amount_out, 0, caller, &[])?;
+				self.asset_0
+			};
+
+			self.transfer_tokens(caller, contract, asset, deposit_in)?;
+			self.transfer_tokens(caller, contract, asset_out, deposit_out)?;
+
+			self.mint(caller)
+		}
+
+		/// Remove `liquidity` and receive only `asset`.
+		/// The withdrawn amount of the other asset is swapped for `asset`.
+		/// Returns the received amount of `asset`.
+		#[ink(message)]
+		pub fn zap_out(
+			&mut self,
+			liquidity: Balance,
+			asset: Asset,
+			min_amount_out: Balance,
+			deadline: Option<u64>,
+		) -> Result<Balance> {
+			let caller = self.env().caller();
+			self.ensure_not_expired(deadline)?;
+			let contract = self.env().account_id();
+
+			let zero_for_one = if asset == self.asset_0 {
+				false
+			} else if asset == self.asset_1 {
+				true
+			} else {
+				return Err(Error::InvalidSwapToken)
+			};
+
+			if self.total_supply == 0 {
+				return Err(Error::WithdrawWithoutSupply)
+			}
+			if self.lp_balance_of(caller) < liquidity {
+				return Err(Error::InsufficientBalance)
+			}
+
+			// Errors do not revert the state of the contract,
+			// so the outcome is checked before any tokens are moved.
+			let (reserve_0, reserve_1, _) = self.get_reserves();
+			let total_supply =
+				self.total_supply.saturating_add(self.fee_liquidity(reserve_0, reserve_1)?);
+			let liquidity_total = liquidity.saturating_add(self.lp_balance_of(contract));
+			let balance_0 = self.balance_of(contract, self.asset_0);
+			let balance_1 = self.balance_of(contract, self.asset_1);
+			let amount_0 = mul_div(liquidity_total, balance_0, total_supply)?;
+			let amount_1 = mul_div(liquidity_total, balance_1, total_supply)?;
+
+			let balance_0 = balance_0.saturating_sub(amount_0);
+			let balance_1 = balance_1.saturating_sub(amount_1);
+			let (amount_kept, amount_swapped) = if zero_for_one {
+				(amount_1, self.get_amount_out(amount_0, balance_0, balance_1)?)
+			} else {
+				(amount_0, self.get_amount_out(amount_1, balance_1, balance_0)?)
+			};
+			let amount_out = amount_kept.saturating_add(amount_swapped);
+			if amount_out < min_amount_out {
+				return Err(Error::SlippageExceeded)
+			}
+
+			self._transfer_liquidity(caller, contract, liquidity)?;
+			let (amount_0, amount_1) = self.burn(caller)?;
+
+			if zero_for_one {
+				self.transfer_tokens(caller, contract, self.asset_0, amount_0)?;
+				self._swap(0, amount_swapped, caller, &[])?;
+			} else {
+				self.transfer_tokens(caller, contract, self.asset_1, amount_1)?;
+				self._swap(amount_swapped, 0, caller, &[])?;
+			}
+
+			Ok(amount_out)
+		}
+
 		/// Swap
 		#[ink(message)]
-		pub fn swap_asset_1_for_asset_2(&mut self, amount_to_receive: Balance) -> Result<()> {
+		pub fn swap_asset_1_for_asset_2(
+			&mut self,
+			amount_to_receive: Balance,
+			max_amount_in: Balance,
+			deadline: Option<u64>,
+		) -> Result<()> {
 			let caller = self.env().caller();
+			self.ensure_not_expired(deadline)?;
 			let contract = self.env().account_id();
 
 			let amount_0_in =
 				self.get_amount_in(amount_to_receive, self.reserve_0, self.reserve_1)?; // TODO check if the reserves are in correct order
+			if amount_0_in > max_amount_in {
+				return Err(Error::SlippageExceeded)
+			}
 			self.transfer_tokens(caller, contract, self.asset_0, amount_0_in)?;
 
-			self._swap(0, amount_to_receive, caller)
+			self._swap(0, amount_to_receive, caller, &[])
 		}
 
 		/// Swap
 		#[ink(message)]
-		pub fn swap_asset_2_for_asset_1(&mut self, amount_to_receive: Balance) -> Result<()> {
+		pub fn swap_asset_2_for_asset_1(
+			&mut self,
+			amount_to_receive: Balance,
+			max_amount_in: Balance,
+			deadline: Option<u64>,
+		) -> Result<()> {
 			let caller = self.env().caller();
+			self.ensure_not_expired(deadline)?;
 			let contract = self.env().account_id();
 
 			let amount_1_in =
 				self.get_amount_in(amount_to_receive, self.reserve_1, self.reserve_0)?;
+			if amount_1_in > max_amount_in {
+				return Err(Error::SlippageExceeded)
+			}
 			self.transfer_tokens(caller, contract, self.asset_1, amount_1_in)?;
 
-			self._swap(amount_to_receive, 0, caller)
+			self._swap(amount_to_receive, 0, caller, &[])
+		}
+
+		/// Send `amount_0_out` of asset 1 and `amount_1_out` of asset 2 to `to`, which have to be
+		/// paid for, including the fee, by the time the invariant is checked.
+		/// Without `data`, the input has to be transferred to the pair beforehand.
+		/// With `data`, `to` has to be a contract with a `flash_callback(sender, amount_0_out,
+		/// amount_1_out, data)` message, which is called after the transfer and can pay within it.
+		/// The whole call is reverted if the invariant does not hold afterwards.
+		#[ink(message)]
+		pub fn swap(
+			&mut self,
+			amount_0_out: Balance,
+			amount_1_out: Balance,
+			to: AccountId,
+			data: Vec<u8>,
+		) -> Result<()> {
+			if let Err(error) = self._swap(amount_0_out, amount_1_out, to, &data) {
+				// the tokens have been sent optimistically, so they have to be taken back
+				ink_env::return_value::<Result<()>>(
+					ReturnFlags::default().set_reverted(true),
+					&Err(error),
+				);
+			}
+			Ok(())
+		}
+
+		/// Swap an exact amount of asset 1, receiving at least `min_amount_out` of asset 2
+		#[ink(message)]
+		pub fn swap_exact_in_asset_1_for_asset_2(
+			&mut self,
+			amount_in: Balance,
+			min_amount_out: Balance,
+			deadline: Option<u64>,
+		) -> Result<Balance> {
+			let caller = self.env().caller();
+			self.ensure_not_expired(deadline)?;
+			let contract = self.env().account_id();
+
+			let amount_1_out = self.get_amount_out(amount_in, self.reserve_0, self.reserve_1)?;
+			if amount_1_out < min_amount_out {
+				return Err(Error::SlippageExceeded)
+			}
+			self.transfer_tokens(caller, contract, self.asset_0, amount_in)?;
+
+			self._swap(0, amount_1_out, caller, &[])?;
+			Ok(amount_1_out)
+		}
+
+		/// Swap an exact amount of asset 2, receiving at least `min_amount_out` of asset 1
+		#[ink(message)]
+		pub fn swap_exact_in_asset_2_for_asset_1(
+			&mut self,
+			amount_in: Balance,
+			min_amount_out: Balance,
+			deadline: Option<u64>,
+		) -> Result<Balance> {
+			let caller = self.env().caller();
+			self.ensure_not_expired(deadline)?;
+			let contract = self.env().account_id();
+
+			let amount_0_out = self.get_amount_out(amount_in, self.reserve_1, self.reserve_0)?;
+			if amount_0_out < min_amount_out {
+				return Err(Error::SlippageExceeded)
+			}
+			self.transfer_tokens(caller, contract, self.asset_1, amount_in)?;
+
+			self._swap(amount_0_out, 0, caller, &[])?;
+			Ok(amount_0_out)
 		}
 
 		fn _swap(
@@ -710,6 +1213,17 @@ pub mod amm {
 			amount_0_out: Balance,
 			amount_1_out: Balance,
 			to: AccountId,
+			data: &[u8],
+		) -> Result<()> {
+			self.lock(|pair| pair.swap_locked(amount_0_out, amount_1_out, to, data))
+		}
+
+		fn swap_locked(
+			&mut self,
+			amount_0_out: Balance,
+			amount_1_out: Balance,
+			to: AccountId,
+			data: &[u8],
 		) -> Result<()> {
 			if !(amount_0_out > 0 || amount_1_out > 0) {
 				return Err(Error::InsufficientOutputAmount)
@@ -731,6 +1245,9 @@ pub mod amm {
 			if amount_1_out > 0 {
 				self.transfer_tokens(contract, to, asset_1, amount_1_out)?;
 			}
+			if !data.is_empty() {
+				self.flash_callback(to, amount_0_out, amount_1_out, data)?;
+			}
 
 			let balance_0 = self.balance_of(contract, asset_0);
 			let balance_1 = self.balance_of(contract, asset_1);
@@ -750,15 +1267,19 @@ pub mod amm {
 				return Err(Error::InsufficientInputAmount)
 			}
 
+			let thousand = U256::from(1000u32);
 			let balance_0_adjusted =
-				balance_0.saturating_mul(1000).saturating_sub(amount_0_in.saturating_mul(3));
+				(U256::from(balance_0) * thousand).saturating_sub(product(amount_0_in, 3));
 			let balance_1_adjusted =
-				balance_1.saturating_mul(1000).saturating_sub(amount_1_in.saturating_mul(3));
-
-			if !(balance_0_adjusted.saturating_mul(balance_1_adjusted) >=
-				reserve_0.saturating_mul(reserve_1).saturating_mul(1000 * 1000))
-			{
-				return Err(Error::InvalidK)
+				(U256::from(balance_1) * thousand).saturating_sub(product(amount_1_in, 3));
+
+			// both sides are below 2^256, as balances are below 2^128
+			let balance = balance_0_adjusted.checked_mul(balance_1_adjusted);
+			let reserve = product(reserve_0, reserve_1).checked_mul(thousand * thousand);
+			match (balance, reserve) {
+				(Some(balance), Some(reserve)) if balance >= reserve => {},
+				(Some(_), Some(_)) => return Err(Error::InvalidK),
+				_ => return Err(Error::Overflow),
 			}
 
 			let balance_0 = self.balance_of(contract, asset_0);
@@ -776,6 +1297,48 @@ pub mod amm {
 			Ok(())
 		}
 
+		/// Returns `false` while a deposit, withdrawal, swap, skim or sync is running.
+		fn unlocked(&self) -> bool {
+			let locked = ink_env::get_contract_storage::<bool>(&Key::from(LOCK_KEY));
+			!locked.ok().flatten().unwrap_or(false)
+		}
+
+		/// Runs `f` with the pair locked, like the `lock` modifier of Uniswap, so that
+		/// calls back into the pair fail with `Locked`. The lock is released even if `f` fails.
+		fn lock<R>(&mut self, f: impl FnOnce(&mut Self) -> Result<R>) -> Result<R> {
+			if !self.unlocked() {
+				return Err(Error::Locked)
+			}
+			ink_env::set_contract_storage(&Key::from(LOCK_KEY), &true);
+
+			let result = f(self);
+
+			ink_env::clear_contract_storage(&Key::from(LOCK_KEY));
+			result
+		}
+
+		/// Calls `flash_callback(sender, amount_0_out, amount_1_out, data)` on the contract `to`.
+		fn flash_callback(
+			&self,
+			to: AccountId,
+			amount_0_out: Balance,
+			amount_1_out: Balance,
+			data: &[u8],
+		) -> Result<()> {
+			build_call::<crate::CustomEnvironment>()
+				.call_type(Call::new().callee(to).gas_limit(0).transferred_value(0))
+				.exec_input(
+					ExecutionInput::new(Selector::new(FLASH_CALLBACK_SELECTOR))
+						.push_arg(self.env().caller())
+						.push_arg(amount_0_out)
+						.push_arg(amount_1_out)
+						.push_arg(data),
+				)
+				.returns::<()>()
+				.fire()
+				.map_err(|_| Error::FlashCallbackFailed)
+		}
+
 		pub fn transfer_tokens(
 			&mut self,
 			from: AccountId,
@@ -812,25 +1375,13 @@ pub mod amm {
 			let time_elapsed = block_timestamp.overflowing_sub(self.block_timestamp_last).0; // overflow is desired
 
 			if time_elapsed > 0 && reserve_0 != 0 && reserve_1 != 0 {
-				// * never overflows, and + overflow is desired
-				self.price_0_cumulative_last = self
-					.price_0_cumulative_last
-					.overflowing_add(
-						reserve_1
-							.checked_div(reserve_0)
-							.unwrap_or(0u128)
-							.saturating_mul(time_elapsed.into()),
-					)
-					.0;
-				self.price_1_cumulative_last = self
-					.price_1_cumulative_last
-					.overflowing_add(
-						reserve_0
-							.checked_div(reserve_1)
-							.unwrap_or(0u128)
-							.saturating_mul(time_elapsed.into()),
-					)
-					.0;
+				// * and + overflow is desired, only differences of cumulative prices matter
+				self.price_0_cumulative_last = self.price_0_cumulative_last.wrapping_add(
+					fixed_from_rational(reserve_1, reserve_0).wrapping_mul(time_elapsed.into()),
+				);
+				self.price_1_cumulative_last = self.price_1_cumulative_last.wrapping_add(
+					fixed_from_rational(reserve_0, reserve_1).wrapping_mul(time_elapsed.into()),
+				);
 			}
 
 			self.reserve_0 = balance_0;
@@ -843,25 +1394,34 @@ pub mod amm {
 		fn _mint_fee(&mut self, reserve_0: Balance, reserve_1: Balance) -> Result<bool> {
 			let fee_on = self.fee_to.is_some();
 			if let Some(fee_to) = self.fee_to {
-				if self.k_last != 0 {
-					let root_k = sqrt(reserve_0.saturating_mul(reserve_1));
-					let root_k_last = sqrt(self.k_last);
-					if root_k > root_k_last {
-						let numerator =
-							self.total_supply.saturating_mul(root_k.saturating_sub(root_k_last));
-						let denominator = root_k.saturating_mul(5).saturating_add(root_k_last);
-						let liquidity = numerator.saturating_div(denominator);
-						if liquidity > 0 {
-							self._mint(fee_to, liquidity)?;
-						}
-					}
+				let liquidity = self.fee_liquidity(reserve_0, reserve_1)?;
+				if liquidity > 0 {
+					self._mint(fee_to, liquidity)?;
 				}
-			} else if self.k_last != 0 {
-				self.k_last = 0;
+			} else if !U256(self.k_last).is_zero() {
+				self.k_last = [0; 4];
 			}
 			Ok(fee_on)
 		}
 
+		/// The amount of LP tokens `_mint_fee` mints to `fee_to` for the given reserves.
+		fn fee_liquidity(&self, reserve_0: Balance, reserve_1: Balance) -> Result<Balance> {
+			let k_last = U256(self.k_last);
+			if self.fee_to.is_none() || k_last.is_zero() {
+				return Ok(0)
+			}
+			let root_k = to_balance(product(reserve_0, reserve_1).integer_sqrt())?;
+			let root_k_last = to_balance(k_last.integer_sqrt())?;
+			if root_k <= root_k_last {
+				return Ok(0)
+			}
+			let denominator = root_k
+				.checked_mul(5)
+				.and_then(|root_k| root_k.checked_add(root_k_last))
+				.ok_or(Error::Overflow)?;
+			mul_div(self.total_supply, root_k - root_k_last, denominator)
+		}
+
 		fn _mint(&mut self, to: AccountId, value: Balance) -> Result<()> {
 			self.total_supply = self.total_supply.saturating_add(value);
 			let balance = self.lp_balance_of(to);
@@ -895,6 +1455,21 @@ pub mod amm {
 			Ok(())
 		}
 
+		/// The part of `amount_in` to swap before depositing, so that the swapped amount and
+		/// the rest of `amount_in` match the ratio of the reserves after the swap.
+		fn zap_swap_amount(&self, amount_in: Balance, reserve_in: Balance) -> Result<Balance> {
+			let b = U256::from(reserve_in) * U256::from(1997u32);
+			let to_sqrt = b
+				.checked_mul(b)
+				.and_then(|b_squared| {
+					product(amount_in, reserve_in)
+						.checked_mul(U256::from(997u32 * 1000 * 4))
+						.and_then(|c| b_squared.checked_add(c))
+				})
+				.ok_or(Error::Overflow)?;
+			to_balance(to_sqrt.integer_sqrt().saturating_sub(b) / U256::from(997u32 * 2))
+		}
+
 		fn get_amount_out(
 			&self,
 			amount_in: Balance,
@@ -907,10 +1482,10 @@ pub mod amm {
 			if !(reserve_in > 0 && reserve_out > 0) {
 				return Err(Error::InsufficientLiquidity)
 			}
-			let amount_in_with_fee = amount_in.saturating_mul(997);
-			let numerator = amount_in_with_fee.saturating_mul(reserve_out);
-			let denominator = reserve_in.saturating_mul(1000).saturating_add(amount_in_with_fee);
-			Ok(numerator.saturating_div(denominator))
+			let amount_in_with_fee = product(amount_in, 997);
+			let numerator = amount_in_with_fee.saturating_mul(U256::from(reserve_out));
+			let denominator = product(reserve_in, 1000).saturating_add(amount_in_with_fee);
+			to_balance(numerator / denominator)
 		}
 
 		fn get_amount_in(
@@ -925,9 +1500,13 @@ pub mod amm {
 			if !(reserve_in > 0 && reserve_out > 0) {
 				return Err(Error::InsufficientLiquidity)
 			}
-			let numerator = reserve_in.saturating_mul(reserve_out).saturating_mul(1000);
-			let denominator = reserve_out.saturating_sub(amount_out).saturating_mul(997);
-			Ok(numerator.saturating_div(denominator).saturating_add(1))
+			let numerator = product(reserve_in, amount_out).checked_mul(U256::from(1000u32));
+			let denominator = product(reserve_out.saturating_sub(amount_out), 997);
+			if denominator.is_zero() {
+				return Err(Error::InsufficientLiquidity)
+			}
+			let amount_in = to_balance(numerator.ok_or(Error::Overflow)? / denominator)?;
+			amount_in.checked_add(1).ok_or(Error::Overflow)
 		}
 
 		fn quote(
@@ -942,7 +1521,7 @@ pub mod amm {
 			if !(reserve_a > 0 && reserve_b > 0) {
 				return Err(Error::InsufficientLiquidity)
 			}
-			let amount_b = amount_a.saturating_mul(reserve_b).saturating_div(reserve_a);
+			let amount_b = mul_div(amount_a, reserve_b, reserve_a)?;
 			Ok(amount_b)
 		}
 	}
@@ -1100,6 +1679,69 @@ pub mod amm {
 			assert_eq!(pair.issuer_1(), "GAP4SFKVFVKENJ7B7VORAYKPB3CJIAJ2LMKDJ22ZFHIAIVYQOR6W3CXF");
 		}
 
+		#[ink::test]
+		#[serial]
+		fn fee_to_setter_can_be_handed_over() {
+			let setter = AccountId::from([0x01; 32]);
+			let new_setter = AccountId::from(TO_BYTE_ARRAY);
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(setter);
+
+			let mut pair = get_default_pair();
+			assert_eq!(pair.fee_to_setter(), setter);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(new_setter);
+			assert_eq!(Err(Error::Forbidden), pair.set_fee_to_setter(new_setter));
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(setter);
+			pair.set_fee_to_setter(new_setter).expect("Could not set fee_to_setter");
+			assert_eq!(pair.fee_to_setter(), new_setter);
+			assert_eq!(Err(Error::Forbidden), pair.set_fee_to(setter));
+			assert_eq!(Err(Error::Forbidden), pair.disable_fee());
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(new_setter);
+			pair.set_fee_to(setter).expect("Could not set fee_to");
+			assert_eq!(pair.fee_to(), Some(setter));
+			pair.disable_fee().expect("Could not disable fee");
+			assert_eq!(pair.fee_to(), None);
+
+			// `new` emits a `Transfer`, followed by the events of the calls above
+			let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+			assert_eq!(emitted_events.len(), 4);
+		}
+
+		#[ink::test]
+		#[serial]
+		fn lp_tokens_can_be_transferred_with_allowance() {
+			let owner = AccountId::from([0x01; 32]);
+			let spender = AccountId::from(TO_BYTE_ARRAY);
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(owner);
+
+			let mut pair = get_default_pair();
+			pair._mint(owner, 1_000).expect("Could not mint");
+
+			pair.transfer_lp(spender, 100).expect("Could not transfer");
+			assert_eq!(pair.lp_balance_of(owner), 900);
+			assert_eq!(pair.lp_balance_of(spender), 100);
+			assert_eq!(Err(Error::InsufficientBalance), pair.transfer_lp(spender, 901));
+
+			pair.approve_lp(spender, 500).expect("Could not approve");
+			assert_eq!(pair.allowance(owner, spender), 500);
+
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(spender);
+			assert_eq!(
+				Err(Error::InsufficientAllowance),
+				pair.transfer_lp_from(owner, spender, 501)
+			);
+			pair.transfer_lp_from(owner, spender, 200).expect("Could not transfer");
+			assert_eq!(pair.lp_balance_of(owner), 700);
+			assert_eq!(pair.lp_balance_of(spender), 300);
+			assert_eq!(pair.allowance(owner, spender), 300);
+
+			// `new` and `_mint` emit a `Transfer` each, followed by the events of the calls above
+			let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+			assert_eq!(emitted_events.len(), 5);
+		}
+
 		#[ink::test]
 		#[serial]
 		fn balance_of_works() {
@@ -1133,7 +1775,7 @@ pub mod amm {
 			let user_balance_0_pre_deposit = pair.balance_of(to, pair.asset_0);
 			let user_balance_1_pre_deposit = pair.balance_of(to, pair.asset_1);
 
-			let result = pair.deposit_asset_1(deposit_amount);
+			let result = pair.deposit_asset_1(deposit_amount, None);
 			let gained_lp = result.expect("Could not unwrap gained lp");
 			assert_eq!(gained_lp > 0, true, "Expected lp to be greater than 0");
 
@@ -1171,18 +1813,19 @@ pub mod amm {
 
 			// execute initial deposit
 			let deposit_amount = 10_000;
-			let result = pair.deposit_asset_1(deposit_amount);
+			let result = pair.deposit_asset_1(deposit_amount, None);
 			let gained_lp = result.expect("Could not unwrap gained lp");
 			assert_eq!(gained_lp > 0, true, "Expected lp to be greater than 0");
 
 			// swap to make it unbalanced
-			pair.swap_asset_2_for_asset_1(10).expect("Swap did not work");
+			pair.swap_asset_2_for_asset_1(10, Balance::MAX, None)
+				.expect("Swap did not work");
 
 			let user_balance_0_pre_deposit = pair.balance_of(to, pair.asset_0);
 			let user_balance_1_pre_deposit = pair.balance_of(to, pair.asset_1);
 
 			// deposit on unbalanced pair
-			let result = pair.deposit_asset_1(deposit_amount);
+			let result = pair.deposit_asset_1(deposit_amount, None);
 			let gained_lp = result.expect("Could not unwrap gained lp");
 			assert_eq!(gained_lp > 0, true, "Expected lp to be greater than 0");
 
@@ -1214,12 +1857,12 @@ pub mod amm {
 			let initial_supply = 1_000_000;
 			add_supply_for_account(to, initial_supply, &pair);
 
-			let result = pair.withdraw(1);
+			let result = pair.withdraw(1, None);
 			assert_eq!(Err(Error::WithdrawWithoutSupply), result);
 
-			let gained_lp = pair.deposit_asset_1(5_000).expect("Could not deposit");
+			let gained_lp = pair.deposit_asset_1(5_000, None).expect("Could not deposit");
 			// try withdrawing more LP than account has
-			let result = pair.withdraw(gained_lp + 2);
+			let result = pair.withdraw(gained_lp + 2, None);
 			assert_eq!(Err(Error::InsufficientBalance), result);
 		}
 
@@ -1238,7 +1881,7 @@ pub mod amm {
 			add_supply_for_account(to, initial_supply, &pair);
 
 			let deposit_amount = 50_000;
-			let result = pair.deposit_asset_1(deposit_amount);
+			let result = pair.deposit_asset_1(deposit_amount, None);
 			let gained_lp = result.expect("Could not unwrap gained lp");
 			assert_eq!(gained_lp > 0, true, "Expected received amount of LP to be greater than 0");
 
@@ -1246,7 +1889,7 @@ pub mod amm {
 			let user_balance_1_pre_withdraw = pair.balance_of(to, pair.asset_1);
 
 			// We cannot withdraw all LP because the pair would be empty so we withdraw with 1 less LP token
-			let result = pair.withdraw(gained_lp - 1);
+			let result = pair.withdraw(gained_lp - 1, None);
 			let (amount_0, amount_1) = result.expect("Could not unwrap result");
 			assert_eq!(true, amount_0 > 0, "Expected received amount to be greater than 0");
 			assert_eq!(true, amount_1 > 0, "Expected received amount to be greater than 0");
@@ -1274,14 +1917,14 @@ pub mod amm {
 
 			let deposit_amount = 500_000;
 			// do initial deposit which initiates total_supply
-			pair.deposit_asset_1(deposit_amount).expect("Could not deposit");
+			pair.deposit_asset_1(deposit_amount, None).expect("Could not deposit");
 
 			// do second deposit
-			let result = pair.deposit_asset_1(deposit_amount);
+			let result = pair.deposit_asset_1(deposit_amount, None);
 			let gained_lp = result.expect("Could not unwrap gained lp");
 			assert_eq!(gained_lp > 0, true, "Expected received amount of LP to be greater than 0");
 
-			let result = pair.withdraw(gained_lp);
+			let result = pair.withdraw(gained_lp, None);
 			let (amount_0, amount_1) = result.expect("Could not unwrap result");
 			assert_eq!(
 				amount_0, deposit_amount,
@@ -1307,14 +1950,14 @@ pub mod amm {
 			let initial_supply = 1_000_000;
 			add_supply_for_account(to, initial_supply, &pair);
 
-			let gained_lp = pair.deposit_asset_1(5000);
+			let gained_lp = pair.deposit_asset_1(5000, None);
 			let gained_lp = gained_lp.expect("Could not unwrap gained lp");
 			assert_eq!(gained_lp > 0, true, "Expected lp to be greater than 0");
 
 			let swap_amount = 100;
 			let user_balance_0_pre_swap = pair.balance_of(to, pair.asset_0);
 
-			let result = pair.swap_asset_2_for_asset_1(swap_amount);
+			let result = pair.swap_asset_2_for_asset_1(swap_amount, Balance::MAX, None);
 			result.expect("Encountered error in swap");
 
 			let user_balance_0_post_swap = pair.balance_of(to, pair.asset_0);
@@ -1322,13 +1965,127 @@ pub mod amm {
 
 			let user_balance_1_pre_swap = pair.balance_of(to, pair.asset_1);
 
-			let result = pair.swap_asset_1_for_asset_2(swap_amount);
+			let result = pair.swap_asset_1_for_asset_2(swap_amount, Balance::MAX, None);
 			result.expect("Encountered error in swap");
 
 			let user_balance_1_post_swap = pair.balance_of(to, pair.asset_1);
 			assert_eq!(user_balance_1_post_swap, user_balance_1_pre_swap + swap_amount);
 		}
 
+		#[ink::test]
+		#[serial]
+		fn swap_pays_out_for_transferred_input() {
+			reset_map();
+			ink_env::test::register_chain_extension(MockedBalanceExtension);
+			ink_env::test::register_chain_extension(MockedTransferExtension);
+
+			let to = AccountId::from(TO_BYTE_ARRAY);
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(to);
+
+			let mut pair = get_default_pair();
+			add_supply_for_account(to, 1_000_000, &pair);
+			pair.deposit_asset_1(5000, None).expect("Could not deposit");
+
+			let contract = ink_env::test::callee::<ink_env::DefaultEnvironment>();
+			let user_balance_1_pre_swap = pair.balance_of(to, pair.asset_1);
+
+			// 100 * 997 * 5000 / (5000 * 1000 + 100 * 997) = 97
+			pair.transfer_tokens(to, contract, pair.asset_0, 100)
+				.expect("Could not transfer");
+			pair.swap(0, 97, to, Vec::new()).expect("Encountered error in swap");
+
+			assert_eq!(pair.balance_of(to, pair.asset_1), user_balance_1_pre_swap + 97);
+			let (reserve_0, reserve_1, _) = pair.get_reserves();
+			assert_eq!((reserve_0, reserve_1), (5100, 4903));
+		}
+
+		#[ink::test]
+		#[serial]
+		fn swap_exact_out_charges_for_the_received_amount() {
+			reset_map();
+			ink_env::test::register_chain_extension(MockedBalanceExtension);
+			ink_env::test::register_chain_extension(MockedTransferExtension);
+
+			let to = AccountId::from(TO_BYTE_ARRAY);
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(to);
+
+			let mut pair = get_default_pair();
+			add_supply_for_account(to, 1_000_000, &pair);
+			pair.deposit_asset_1(5000, None).expect("Could not deposit");
+
+			// 5000 * 100 * 1000 / ((5000 - 100) * 997) + 1 = 103
+			assert_eq!(pair.get_amount_in(100, 5000, 5000), Ok(103));
+			assert_eq!(Err(Error::SlippageExceeded), pair.swap_asset_1_for_asset_2(100, 102, None));
+
+			let user_balance_0_pre_swap = pair.balance_of(to, pair.asset_0);
+			pair.swap_asset_1_for_asset_2(100, 103, None)
+				.expect("Encountered error in swap");
+
+			assert_eq!(pair.balance_of(to, pair.asset_0), user_balance_0_pre_swap - 103);
+			let (reserve_0, reserve_1, _) = pair.get_reserves();
+			assert_eq!((reserve_0, reserve_1), (5103, 4900));
+		}
+
+		#[ink::test]
+		#[serial]
+		fn large_balances_do_not_overflow() {
+			reset_map();
+			ink_env::test::register_chain_extension(MockedBalanceExtension);
+			ink_env::test::register_chain_extension(MockedTransferExtension);
+
+			let to = AccountId::from(TO_BYTE_ARRAY);
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(to);
+
+			let mut pair = get_default_pair();
+			let deposit = 10u128.pow(24);
+			add_supply_for_account(to, 10u128.pow(25), &pair);
+
+			// the product of the amounts does not fit into a u128
+			pair.deposit_asset_1(deposit, None).expect("Could not deposit");
+			assert_eq!(pair.lp_balance_of(to), deposit - MINIMUM_LIQUIDITY);
+
+			// paying almost nothing for a tenth of the reserve breaks the invariant
+			let contract = ink_env::test::callee::<ink_env::DefaultEnvironment>();
+			pair.transfer_tokens(to, contract, pair.asset_0, 1).expect("Could not transfer");
+			assert_eq!(Err(Error::InvalidK), pair._swap(0, deposit / 10, to, &[]));
+
+			assert_eq!(Err(Error::Overflow), pair.quote(Balance::MAX, 1, 2));
+		}
+
+		#[ink::test]
+		#[serial]
+		fn calls_back_into_the_pair_fail_while_it_is_locked() {
+			reset_map();
+			ink_env::test::register_chain_extension(MockedBalanceExtension);
+			ink_env::test::register_chain_extension(MockedTransferExtension);
+
+			let to = AccountId::from(TO_BYTE_ARRAY);
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(to);
+
+			let mut pair = get_default_pair();
+			add_supply_for_account(to, 1_000_000, &pair);
+			pair.deposit_asset_1(5000, None).expect("Could not deposit");
+
+			// the chain extension mocks cannot call the pair, so the calls a malicious
+			// extension or flash swap receiver would make are made while holding the lock
+			pair.lock(|pair| {
+				assert!(!pair.unlocked());
+				assert_eq!(Err(Error::Locked), pair.mint(to));
+				assert_eq!(Err(Error::Locked), pair.burn(to));
+				assert_eq!(Err(Error::Locked), pair._swap(0, 97, to, &[]));
+				assert_eq!(Err(Error::Locked), pair.skim(to));
+				assert_eq!(Err(Error::Locked), pair.sync());
+				Ok(())
+			})
+			.expect("Could not lock");
+
+			// the lock is released, also after a failed call
+			assert!(pair.unlocked());
+			assert_eq!(Err(Error::InsufficientOutputAmount), pair._swap(0, 0, to, &[]));
+			assert!(pair.unlocked());
+			pair.sync().expect("Could not sync");
+		}
+
 		#[ink::test]
 		#[serial]
 		fn swap_works_with_large_amount() {
@@ -1343,25 +2100,234 @@ pub mod amm {
 			let initial_supply = 10_000_000;
 			add_supply_for_account(to, initial_supply, &pair);
 
-			let gained_lp = pair.deposit_asset_1(1_000_000);
+			let gained_lp = pair.deposit_asset_1(1_000_000, None);
 			let gained_lp = gained_lp.expect("Could not unwrap gained lp");
 			assert_eq!(gained_lp > 0, true, "Expected lp to be greater than 0");
 
 			let swap_amount = 200_000;
 			let user_balance_0_pre_swap = pair.balance_of(to, pair.asset_0);
 
-			let result = pair.swap_asset_2_for_asset_1(swap_amount);
+			let result = pair.swap_asset_2_for_asset_1(swap_amount, Balance::MAX, None);
 			result.expect("Encountered error in swap");
 
 			let user_balance_0_post_swap = pair.balance_of(to, pair.asset_0);
 			assert_eq!(user_balance_0_post_swap, user_balance_0_pre_swap + swap_amount);
 			let user_balance_1_pre_swap = pair.balance_of(to, pair.asset_1);
 
-			let result = pair.swap_asset_1_for_asset_2(swap_amount);
+			let result = pair.swap_asset_1_for_asset_2(swap_amount, Balance::MAX, None);
 			result.expect("Encountered error in swap");
 
 			let user_balance_1_post_swap = pair.balance_of(to, pair.asset_1);
 			assert_eq!(user_balance_1_post_swap, user_balance_1_pre_swap + swap_amount);
 		}
+
+		#[ink::test]
+		#[serial]
+		fn swap_exact_in_works() {
+			reset_map();
+			ink_env::test::register_chain_extension(MockedBalanceExtension);
+			ink_env::test::register_chain_extension(MockedTransferExtension);
+
+			let to = AccountId::from(TO_BYTE_ARRAY);
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(to);
+
+			let mut pair = get_default_pair();
+			let initial_supply = 1_000_000;
+			add_supply_for_account(to, initial_supply, &pair);
+
+			pair.deposit_asset_1(100_000, None).expect("Could not unwrap gained lp");
+
+			let amount_in = 1_000;
+			let user_balance_0_pre_swap = pair.balance_of(to, pair.asset_0);
+			let user_balance_1_pre_swap = pair.balance_of(to, pair.asset_1);
+
+			let amount_out = pair
+				.swap_exact_in_asset_1_for_asset_2(amount_in, 980, None)
+				.expect("Encountered error in swap");
+			assert_eq!(amount_out, 987);
+
+			let user_balance_0_post_swap = pair.balance_of(to, pair.asset_0);
+			let user_balance_1_post_swap = pair.balance_of(to, pair.asset_1);
+			assert_eq!(user_balance_0_post_swap, user_balance_0_pre_swap - amount_in);
+			assert_eq!(user_balance_1_post_swap, user_balance_1_pre_swap + amount_out);
+
+			let amount_out = pair
+				.swap_exact_in_asset_2_for_asset_1(amount_in, 980, None)
+				.expect("Encountered error in swap");
+			assert_eq!(pair.balance_of(to, pair.asset_0), user_balance_0_post_swap + amount_out);
+		}
+
+		#[ink::test]
+		#[serial]
+		fn swap_fails_if_slippage_exceeded() {
+			reset_map();
+			ink_env::test::register_chain_extension(MockedBalanceExtension);
+			ink_env::test::register_chain_extension(MockedTransferExtension);
+
+			let to = AccountId::from(TO_BYTE_ARRAY);
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(to);
+
+			let mut pair = get_default_pair();
+			let initial_supply = 1_000_000;
+			add_supply_for_account(to, initial_supply, &pair);
+
+			pair.deposit_asset_1(100_000, None).expect("Could not unwrap gained lp");
+
+			let result = pair.swap_exact_in_asset_1_for_asset_2(1_000, 988, None);
+			assert_eq!(Err(Error::SlippageExceeded), result);
+			let result = pair.swap_exact_in_asset_2_for_asset_1(1_000, 988, None);
+			assert_eq!(Err(Error::SlippageExceeded), result);
+
+			// the fee alone makes the input larger than the output
+			let result = pair.swap_asset_1_for_asset_2(1_000, 1_000, None);
+			assert_eq!(Err(Error::SlippageExceeded), result);
+			let result = pair.swap_asset_2_for_asset_1(1_000, 1_000, None);
+			assert_eq!(Err(Error::SlippageExceeded), result);
+		}
+
+		#[ink::test]
+		#[serial]
+		fn calls_fail_after_deadline() {
+			reset_map();
+			ink_env::test::register_chain_extension(MockedBalanceExtension);
+			ink_env::test::register_chain_extension(MockedTransferExtension);
+
+			let to = AccountId::from(TO_BYTE_ARRAY);
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(to);
+
+			let mut pair = get_default_pair();
+			let initial_supply = 1_000_000;
+			add_supply_for_account(to, initial_supply, &pair);
+
+			let deadline = Some(ink_env::block_timestamp::<ink_env::DefaultEnvironment>());
+			pair.deposit_asset_1(100_000, deadline).expect("Could not unwrap gained lp");
+
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+				.expect("Cannot advance block");
+
+			assert_eq!(Err(Error::Expired), pair.deposit_asset_1(1_000, deadline));
+			assert_eq!(Err(Error::Expired), pair.deposit_asset_2(1_000, deadline));
+			assert_eq!(Err(Error::Expired), pair.withdraw(1, deadline));
+			assert_eq!(
+				Err(Error::Expired),
+				pair.swap_asset_1_for_asset_2(10, Balance::MAX, deadline)
+			);
+			assert_eq!(
+				Err(Error::Expired),
+				pair.swap_asset_2_for_asset_1(10, Balance::MAX, deadline)
+			);
+			assert_eq!(
+				Err(Error::Expired),
+				pair.swap_exact_in_asset_1_for_asset_2(10, 0, deadline)
+			);
+			assert_eq!(
+				Err(Error::Expired),
+				pair.swap_exact_in_asset_2_for_asset_1(10, 0, deadline)
+			);
+		}
+
+		#[ink::test]
+		#[serial]
+		fn cumulative_prices_keep_fractions() {
+			reset_map();
+			ink_env::test::register_chain_extension(MockedBalanceExtension);
+			ink_env::test::register_chain_extension(MockedTransferExtension);
+
+			let to = AccountId::from(TO_BYTE_ARRAY);
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(to);
+
+			let mut pair = get_default_pair();
+			add_supply_for_account(to, 100_000, &pair);
+
+			pair.add_liquidity(10_000, 30_000, 0, 0, None).expect("Could not add liquidity");
+			let timestamp = ink_env::block_timestamp::<ink_env::DefaultEnvironment>();
+
+			ink_env::test::advance_block::<ink_env::DefaultEnvironment>()
+				.expect("Cannot advance block");
+			pair.sync().expect("Could not sync");
+			let time_elapsed =
+				(ink_env::block_timestamp::<ink_env::DefaultEnvironment>() - timestamp) as u128;
+
+			assert_eq!(pair.price_0_cumulative_last(), 3 * PRICE_ACCURACY * time_elapsed);
+			assert_eq!(pair.price_1_cumulative_last(), 333_333_333_333_333_333 * time_elapsed);
+		}
+
+		#[ink::test]
+		#[serial]
+		fn add_liquidity_works() {
+			reset_map();
+			ink_env::test::register_chain_extension(MockedBalanceExtension);
+			ink_env::test::register_chain_extension(MockedTransferExtension);
+
+			let to = AccountId::from(TO_BYTE_ARRAY);
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(to);
+
+			let mut pair = get_default_pair();
+			let initial_supply = 1_000_000;
+			add_supply_for_account(to, initial_supply, &pair);
+
+			let gained_lp = pair
+				.add_liquidity(10_000, 20_000, 0, 0, None)
+				.expect("Could not unwrap gained lp");
+			assert_eq!(pair.lp_balance_of(to), gained_lp);
+			assert_eq!(pair.get_reserves().0, 10_000);
+			assert_eq!(pair.get_reserves().1, 20_000);
+
+			pair.add_liquidity(1_000, 5_000, 0, 0, None)
+				.expect("Could not unwrap gained lp");
+			assert_eq!(pair.get_reserves().0, 11_000);
+			assert_eq!(pair.get_reserves().1, 22_000);
+
+			pair.add_liquidity(1_000, 1_000, 0, 0, None)
+				.expect("Could not unwrap gained lp");
+			assert_eq!(pair.get_reserves().0, 11_500);
+			assert_eq!(pair.get_reserves().1, 23_000);
+
+			let result = pair.add_liquidity(1_000, 5_000, 0, 2_001, None);
+			assert_eq!(Err(Error::SlippageExceeded), result);
+			let result = pair.add_liquidity(1_000, 1_000, 501, 0, None);
+			assert_eq!(Err(Error::SlippageExceeded), result);
+		}
+
+		#[ink::test]
+		#[serial]
+		fn zap_in_and_zap_out_work() {
+			reset_map();
+			ink_env::test::register_chain_extension(MockedBalanceExtension);
+			ink_env::test::register_chain_extension(MockedTransferExtension);
+
+			let to = AccountId::from(TO_BYTE_ARRAY);
+			ink_env::test::set_caller::<ink_env::DefaultEnvironment>(to);
+
+			let mut pair = get_default_pair();
+			let initial_supply = 1_000_000;
+			add_supply_for_account(to, initial_supply, &pair);
+
+			pair.deposit_asset_1(100_000, None).expect("Could not unwrap gained lp");
+			let lp_pre_zap = pair.lp_balance_of(to);
+
+			let result = pair.zap_in(pair.asset_0, 10_000, 4_872, None);
+			assert_eq!(Err(Error::SlippageExceeded), result);
+
+			// 4_888 are swapped for 4_646, which are deposited with 5_110 of the rest
+			let gained_lp = pair
+				.zap_in(pair.asset_0, 10_000, 4_871, None)
+				.expect("Encountered error in zap");
+			assert_eq!(gained_lp, 4_871);
+			assert_eq!(pair.lp_balance_of(to), lp_pre_zap + gained_lp);
+			assert_eq!(pair.balance_of(to, pair.asset_0), initial_supply - 100_000 - 9_998);
+			assert_eq!(pair.balance_of(to, pair.asset_1), initial_supply - 100_000);
+
+			let user_balance_0_pre_zap = pair.balance_of(to, pair.asset_0);
+			let user_balance_1_pre_zap = pair.balance_of(to, pair.asset_1);
+
+			let result = pair.zap_out(10_000, pair.asset_1, initial_supply, None);
+			assert_eq!(Err(Error::SlippageExceeded), result);
+
+			let amount_out =
+				pair.zap_out(10_000, pair.asset_1, 1, None).expect("Encountered error in zap");
+			assert_eq!(pair.balance_of(to, pair.asset_0), user_balance_0_pre_zap);
+			assert_eq!(pair.balance_of(to, pair.asset_1), user_balance_1_pre_zap + amount_out);
+		}
 	}
 }
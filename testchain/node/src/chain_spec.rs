@@ -2,7 +2,7 @@ use sc_service::ChainType;
 use sp_core::{sr25519, Pair, Public};
 use sp_runtime::traits::{IdentifyAccount, Verify};
 use test_node_runtime::{
-	AccountId, AmmEURUSDCConfig, BalancesConfig, CurrencyId, GenesisConfig, Signature, SudoConfig,
+	AccountId, AmmConfig, BalancesConfig, CurrencyId, GenesisConfig, Signature, SudoConfig,
 	SystemConfig, TokensConfig, WASM_BINARY,
 };
 
@@ -175,10 +175,9 @@ fn testnet_genesis(
 				.collect(),
 		},
 
-		amm_eurusdc: AmmEURUSDCConfig {
-			contract_id: Some(get_account_id_from_seed::<sr25519::Public>("Contract")),
-			zero_account: Some(get_account_id_from_seed::<sr25519::Public>("Zero")),
+		amm: AmmConfig {
 			fee_to_setter: Some(get_account_id_from_seed::<sr25519::Public>("Alice")),
+			pools: vec![(stellar_eur_asset, stellar_usdc_asset)],
 		},
 	}
 }
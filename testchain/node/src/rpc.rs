@@ -8,12 +8,15 @@
 use std::sync::Arc;
 
 use pallet_contracts_rpc::{Contracts, ContractsApi};
+use pallet_pendulum_amm_rpc::{Amm, AmmApi};
 pub use sc_rpc_api::DenyUnsafe;
 use sc_transaction_pool_api::TransactionPool;
 use sp_api::ProvideRuntimeApi;
 use sp_block_builder::BlockBuilder;
 use sp_blockchain::{Error as BlockChainError, HeaderBackend, HeaderMetadata};
-use test_node_runtime::{opaque::Block, AccountId, Balance, BlockNumber, Hash, Index};
+use test_node_runtime::{
+	opaque::Block, AccountId, Balance, BlockNumber, CurrencyId, Hash, Index, Moment,
+};
 
 /// Full client dependencies.
 pub struct FullDeps<C, P> {
@@ -34,6 +37,7 @@ where
 	C::Api: substrate_frame_rpc_system::AccountNonceApi<Block, AccountId, Index>,
 	C::Api: pallet_contracts_rpc::ContractsRuntimeApi<Block, AccountId, Balance, BlockNumber, Hash>,
 	C::Api: pallet_transaction_payment_rpc::TransactionPaymentRuntimeApi<Block, Balance>,
+	C::Api: pallet_pendulum_amm_rpc::AmmRuntimeApi<Block, AccountId, CurrencyId, Balance, Moment>,
 	C::Api: BlockBuilder<Block>,
 	P: TransactionPool + 'static,
 {
@@ -55,5 +59,8 @@ where
 	// Contracts RPC API extension
 	io.extend_with(ContractsApi::to_delegate(Contracts::new(client.clone())));
 
+	// AMM RPC API extension
+	io.extend_with(AmmApi::to_delegate(Amm::new(client)));
+
 	io
 }
@@ -31,7 +31,7 @@ use sp_std::{fmt, prelude::*};
 use sp_version::NativeVersion;
 use sp_version::RuntimeVersion;
 
-use pallet_pendulum_amm::AmmExtension;
+use pallet_pendulum_amm::{AmmExtension, LpTokenOf, LpTokens};
 
 // A few exports that help ease life for downstream crates.
 pub use frame_support::{
@@ -43,7 +43,7 @@ pub use frame_support::{
 		constants::{BlockExecutionWeight, ExtrinsicBaseWeight, RocksDbWeight, WEIGHT_PER_SECOND},
 		IdentityFee, Weight,
 	},
-	StorageValue,
+	PalletId, StorageValue,
 };
 pub use frame_system::Call as SystemCall;
 pub use pallet_balances::Call as BalancesCall;
@@ -74,6 +74,9 @@ pub type Index = u32;
 /// A hash of some data used by the chain.
 pub type Hash = sp_core::H256;
 
+/// A timestamp: milliseconds since the unix epoch.
+pub type Moment = u64;
+
 pub type Bytes4 = [u8; 4];
 pub type Bytes12 = [u8; 12];
 pub type AssetIssuer = [u8; 32];
@@ -249,31 +252,15 @@ parameter_types! {
 }
 
 impl pallet_timestamp::Config for Runtime {
-	/// A timestamp: milliseconds since the unix epoch.
-	type Moment = u64;
+	type Moment = Moment;
 	type OnTimestampSet = ();
 	type MinimumPeriod = MinimumPeriod;
 	type WeightInfo = ();
 }
 
 parameter_types! {
-	pub StellarUsdcAsset: CurrencyId = CurrencyId::try_from((
-		"USDC",
-		[
-			20, 209, 150, 49, 176, 55, 23, 217, 171, 154, 54, 110, 16, 50, 30, 226, 102, 231, 46,
-			199, 108, 171, 97, 144, 240, 161, 51, 109, 72, 34, 159, 139,
-		],
-	))
-	.unwrap();
-
-	pub StellarEurAsset: CurrencyId = CurrencyId::try_from((
-		"EUR",
-		[
-			20, 209, 150, 49, 176, 55, 23, 217, 171, 154, 54, 110, 16, 50, 30, 226, 102, 231, 46,
-			199, 108, 171, 97, 144, 240, 161, 51, 109, 72, 34, 159, 139,
-		],
-	))
-	.unwrap();
+	pub const AmmPalletId: PalletId = PalletId(*b"pen/amm_");
+	pub const AmmSwapFee: Permill = Permill::from_perthousand(3);
 }
 
 impl pallet_pendulum_amm::Config for Runtime {
@@ -282,12 +269,19 @@ impl pallet_pendulum_amm::Config for Runtime {
 	type Balance = Balance;
 	type CurrencyId = CurrencyId;
 	type AmmExtension = Extension;
+	type LpTokens = LpTokensAsCurrencies;
+	type FlashLoanReceiver = ();
+	type PalletId = AmmPalletId;
 	type MinimumLiquidity = ConstU128<1000>;
 
 	type MintFee = ConstU128<5>;
-	type BaseFee = ConstU128<3>;
-	type Asset0 = StellarEurAsset;
-	type Asset1 = StellarUsdcAsset;
+	type DefaultSwapFee = AmmSwapFee;
+	type AdminOrigin = frame_system::EnsureRoot<AccountId>;
+	type MaxObservations = ConstU32<64>;
+	type MaxPathLength = ConstU32<4>;
+	type MaxFlashDataLen = ConstU32<256>;
+	type MinRampTime = ConstU64<{ 24 * 60 * 60 * 1000 }>;
+	type MaxAssetsPerPool = ConstU32<4>;
 }
 
 parameter_types! {
@@ -339,6 +333,15 @@ pub enum CurrencyId {
 	StellarNative,
 	AlphaNum4 { code: Bytes4, issuer: AssetIssuer },
 	AlphaNum12 { code: Bytes12, issuer: AssetIssuer },
+	/// The LP token of an AMM pool, see `CurrencyId::lp_token`.
+	LpToken([u8; 32]),
+}
+
+impl CurrencyId {
+	/// The LP token of a pool or basket, identified by the hash of `token`.
+	pub fn lp_token(token: &LpTokenOf<Runtime>) -> Self {
+		CurrencyId::LpToken(<BlakeTwo256 as sp_runtime::traits::Hash>::hash_of(token).0)
+	}
 }
 
 impl Default for CurrencyId {
@@ -388,6 +391,7 @@ impl fmt::Debug for CurrencyId {
 					str::from_utf8(issuer).unwrap()
 				)
 			},
+			Self::LpToken(id) => write!(f, "LP {}", sp_core::hexdisplay::HexDisplay::from(id)),
 		}
 	}
 }
@@ -479,11 +483,46 @@ impl AmmExtension<AccountId, CurrencyId, Balance, u64> for Extension {
 	}
 }
 
+/// Keeps the LP tokens of the AMM as `CurrencyId::LpToken` in `orml_tokens`,
+/// so that they can be used like any other currency.
+pub struct LpTokensAsCurrencies;
+
+impl LpTokens<AccountId, LpTokenOf<Runtime>, Balance> for LpTokensAsCurrencies {
+	fn balance(token: &LpTokenOf<Runtime>, who: &AccountId) -> Balance {
+		<Tokens as MultiCurrency<AccountId>>::free_balance(CurrencyId::lp_token(token), who)
+	}
+
+	fn mint(
+		token: &LpTokenOf<Runtime>,
+		to: &AccountId,
+		amount: Balance,
+	) -> sp_runtime::DispatchResult {
+		<Tokens as MultiCurrency<AccountId>>::deposit(CurrencyId::lp_token(token), to, amount)
+	}
+
+	fn burn(
+		token: &LpTokenOf<Runtime>,
+		from: &AccountId,
+		amount: Balance,
+	) -> sp_runtime::DispatchResult {
+		<Tokens as MultiCurrency<AccountId>>::withdraw(CurrencyId::lp_token(token), from, amount)
+	}
+
+	fn transfer(
+		token: &LpTokenOf<Runtime>,
+		from: &AccountId,
+		to: &AccountId,
+		amount: Balance,
+	) -> sp_runtime::DispatchResult {
+		<Tokens as MultiCurrency<AccountId>>::transfer(CurrencyId::lp_token(token), from, to, amount)
+	}
+}
+
 pub struct BalanceChainExtension;
-use sp_runtime::DispatchError;
+use sp_runtime::{DispatchError, FixedU128};
 
 use core::convert::TryFrom;
-use frame_support::traits::{ConstU128, Contains};
+use frame_support::traits::{ConstU128, ConstU64, Contains};
 use orml_currencies::BasicCurrencyAdapter;
 use orml_traits::{parameter_type_with_key, MultiCurrency};
 use sp_std::str;
@@ -622,7 +661,7 @@ construct_runtime!(
 		Contracts: pallet_contracts,
 		Currencies: orml_currencies,
 		Tokens: orml_tokens exclude_parts { Call },
-		AmmEURUSDC: pallet_pendulum_amm
+		Amm: pallet_pendulum_amm
 	}
 );
 
@@ -765,7 +804,7 @@ impl_runtime_apis! {
 			list_benchmark!(list, extra, frame_system, SystemBench::<Runtime>);
 			list_benchmark!(list, extra, pallet_balances, Balances);
 			list_benchmark!(list, extra, pallet_timestamp, Timestamp);
-			list_benchmark!(list, extra, pallet_pendulum_amm, AmmEURUSDC);
+			list_benchmark!(list, extra, pallet_pendulum_amm, Amm);
 
 			let storage_info = AllPalletsWithSystem::storage_info();
 
@@ -803,7 +842,7 @@ impl_runtime_apis! {
 			add_benchmark!(params, batches, frame_system, SystemBench::<Runtime>);
 			add_benchmark!(params, batches, pallet_balances, Balances);
 			add_benchmark!(params, batches, pallet_timestamp, Timestamp);
-			add_benchmark!(params, batches, pallet_pendulum_amm, AmmEURUSDC);
+			add_benchmark!(params, batches, pallet_pendulum_amm, Amm);
 
 			Ok(batches)
 		}
@@ -853,6 +892,60 @@ impl_runtime_apis! {
 		}
 	}
 
+	impl pallet_pendulum_amm_rpc_runtime_api::AmmApi<Block, AccountId, CurrencyId, Balance, Moment>
+		for Runtime
+	{
+		fn get_reserves(pool: (CurrencyId, CurrencyId)) -> Option<(Balance, Balance, Moment)> {
+			Amm::get_reserves(pool)
+		}
+
+		fn quote_exact_in(
+			pool: (CurrencyId, CurrencyId),
+			asset_in: CurrencyId,
+			amount_in: Balance,
+		) -> Result<Balance, DispatchError> {
+			Amm::quote_exact_in(pool, asset_in, amount_in)
+		}
+
+		fn quote_exact_out(
+			pool: (CurrencyId, CurrencyId),
+			asset_in: CurrencyId,
+			amount_out: Balance,
+		) -> Result<Balance, DispatchError> {
+			Amm::quote_exact_out(pool, asset_in, amount_out)
+		}
+
+		fn spot_price(
+			pool: (CurrencyId, CurrencyId),
+			asset_in: CurrencyId,
+		) -> Result<FixedU128, DispatchError> {
+			Amm::spot_price(pool, asset_in)
+		}
+
+		fn lp_balance(pool: (CurrencyId, CurrencyId), who: AccountId) -> Balance {
+			Amm::lp_balance(pool, who)
+		}
+
+		fn total_supply(pool: (CurrencyId, CurrencyId)) -> Balance {
+			Amm::total_supply(pool)
+		}
+
+		fn price_cumulative(pool: (CurrencyId, CurrencyId)) -> (FixedU128, FixedU128) {
+			Amm::price_cumulative(pool)
+		}
+
+		fn consult(
+			pool: (CurrencyId, CurrencyId),
+			window: Moment,
+		) -> Result<(FixedU128, FixedU128), DispatchError> {
+			Amm::consult(pool, window)
+		}
+
+		fn pools() -> Vec<((CurrencyId, CurrencyId), Balance, Balance, Permill)> {
+			Amm::pools()
+		}
+	}
+
 	#[cfg(feature = "try-runtime")]
 	impl frame_try_runtime::TryRuntime<Block> for Runtime {
 		fn on_runtime_upgrade() -> (Weight, Weight) {